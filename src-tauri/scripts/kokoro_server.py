@@ -2,6 +2,16 @@
 """
 Persistent Kokoro TTS Server
 Keeps the model warm for fast sentence generation (~0.3s per sentence)
+
+Endpoints:
+  POST /tts         -> writes a WAV file, returns {"file": path}
+  POST /tts/stream  -> chunked raw PCM (24kHz mono 16-bit LE) as it is generated
+  GET  /health
+
+Both POST endpoints take {"text", "voice", "speed", "lang_code"}. lang_code is
+Kokoro's language ('a' American English, 'b' British English, 'e' Spanish,
+'f' French, 'h' Hindi, 'i' Italian, 'j' Japanese, 'p' Portuguese, 'z' Mandarin)
+and defaults to 'a'.
 """
 
 import sys
@@ -16,14 +26,19 @@ import numpy as np
 import warnings
 warnings.filterwarnings("ignore")
 
-# Global pipeline (loaded once)
-pipeline = None
+# One pipeline per language, all sharing the model loaded by the first one
+pipelines = {}
 
-def init_pipeline():
-    global pipeline
+def init_pipeline(lang_code='a'):
+    pipeline = pipelines.get(lang_code)
     if pipeline is None:
         from kokoro import KPipeline
-        pipeline = KPipeline(lang_code='a')
+        shared = next(iter(pipelines.values()), None)
+        if shared is not None:
+            pipeline = KPipeline(lang_code=lang_code, model=shared.model)
+        else:
+            pipeline = KPipeline(lang_code=lang_code)
+        pipelines[lang_code] = pipeline
     return pipeline
 
 def trim_silence(audio, sample_rate=24000, threshold_db=-40, min_silence_ms=100):
@@ -59,12 +74,92 @@ def trim_silence(audio, sample_rate=24000, threshold_db=-40, min_silence_ms=100)
     
     return audio[:end_idx]
 
+def to_pcm16(audio):
+    """Convert float audio in [-1, 1] to 16-bit little-endian PCM bytes"""
+    audio = np.clip(np.asarray(audio, dtype=np.float32), -1.0, 1.0)
+    return (audio * 32767).astype('<i2').tobytes()
+
 class TTSHandler(BaseHTTPRequestHandler):
+    # HTTP/1.1 for chunked streaming and keep-alive
+    protocol_version = 'HTTP/1.1'
+    
     def log_message(self, format, *args):
         pass  # Suppress logging
     
+    def send_json(self, payload):
+        body = json.dumps(payload).encode()
+        self.send_response(200)
+        self.send_header('Content-Type', 'application/json')
+        self.send_header('Content-Length', str(len(body)))
+        self.end_headers()
+        self.wfile.write(body)
+    
+    def write_chunk(self, data):
+        self.wfile.write(f'{len(data):x}\r\n'.encode() + data + b'\r\n')
+        self.wfile.flush()
+    
+    def stream_tts(self, text, voice, speed, lang_code):
+        """
+        Stream raw PCM (24kHz mono, 16-bit LE) with chunked transfer encoding.
+        Each chunk from the Kokoro generator is sent as soon as it is ready,
+        so the client can start playback before the whole text is synthesized.
+        """
+        pipe = init_pipeline(lang_code)
+        generator = pipe(text, voice=voice, speed=speed)
+        
+        self.send_response(200)
+        self.send_header('Content-Type', 'audio/pcm')
+        self.send_header('X-Sample-Rate', '24000')
+        self.send_header('Transfer-Encoding', 'chunked')
+        self.end_headers()
+        
+        try:
+            for gs, ps, audio in generator:
+                if audio is None or len(audio) == 0:
+                    continue
+                # Keep a short natural pause between segments
+                audio = trim_silence(np.asarray(audio), sample_rate=24000,
+                                     threshold_db=-40, min_silence_ms=50)
+                self.write_chunk(to_pcm16(audio))
+        except (BrokenPipeError, ConnectionResetError):
+            # Client stopped playback
+            self.close_connection = True
+            return
+        except Exception:
+            # Headers are already sent - end the stream so the client stops waiting
+            self.close_connection = True
+        
+        self.wfile.write(b'0\r\n\r\n')
+        self.wfile.flush()
+    
     def do_POST(self):
-        if self.path == '/tts':
+        if self.path == '/tts/stream':
+            content_length = int(self.headers['Content-Length'])
+            body = self.rfile.read(content_length)
+            
+            try:
+                data = json.loads(body)
+                text = data.get('text', '')
+                voice = data.get('voice', 'af_heart')
+                speed = data.get('speed', 1.2)
+                lang_code = data.get('lang_code', 'a')
+            except Exception as e:
+                self.send_error(400, str(e))
+                return
+            
+            if not text:
+                self.send_error(400, 'Missing text')
+                return
+            
+            try:
+                init_pipeline(lang_code)
+            except Exception as e:
+                self.send_error(400, f'Unsupported lang_code {lang_code!r}: {e}')
+                return
+            
+            self.stream_tts(text, voice, speed, lang_code)
+        
+        elif self.path == '/tts':
             content_length = int(self.headers['Content-Length'])
             body = self.rfile.read(content_length)
             
@@ -73,13 +168,14 @@ class TTSHandler(BaseHTTPRequestHandler):
                 text = data.get('text', '')
                 voice = data.get('voice', 'af_heart')
                 speed = data.get('speed', 1.2)
+                lang_code = data.get('lang_code', 'a')
                 
                 if not text:
                     self.send_error(400, 'Missing text')
                     return
                 
                 # Generate audio
-                pipe = init_pipeline()
+                pipe = init_pipeline(lang_code)
                 generator = pipe(text, voice=voice, speed=speed)
                 
                 all_audio = []
@@ -100,29 +196,24 @@ class TTSHandler(BaseHTTPRequestHandler):
                 sf.write(temp_file, audio_array, 24000)
                 
                 # Return file path
-                self.send_response(200)
-                self.send_header('Content-Type', 'application/json')
-                self.end_headers()
-                self.wfile.write(json.dumps({'file': temp_file}).encode())
+                self.send_json({'file': temp_file})
                 
             except Exception as e:
                 self.send_error(500, str(e))
         
         elif self.path == '/health':
-            self.send_response(200)
-            self.send_header('Content-Type', 'application/json')
-            self.end_headers()
-            self.wfile.write(json.dumps({'status': 'ok'}).encode())
+            self.send_json({'status': 'ok'})
         
         else:
             self.send_error(404, 'Not found')
     
     def do_GET(self):
         if self.path == '/health':
-            self.send_response(200)
-            self.send_header('Content-Type', 'application/json')
-            self.end_headers()
-            self.wfile.write(json.dumps({'status': 'ok', 'model_loaded': pipeline is not None}).encode())
+            self.send_json({
+                'status': 'ok',
+                'model_loaded': bool(pipelines),
+                'languages': sorted(pipelines),
+            })
         else:
             self.send_error(404, 'Not found')
 
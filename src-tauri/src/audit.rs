@@ -0,0 +1,292 @@
+//! Audit log of what was said and what it let OpenCode do
+//!
+//! A misheard "yes" can run a shell command, so everything on the way there
+//! is written to an append-only JSON Lines file in the data directory: each
+//! transcription and where it went, each tool call OpenCode starts, each
+//! permission request with the policy rule that matched, the questions read
+//! out about it, and the decision (and whether it came by voice, hotkey,
+//! timeout or policy). One record per line:
+//!
+//! ```json
+//! {"atMs":1760000000000,"sessionId":"ses_1","event":"decision","permissionId":"per_1","approved":true,"via":"voice","rule":null}
+//! ```
+//!
+//! Once the file passes `MAX_FILE_BYTES` it is renamed to `audit.1.jsonl`
+//! (shifting older files up) and a new one is started; the oldest beyond
+//! `KEPT_FILES` is deleted. Records are never changed after they're written.
+//!
+//! Like history, auditing never gets in the way of the voice loop: a failure
+//! to write is logged and the record dropped.
+
+use crate::permissions::{AnswerSource, Severity};
+use crate::policy::Action;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const FILE_STEM: &str = "audit";
+const MAX_FILE_BYTES: u64 = 5 * 1024 * 1024;
+// The current file plus rotated ones
+const KEPT_FILES: usize = 5;
+// Longer strings in tool inputs (whole files being written) are cut short
+const MAX_INPUT_CHARS: usize = 500;
+// Tool calls remembered so each is logged once, however often it's updated
+const SEEN_CALLS: usize = 256;
+
+// Held while writing, so rotation and appends don't interleave
+static WRITE: Mutex<()> = Mutex::new(());
+
+static SEEN_CALL_IDS: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// What became of a transcription
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Handling {
+    /// Sent to OpenCode as a prompt
+    Prompt,
+    /// Carried out as a voice command
+    Command,
+    /// Taken as the answer to a permission request
+    Answer,
+    /// Heard while a permission request was pending, but not an answer
+    Unclear,
+}
+
+/// Something worth accounting for
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    #[serde(rename_all = "camelCase")]
+    Transcription {
+        text: String,
+        /// push-to-talk, continuous, wake word, superwhisper, ...
+        source: String,
+        handled: Handling,
+    },
+    #[serde(rename_all = "camelCase")]
+    ToolCall {
+        call_id: String,
+        tool: String,
+        title: Option<String>,
+        input: serde_json::Value,
+    },
+    #[serde(rename_all = "camelCase")]
+    Permission {
+        permission_id: String,
+        tool: String,
+        description: String,
+        severity: Severity,
+        action: Action,
+        rule: Option<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Prompt { permission_id: String, text: String },
+    #[serde(rename_all = "camelCase")]
+    Decision {
+        permission_id: String,
+        approved: bool,
+        via: AnswerSource,
+        rule: Option<String>,
+    },
+}
+
+impl Event {
+    fn name(&self) -> &'static str {
+        match self {
+            Event::Transcription { .. } => "transcription",
+            Event::ToolCall { .. } => "tool_call",
+            Event::Permission { .. } => "permission",
+            Event::Prompt { .. } => "prompt",
+            Event::Decision { .. } => "decision",
+        }
+    }
+}
+
+/// One line of the log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Record {
+    pub at_ms: i64,
+    pub session_id: Option<String>,
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+/// Which records to return; every field narrows the result
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Query {
+    /// Event names: "transcription", "tool_call", "permission", "prompt", "decision"
+    pub events: Vec<String>,
+    pub session_id: Option<String>,
+    pub since_ms: Option<i64>,
+    pub until_ms: Option<i64>,
+    /// Case-insensitive text anywhere in the record
+    pub text: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// Where the current log file lives
+pub fn log_path() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("opencode-talk").join(format!("{}.jsonl", FILE_STEM)))
+}
+
+// `audit.jsonl` for 0, `audit.N.jsonl` for rotated files
+fn file_path(current: &Path, index: usize) -> PathBuf {
+    if index == 0 {
+        current.to_path_buf()
+    } else {
+        current.with_file_name(format!("{}.{}.jsonl", FILE_STEM, index))
+    }
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+/// Append a record for a session
+pub fn record(session_id: Option<&str>, event: Event) {
+    let record = Record {
+        at_ms: now_ms(),
+        session_id: session_id.map(str::to_string),
+        event,
+    };
+    if let Err(e) = append(&record) {
+        eprintln!("[AUDIT] Couldn't record {}: {}", record.event.name(), e);
+    }
+}
+
+/// Record a tool call the first time it's seen running (OpenCode updates the
+/// same call as it progresses)
+pub fn record_tool_call(session_id: &str, call_id: &str, tool: &str, title: Option<&str>, input: &serde_json::Value) {
+    {
+        let mut seen = SEEN_CALL_IDS.lock().unwrap();
+        if seen.iter().any(|id| id == call_id) {
+            return;
+        }
+        seen.push_back(call_id.to_string());
+        if seen.len() > SEEN_CALLS {
+            seen.pop_front();
+        }
+    }
+    record(
+        Some(session_id),
+        Event::ToolCall {
+            call_id: call_id.to_string(),
+            tool: tool.to_string(),
+            title: title.map(str::to_string),
+            input: shorten(input),
+        },
+    );
+}
+
+// The input with long strings cut, so a file being written doesn't end up in the log
+fn shorten(value: &serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::String(s) if s.chars().count() > MAX_INPUT_CHARS => {
+            let kept: String = s.chars().take(MAX_INPUT_CHARS).collect();
+            serde_json::Value::String(format!("{}… ({} characters)", kept, s.chars().count()))
+        }
+        serde_json::Value::Array(items) => items.iter().map(shorten).collect(),
+        serde_json::Value::Object(fields) => fields
+            .iter()
+            .map(|(key, value)| (key.clone(), shorten(value)))
+            .collect::<serde_json::Map<_, _>>()
+            .into(),
+        other => other.clone(),
+    }
+}
+
+fn append(record: &Record) -> Result<(), String> {
+    let path = log_path().ok_or("No data directory for the audit log")?;
+    let mut line = serde_json::to_string(record).map_err(|e| e.to_string())?;
+    line.push('\n');
+
+    let _write = WRITE.lock().unwrap();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Can't create {}: {}", dir.display(), e))?;
+    }
+    let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+    if size > 0 && size + line.len() as u64 > MAX_FILE_BYTES {
+        rotate(&path)?;
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Can't open {}: {}", path.display(), e))?;
+    file.write_all(line.as_bytes())
+        .map_err(|e| format!("Can't write {}: {}", path.display(), e))
+}
+
+// Shift audit.N.jsonl to audit.N+1.jsonl, dropping the oldest
+fn rotate(current: &Path) -> Result<(), String> {
+    let oldest = file_path(current, KEPT_FILES - 1);
+    if oldest.exists() {
+        std::fs::remove_file(&oldest).map_err(|e| format!("Can't remove {}: {}", oldest.display(), e))?;
+    }
+    for index in (0..KEPT_FILES - 1).rev() {
+        let from = file_path(current, index);
+        if from.exists() {
+            let to = file_path(current, index + 1);
+            std::fs::rename(&from, &to).map_err(|e| format!("Can't rotate {}: {}", from.display(), e))?;
+        }
+    }
+    eprintln!("[AUDIT] Rotated {}", current.display());
+    Ok(())
+}
+
+/// Records matching a query, newest first
+pub fn query(query: &Query) -> Result<Vec<Record>, String> {
+    let path = log_path().ok_or("No data directory for the audit log")?;
+    let limit = query.limit.unwrap_or(500);
+    let text = query.text.as_deref().map(str::to_lowercase);
+    let events: HashSet<&str> = query.events.iter().map(String::as_str).collect();
+
+    let mut found = Vec::new();
+    // Newest file first, and within each file the last lines first
+    for index in 0..KEPT_FILES {
+        let file = match std::fs::File::open(file_path(&path, index)) {
+            Ok(file) => file,
+            Err(_) => continue,
+        };
+        let lines: Vec<String> = BufReader::new(file).lines().map_while(Result::ok).collect();
+        for line in lines.iter().rev() {
+            if let Some(text) = &text {
+                if !line.to_lowercase().contains(text.as_str()) {
+                    continue;
+                }
+            }
+            // Lines from a crash mid-write, or a future format, are skipped
+            let Ok(record) = serde_json::from_str::<Record>(line) else {
+                continue;
+            };
+            if query.until_ms.is_some_and(|until| record.at_ms > until)
+                || (!events.is_empty() && !events.contains(record.event.name()))
+                || query
+                    .session_id
+                    .as_ref()
+                    .is_some_and(|session| record.session_id.as_ref() != Some(session))
+            {
+                continue;
+            }
+            // Older than `since`: so is everything after it
+            if query.since_ms.is_some_and(|since| record.at_ms < since) {
+                return Ok(found);
+            }
+            found.push(record);
+            if found.len() >= limit {
+                return Ok(found);
+            }
+        }
+    }
+    Ok(found)
+}
@@ -0,0 +1,128 @@
+//! Stand-in OpenAI-compatible speech server for testing the "openai" TTS engine
+//!
+//! Answers POST /v1/audio/speech with a tone instead of real speech. The PCM is
+//! sent in small chunks with a delay between them, like a real streaming server,
+//! so you can hear whether playback starts on the first chunk.
+//!
+//! Run with: cargo run --bin openai_tts_standin -- [port]
+//! Then set the OpenAI base URL in Settings to http://127.0.0.1:<port>
+
+use std::env;
+use std::io::{self, Read};
+use std::time::{Duration, Instant};
+use tiny_http::{Header, Method, Response, Server};
+
+const SAMPLE_RATE: u32 = 24000;
+const CHUNK_MS: u32 = 100;
+
+/// Produces a sine tone as 16-bit PCM, one chunk at a time with a delay
+/// between chunks to simulate synthesis time
+struct ToneReader {
+    frequency: f32,
+    total_samples: u32,
+    position: u32,
+    pending: Vec<u8>,
+}
+
+impl ToneReader {
+    fn new(frequency: f32, duration: Duration) -> Self {
+        Self {
+            frequency,
+            total_samples: (duration.as_secs_f32() * SAMPLE_RATE as f32) as u32,
+            position: 0,
+            pending: Vec::new(),
+        }
+    }
+
+    fn next_chunk(&mut self) -> Vec<u8> {
+        let chunk_samples = SAMPLE_RATE * CHUNK_MS / 1000;
+        let end = (self.position + chunk_samples).min(self.total_samples);
+        let mut bytes = Vec::with_capacity(((end - self.position) * 2) as usize);
+
+        for n in self.position..end {
+            let t = n as f32 / SAMPLE_RATE as f32;
+            let sample = (t * self.frequency * std::f32::consts::TAU).sin() * 0.3;
+            bytes.extend_from_slice(&((sample * i16::MAX as f32) as i16).to_le_bytes());
+        }
+        self.position = end;
+
+        // Pretend synthesis takes half of real time
+        std::thread::sleep(Duration::from_millis((CHUNK_MS / 2) as u64));
+        bytes
+    }
+}
+
+impl Read for ToneReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pending.is_empty() {
+            if self.position >= self.total_samples {
+                return Ok(0);
+            }
+            self.pending = self.next_chunk();
+        }
+
+        let n = buf.len().min(self.pending.len());
+        buf[..n].copy_from_slice(&self.pending[..n]);
+        self.pending.drain(..n);
+        Ok(n)
+    }
+}
+
+fn main() {
+    let port: u16 = env::args()
+        .nth(1)
+        .and_then(|p| p.parse().ok())
+        .unwrap_or(8880);
+
+    let server = Server::http(format!("127.0.0.1:{}", port))
+        .expect("Failed to start stand-in server");
+    println!("Stand-in speech server on http://127.0.0.1:{}", port);
+    println!("POST /v1/audio/speech returns a tone (~60ms per character) as 24kHz PCM");
+
+    for mut request in server.incoming_requests() {
+        if request.method() != &Method::Post || !request.url().starts_with("/v1/audio/speech") {
+            let _ = request.respond(Response::from_string("Not found").with_status_code(404));
+            continue;
+        }
+
+        let mut body = String::new();
+        if request.as_reader().read_to_string(&mut body).is_err() {
+            let _ = request.respond(Response::from_string("Bad body").with_status_code(400));
+            continue;
+        }
+
+        let payload: serde_json::Value = match serde_json::from_str(&body) {
+            Ok(v) => v,
+            Err(e) => {
+                let _ = request.respond(Response::from_string(format!("Bad JSON: {}", e)).with_status_code(400));
+                continue;
+            }
+        };
+
+        let input = payload["input"].as_str().unwrap_or_default();
+        let speed = payload["speed"].as_f64().unwrap_or(1.0).max(0.25) as f32;
+        println!(
+            "model={} voice={} speed={} format={} input={:?}",
+            payload["model"], payload["voice"], speed, payload["response_format"], input
+        );
+
+        if payload["response_format"].as_str() != Some("pcm") {
+            let _ = request.respond(
+                Response::from_string("Only response_format=pcm is supported").with_status_code(400),
+            );
+            continue;
+        }
+
+        let duration = Duration::from_millis((input.chars().count() as f32 * 60.0 / speed) as u64);
+        let started = Instant::now();
+        let response = Response::new(
+            200.into(),
+            vec![Header::from_bytes(&b"Content-Type"[..], &b"audio/pcm"[..]).unwrap()],
+            ToneReader::new(440.0, duration),
+            None, // unknown length - sent chunked
+            None,
+        );
+        let _ = request.respond(response);
+        println!("  streamed {:?} of audio in {:?}", duration, started.elapsed());
+    }
+}
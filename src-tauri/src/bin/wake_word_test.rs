@@ -0,0 +1,133 @@
+//! Test harness for the wake word detector
+//!
+//! Runs recorded WAV files through the same VAD + local whisper + phrase
+//! matching path the app uses and reports every detection. Files in a
+//! directory named `negative` (or with "negative" in their name) are expected
+//! not to trigger; all others are expected to. Exits non-zero on any miss or
+//! false alarm, so a folder of recordings can be used as a regression check.
+//!
+//! Run with: cargo run --bin wake_word_test -- [--phrase "hey opencode"]
+//!     [--sensitivity 0.5] [--model base.en] [--language en] <wav or dir>...
+
+use opencode_talk_lib::wake_word::{Spotter, WakeWordConfig};
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+fn usage() -> ExitCode {
+    eprintln!(
+        "Usage: wake_word_test [--phrase TEXT] [--sensitivity 0-1] [--model NAME|PATH] [--language CODE] <wav or dir>..."
+    );
+    ExitCode::FAILURE
+}
+
+// Same lookup as the app: a path, or a name in ~/.local/share/whisper-models
+fn model_path(model: &str) -> PathBuf {
+    if model.contains('/') || model.ends_with(".bin") {
+        return PathBuf::from(model);
+    }
+    dirs::data_local_dir()
+        .unwrap_or_default()
+        .join("whisper-models")
+        .join(format!("ggml-{}.bin", model))
+}
+
+fn collect_wavs(path: &Path, files: &mut Vec<PathBuf>) {
+    if path.is_dir() {
+        let mut entries: Vec<PathBuf> = match std::fs::read_dir(path) {
+            Ok(entries) => entries.filter_map(|e| e.ok().map(|e| e.path())).collect(),
+            Err(e) => {
+                eprintln!("Failed to read {}: {}", path.display(), e);
+                return;
+            }
+        };
+        entries.sort();
+        for entry in entries {
+            collect_wavs(&entry, files);
+        }
+    } else if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("wav")) {
+        files.push(path.to_path_buf());
+    }
+}
+
+fn expects_wake_word(path: &Path) -> bool {
+    !path
+        .components()
+        .any(|c| c.as_os_str().to_string_lossy().to_lowercase().contains("negative"))
+}
+
+fn main() -> ExitCode {
+    let mut config = WakeWordConfig::default();
+    let mut model = "base.en".to_string();
+    let mut language = "en".to_string();
+    let mut inputs = Vec::new();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--phrase" => config.phrase = args.next().unwrap_or_default(),
+            "--sensitivity" => match args.next().and_then(|s| s.parse().ok()) {
+                Some(sensitivity) => config.sensitivity = sensitivity,
+                None => return usage(),
+            },
+            "--model" => model = args.next().unwrap_or_default(),
+            "--language" => language = args.next().unwrap_or_default(),
+            "-h" | "--help" => return usage(),
+            _ => inputs.push(PathBuf::from(arg)),
+        }
+    }
+    if inputs.is_empty() {
+        return usage();
+    }
+
+    let model = model_path(&model);
+    if !model.exists() {
+        eprintln!("Whisper model not found at {}", model.display());
+        return ExitCode::FAILURE;
+    }
+
+    let mut files = Vec::new();
+    for input in &inputs {
+        collect_wavs(input, &mut files);
+    }
+
+    println!(
+        "Phrase {:?}, sensitivity {:.2}, {} file(s)\n",
+        config.phrase,
+        config.sensitivity,
+        files.len()
+    );
+    let spotter = Spotter::new(config, model, &language);
+
+    let mut failures = 0;
+    for file in &files {
+        let expected = expects_wake_word(file);
+        let detections = match spotter.scan_wav(file) {
+            Ok(detections) => detections,
+            Err(e) => {
+                println!("ERROR {}: {}", file.display(), e);
+                failures += 1;
+                continue;
+            }
+        };
+
+        let passed = detections.is_empty() != expected;
+        if !passed {
+            failures += 1;
+        }
+        println!("{} {}", if passed { "ok   " } else { "FAIL " }, file.display());
+        for detection in &detections {
+            println!(
+                "      {:.1}s-{:.1}s score {:.2} {:?} command {:?}",
+                detection.start_secs, detection.end_secs, detection.score, detection.transcript, detection.command
+            );
+        }
+    }
+
+    println!("\n{} of {} passed", files.len() - failures, files.len());
+    if failures == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,72 @@
+//! User-editable config files that are re-read when they change
+//!
+//! The lexicon, the voice command grammar and the permission policy are all
+//! TOML files (or JSON with the same shape) that are compiled into something
+//! quicker to use. A `Cache` keeps the compiled value along with the
+//! modification times of the files it was built from, and rebuilds it when
+//! one of them appears, disappears or changes.
+
+use serde::de::DeserializeOwned;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+type Stamps = Vec<(PathBuf, Option<SystemTime>)>;
+
+/// A value compiled from config files, plus whatever else it depends on (`K`)
+pub struct Cache<T, K = ()> {
+    cached: Mutex<Option<(Stamps, K, Arc<T>)>>,
+}
+
+impl<T, K: PartialEq> Cache<T, K> {
+    pub const fn new() -> Self {
+        Self {
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// The value for `candidates` (in priority order) and `key`, rebuilt if
+    /// any of the files or the key changed. `build` gets the files that exist.
+    pub fn get(&self, candidates: Vec<PathBuf>, key: K, build: impl FnOnce(&[&Path], &K) -> T) -> Arc<T> {
+        let stamps: Stamps = candidates
+            .into_iter()
+            .map(|path| {
+                let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+                (path, modified)
+            })
+            .collect();
+
+        let mut cached = self.cached.lock().unwrap();
+        if let Some((cached_stamps, cached_key, value)) = cached.as_ref() {
+            if *cached_stamps == stamps && *cached_key == key {
+                return value.clone();
+            }
+        }
+
+        let existing: Vec<&Path> = stamps
+            .iter()
+            .filter(|(_, modified)| modified.is_some())
+            .map(|(path, _)| path.as_path())
+            .collect();
+        let value = Arc::new(build(&existing, &key));
+
+        *cached = Some((stamps, key, value.clone()));
+        value
+    }
+}
+
+/// The files called `names` in `dir`, in order
+pub fn candidates(dir: &Path, names: &[&str]) -> Vec<PathBuf> {
+    names.iter().map(|name| dir.join(name)).collect()
+}
+
+/// Read a config file, as JSON if it's named `.json` and TOML otherwise
+pub fn read<T: DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+
+    if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str(&contents).map_err(|e| e.to_string())
+    } else {
+        toml::from_str(&contents).map_err(|e| e.to_string())
+    }
+}
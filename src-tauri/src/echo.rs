@@ -0,0 +1,149 @@
+//! Echo suppression for listening through speakers
+//!
+//! Everything the audio thread plays passes through a `ReferenceTap`, which
+//! keeps a short log of the playback level as it goes out to the device. When
+//! a microphone frame is checked, the loudest playback in the preceding few
+//! hundred milliseconds (covering output latency, the trip through the room
+//! and reverb) times the learned speaker-to-mic coupling is the echo to
+//! expect. The frame only counts as speech when it is clearly louder than
+//! that, so the assistant's voice coming back through the mic is ignored while
+//! the user can still talk over it.
+//!
+//! This gates on level rather than subtracting the echo waveform, which would
+//! need the output and input clocks to stay sample-aligned.
+
+use once_cell::sync::Lazy;
+use rodio::Source;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Playback level is logged in blocks of this length
+const BLOCK_MS: u32 = 10;
+
+// How long the log reaches back
+const HISTORY: Duration = Duration::from_secs(2);
+
+// Playback this far before a mic frame can still be in it
+const ECHO_WINDOW: Duration = Duration::from_millis(400);
+
+// Speech has to be this much louder than the expected echo (about 6dB)
+const ECHO_MARGIN: f32 = 2.0;
+
+// Quieter playback can't produce an echo worth gating
+const MIN_REFERENCE_RMS: f32 = 0.005;
+
+// Mic level / playback level. Starts high so the first sentences are gated
+// while the real coupling is learned, then follows the echo actually heard:
+// up quickly to cover the loudest echo, down slowly so the quiet gaps between
+// syllables don't drag it under the peaks.
+const INITIAL_COUPLING: f32 = 2.0;
+const MIN_COUPLING: f32 = 0.02;
+const MAX_COUPLING: f32 = 4.0;
+const COUPLING_RISE: f32 = 0.1;
+const COUPLING_FALL: f32 = 0.01;
+
+// Recent playback levels, newest last
+static REFERENCE: Lazy<Mutex<VecDeque<(Instant, f32)>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
+
+// Learned coupling, kept for as long as the app runs (the room doesn't change
+// between listening sessions)
+static COUPLING: Mutex<f32> = Mutex::new(INITIAL_COUPLING);
+
+/// Loudest playback between two instants, or None if nothing was playing
+pub fn reference_level(from: Instant, to: Instant) -> Option<f32> {
+    REFERENCE
+        .lock()
+        .unwrap()
+        .iter()
+        .rev()
+        .skip_while(|(at, _)| *at > to)
+        .take_while(|(at, _)| *at >= from)
+        .map(|(_, rms)| *rms)
+        .reduce(f32::max)
+}
+
+fn log_reference(rms: f32) {
+    let now = Instant::now();
+    let mut reference = REFERENCE.lock().unwrap();
+    while reference.front().is_some_and(|(at, _)| now.duration_since(*at) > HISTORY) {
+        reference.pop_front();
+    }
+    reference.push_back((now, rms));
+}
+
+/// Passes a source through unchanged while logging its level as a reference
+/// for echo suppression
+pub struct ReferenceTap<S> {
+    source: S,
+    block_len: usize,
+    sum_squares: f32,
+    counted: usize,
+}
+
+impl<S: Source<Item = i16>> ReferenceTap<S> {
+    pub fn new(source: S) -> Self {
+        let samples_per_sec = source.sample_rate() * source.channels().max(1) as u32;
+        Self {
+            source,
+            block_len: (samples_per_sec * BLOCK_MS / 1000).max(1) as usize,
+            sum_squares: 0.0,
+            counted: 0,
+        }
+    }
+}
+
+impl<S: Source<Item = i16>> Iterator for ReferenceTap<S> {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        let sample = self.source.next()?;
+        let value = sample as f32 / 32768.0;
+        self.sum_squares += value * value;
+        self.counted += 1;
+        if self.counted >= self.block_len {
+            log_reference((self.sum_squares / self.counted as f32).sqrt());
+            self.sum_squares = 0.0;
+            self.counted = 0;
+        }
+        Some(sample)
+    }
+}
+
+impl<S: Source<Item = i16>> Source for ReferenceTap<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
+}
+
+/// Whether a microphone frame captured between `start` and `end` with level
+/// `rms` is explained by our own playback (and so isn't speech)
+pub fn is_echo(rms: f32, start: Instant, end: Instant) -> bool {
+    let from = start.checked_sub(ECHO_WINDOW).unwrap_or(start);
+    let Some(reference) = reference_level(from, end).filter(|r| *r > MIN_REFERENCE_RMS) else {
+        return false;
+    };
+
+    let mut coupling = COUPLING.lock().unwrap();
+    let is_echo = rms <= reference * *coupling * ECHO_MARGIN;
+
+    // Learn from frames that are echo alone, not the user talking over it
+    if is_echo {
+        let ratio = rms / reference;
+        let rate = if ratio < *coupling { COUPLING_FALL } else { COUPLING_RISE };
+        *coupling = (*coupling + (ratio - *coupling) * rate).clamp(MIN_COUPLING, MAX_COUPLING);
+    }
+    is_echo
+}
@@ -0,0 +1,432 @@
+//! Conversation history, kept in a local SQLite database
+//!
+//! Everything said and done by voice is recorded per OpenCode session: what
+//! the user said (with the length of the audio and how long transcription
+//! took), the assistant's answers and what was actually spoken of them, other
+//! spoken messages, and confirmation decisions. The frontend's conversation
+//! store only lives as long as the window; this is what survives a restart.
+//!
+//! Recording never gets in the way of the voice loop: callers log a failure
+//! and carry on.
+
+use once_cell::sync::Lazy;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Bumped when the tables change; older databases are migrated step by step
+const SCHEMA_VERSION: i32 = 1;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS sessions (
+        id TEXT PRIMARY KEY,
+        title TEXT
+    );
+    CREATE TABLE IF NOT EXISTS entries (
+        id INTEGER PRIMARY KEY,
+        session_id TEXT,
+        kind TEXT NOT NULL,
+        role TEXT,
+        text TEXT NOT NULL,
+        spoken TEXT,
+        at_ms INTEGER NOT NULL,
+        duration_ms INTEGER,
+        detail TEXT
+    );
+    CREATE INDEX IF NOT EXISTS entries_by_session ON entries (session_id, at_ms);
+";
+
+// Opened on first use
+static DB: Lazy<Mutex<Option<Connection>>> = Lazy::new(|| Mutex::new(None));
+
+/// What an entry records
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryKind {
+    /// Something the user said (or typed), as transcribed
+    Transcription,
+    /// An answer from OpenCode, with the part of it that was spoken
+    Response,
+    /// Any other message spoken aloud (system notices, errors, tool summaries)
+    Spoken,
+    /// A yes/no decision on an action that needed confirming
+    Confirmation,
+}
+
+impl EntryKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Transcription => "transcription",
+            Self::Response => "response",
+            Self::Spoken => "spoken",
+            Self::Confirmation => "confirmation",
+        }
+    }
+
+    fn parse(kind: &str) -> Self {
+        match kind {
+            "transcription" => Self::Transcription,
+            "response" => Self::Response,
+            "confirmation" => Self::Confirmation,
+            _ => Self::Spoken,
+        }
+    }
+}
+
+/// An entry to record
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewEntry {
+    pub kind: EntryKind,
+    pub text: String,
+    /// Speech role, or where a transcription came from (push-to-talk, ...)
+    #[serde(default)]
+    pub role: Option<String>,
+    #[serde(default)]
+    pub spoken: Option<String>,
+    /// Audio length for transcriptions, response time for answers, playback
+    /// time for spoken messages
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
+    /// Anything else worth keeping (transcription latency, the tool a
+    /// confirmation was for and whether it was approved, ...)
+    #[serde(default)]
+    pub detail: Option<serde_json::Value>,
+}
+
+impl NewEntry {
+    pub fn new(kind: EntryKind, text: impl Into<String>) -> Self {
+        Self {
+            kind,
+            text: text.into(),
+            role: None,
+            spoken: None,
+            duration_ms: None,
+            detail: None,
+        }
+    }
+}
+
+/// A recorded entry
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Entry {
+    pub id: i64,
+    pub session_id: Option<String>,
+    pub kind: EntryKind,
+    pub role: Option<String>,
+    pub text: String,
+    pub spoken: Option<String>,
+    /// When it happened, in milliseconds since the Unix epoch
+    pub at_ms: i64,
+    pub duration_ms: Option<i64>,
+    pub detail: Option<serde_json::Value>,
+}
+
+/// A session that has history
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSummary {
+    pub session_id: Option<String>,
+    pub title: Option<String>,
+    pub entries: i64,
+    pub first_ms: i64,
+    pub last_ms: i64,
+}
+
+/// Export file formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Json,
+    Markdown,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Markdown => "md",
+        }
+    }
+}
+
+/// Where the database lives
+pub fn database_path() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("opencode-talk").join("history.sqlite3"))
+}
+
+/// Where exports are written. The webview doesn't get to choose the path, so
+/// it can't be used to overwrite arbitrary files.
+pub fn export_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("opencode-talk").join("exports"))
+}
+
+fn open() -> Result<Connection, String> {
+    let path = database_path().ok_or("No data directory for the history database")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Can't create {}: {}", dir.display(), e))?;
+    }
+    let connection = Connection::open(&path).map_err(|e| format!("Can't open {}: {}", path.display(), e))?;
+    migrate(&connection).map_err(|e| format!("Can't set up the history database: {}", e))?;
+    eprintln!("[HISTORY] Recording to {}", path.display());
+    Ok(connection)
+}
+
+fn migrate(connection: &Connection) -> rusqlite::Result<()> {
+    connection.pragma_update(None, "journal_mode", "WAL")?;
+    let version: i32 = connection.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    if version < SCHEMA_VERSION {
+        connection.execute_batch(SCHEMA)?;
+        connection.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+    }
+    Ok(())
+}
+
+// Run something against the database, opening it first if needed
+fn with_db<T>(f: impl FnOnce(&Connection) -> rusqlite::Result<T>) -> Result<T, String> {
+    let mut db = DB.lock().unwrap();
+    if db.is_none() {
+        *db = Some(open()?);
+    }
+    f(db.as_ref().unwrap()).map_err(|e| format!("History database error: {}", e))
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+/// Record an entry for a session, returning its id
+pub fn record(session_id: Option<&str>, entry: NewEntry) -> Result<i64, String> {
+    let detail = entry.detail.map(|d| d.to_string());
+    with_db(|db| {
+        db.execute(
+            "INSERT INTO entries (session_id, kind, role, text, spoken, at_ms, duration_ms, detail)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                session_id,
+                entry.kind.as_str(),
+                entry.role,
+                entry.text,
+                entry.spoken,
+                now_ms(),
+                entry.duration_ms.map(|ms| ms as i64),
+                detail,
+            ],
+        )?;
+        Ok(db.last_insert_rowid())
+    })
+}
+
+/// Remember a session's title for listings and exports
+pub fn set_session_title(session_id: &str, title: &str) -> Result<(), String> {
+    with_db(|db| {
+        db.execute(
+            "INSERT INTO sessions (id, title) VALUES (?1, ?2)
+             ON CONFLICT (id) DO UPDATE SET title = excluded.title",
+            params![session_id, title],
+        )
+        .map(|_| ())
+    })
+}
+
+fn entry_from_row(row: &Row) -> rusqlite::Result<Entry> {
+    let kind: String = row.get("kind")?;
+    let detail: Option<String> = row.get("detail")?;
+    Ok(Entry {
+        id: row.get("id")?,
+        session_id: row.get("session_id")?,
+        kind: EntryKind::parse(&kind),
+        role: row.get("role")?,
+        text: row.get("text")?,
+        spoken: row.get("spoken")?,
+        at_ms: row.get("at_ms")?,
+        duration_ms: row.get("duration_ms")?,
+        detail: detail.and_then(|d| serde_json::from_str(&d).ok()),
+    })
+}
+
+/// Sessions with history, most recently active first
+pub fn sessions(limit: usize) -> Result<Vec<SessionSummary>, String> {
+    with_db(|db| {
+        let mut statement = db.prepare(
+            "SELECT e.session_id, s.title, COUNT(*), MIN(e.at_ms), MAX(e.at_ms)
+             FROM entries e LEFT JOIN sessions s ON s.id = e.session_id
+             GROUP BY e.session_id
+             ORDER BY MAX(e.at_ms) DESC
+             LIMIT ?1",
+        )?;
+        let rows = statement.query_map(params![limit as i64], |row| {
+            Ok(SessionSummary {
+                session_id: row.get(0)?,
+                title: row.get(1)?,
+                entries: row.get(2)?,
+                first_ms: row.get(3)?,
+                last_ms: row.get(4)?,
+            })
+        })?;
+        rows.collect()
+    })
+}
+
+/// A session's entries in order (or the latest entries of all sessions when
+/// no session is given), at most `limit` of the most recent
+pub fn entries(session_id: Option<&str>, limit: usize) -> Result<Vec<Entry>, String> {
+    with_db(|db| {
+        let mut statement = db.prepare(
+            "SELECT * FROM (
+                 SELECT * FROM entries WHERE ?1 IS NULL OR session_id = ?1
+                 ORDER BY at_ms DESC, id DESC LIMIT ?2
+             ) ORDER BY at_ms, id",
+        )?;
+        let rows = statement.query_map(params![session_id, limit as i64], entry_from_row)?;
+        rows.collect()
+    })
+}
+
+/// A single entry by id
+pub fn entry(id: i64) -> Result<Option<Entry>, String> {
+    with_db(|db| {
+        db.query_row("SELECT * FROM entries WHERE id = ?1", params![id], entry_from_row)
+            .optional()
+    })
+}
+
+/// The most recent entry of a kind in a session
+pub fn latest(session_id: Option<&str>, kind: EntryKind) -> Result<Option<Entry>, String> {
+    with_db(|db| {
+        db.query_row(
+            "SELECT * FROM entries WHERE session_id IS ?1 AND kind = ?2
+             ORDER BY at_ms DESC, id DESC LIMIT 1",
+            params![session_id, kind.as_str()],
+            entry_from_row,
+        )
+        .optional()
+    })
+}
+
+/// Entries whose text (or spoken text) contains every word of the query,
+/// newest first
+pub fn search(query: &str, limit: usize) -> Result<Vec<Entry>, String> {
+    let words: Vec<String> = query
+        .split_whitespace()
+        .map(|word| format!("%{}%", word.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")))
+        .collect();
+    if words.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let conditions = (1..=words.len())
+        .map(|i| format!("(text LIKE ?{i} ESCAPE '\\' OR spoken LIKE ?{i} ESCAPE '\\')"))
+        .collect::<Vec<_>>()
+        .join(" AND ");
+    let sql = format!(
+        "SELECT * FROM entries WHERE {} ORDER BY at_ms DESC, id DESC LIMIT {}",
+        conditions, limit
+    );
+    with_db(|db| {
+        let mut statement = db.prepare(&sql)?;
+        let rows = statement.query_map(rusqlite::params_from_iter(&words), entry_from_row)?;
+        rows.collect()
+    })
+}
+
+/// Export one session (or everything) as JSON or Markdown
+pub fn export(session_id: Option<&str>, format: ExportFormat) -> Result<String, String> {
+    let mut entries = entries(session_id, usize::MAX >> 1)?;
+    if session_id.is_none() {
+        // Keep each session together, sessions in the order they started
+        let mut first_seen: Vec<Option<String>> = Vec::new();
+        for entry in &entries {
+            if !first_seen.contains(&entry.session_id) {
+                first_seen.push(entry.session_id.clone());
+            }
+        }
+        entries.sort_by_key(|entry| first_seen.iter().position(|id| *id == entry.session_id));
+    }
+    match format {
+        ExportFormat::Json => serde_json::to_string_pretty(&entries).map_err(|e| e.to_string()),
+        ExportFormat::Markdown => markdown(&entries),
+    }
+}
+
+/// Export one session (or everything) to a new file in `export_dir`,
+/// returning its path
+pub fn export_file(session_id: Option<&str>, format: ExportFormat) -> Result<PathBuf, String> {
+    let dir = export_dir().ok_or("No data directory for history exports")?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Can't create {}: {}", dir.display(), e))?;
+
+    // Session IDs come from OpenCode; keep only what's safe in a file name
+    let name: String = session_id
+        .unwrap_or("all")
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+        .collect();
+    let path = dir.join(format!("history-{}-{}.{}", name, now_ms(), format.extension()));
+
+    let content = export(session_id, format)?;
+    std::fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+fn markdown(entries: &[Entry]) -> Result<String, String> {
+    let mut out = String::from("# OpenCode Talk history\n\n");
+    let mut session = None;
+    for entry in entries {
+        if session != Some(&entry.session_id) {
+            session = Some(&entry.session_id);
+            let heading = match &entry.session_id {
+                Some(id) => session_title(id)?.unwrap_or_else(|| id.clone()),
+                None => "No session".to_string(),
+            };
+            out.push_str(&format!("## {}\n\n", heading));
+        }
+
+        let time = local_time(entry.at_ms)?;
+        let text = entry.text.trim();
+        match entry.kind {
+            EntryKind::Transcription => out.push_str(&format!("**You** ({}): {}\n\n", time, text)),
+            EntryKind::Response => {
+                out.push_str(&format!("**Assistant** ({}):\n\n{}\n\n", time, text));
+                if let Some(spoken) = entry.spoken.as_deref().filter(|s| s.trim() != text) {
+                    out.push_str(&format!("> Spoken: {}\n\n", spoken.trim()));
+                }
+            }
+            EntryKind::Spoken => out.push_str(&format!("*Spoken ({})*: {}\n\n", time, text)),
+            EntryKind::Confirmation => {
+                let approved = entry.detail.as_ref().and_then(|d| d.get("approved")).and_then(|a| a.as_bool());
+                let decision = match approved {
+                    Some(true) => "approved",
+                    Some(false) => "declined",
+                    None => "no answer",
+                };
+                out.push_str(&format!("**Confirmation** ({}): {} ({})\n\n", time, text, decision));
+            }
+        }
+    }
+    Ok(format!("{}\n", out.trim_end()))
+}
+
+fn session_title(session_id: &str) -> Result<Option<String>, String> {
+    with_db(|db| {
+        db.query_row("SELECT title FROM sessions WHERE id = ?1", params![session_id], |row| row.get(0))
+            .optional()
+            .map(Option::flatten)
+    })
+}
+
+// Let SQLite format the time, in the user's time zone
+fn local_time(at_ms: i64) -> Result<String, String> {
+    with_db(|db| {
+        db.query_row(
+            "SELECT datetime(?1 / 1000, 'unixepoch', 'localtime')",
+            params![at_ms],
+            |row| row.get(0),
+        )
+    })
+}
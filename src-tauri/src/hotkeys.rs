@@ -0,0 +1,176 @@
+//! Global hotkeys, registered by the backend
+//!
+//! Registering from the webview loses key-up events while the window is
+//! hidden, which leaves push-to-talk stuck on. Here the OS delivers press and
+//! release straight to a Rust handler that drives the voice state machine.
+//!
+//! Hotkeys are written the way the settings show them ("Option+Space",
+//! "Command+Shift+K", "Escape"). The accepted modifier names cover macOS,
+//! Windows and Linux spellings.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::AppHandle;
+use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutState};
+
+// What is registered now, so rebinding can release the old key
+static REGISTERED: Mutex<Option<HashMap<HotkeyAction, Shortcut>>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HotkeyAction {
+    /// Hold to record, release to transcribe
+    PushToTalk,
+    /// Toggle hands-free listening
+    ContinuousMode,
+    /// Stop speaking and cancel whatever is in progress
+    Interrupt,
+    /// Allow the tool call being asked about
+    Approve,
+    /// Deny the tool call being asked about
+    Deny,
+}
+
+impl HotkeyAction {
+    pub const ALL: [HotkeyAction; 5] = [
+        Self::PushToTalk,
+        Self::ContinuousMode,
+        Self::Interrupt,
+        Self::Approve,
+        Self::Deny,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::PushToTalk => "push-to-talk",
+            Self::ContinuousMode => "continuous mode",
+            Self::Interrupt => "interrupt",
+            Self::Approve => "approve",
+            Self::Deny => "deny",
+        }
+    }
+}
+
+/// Registration result for one action
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HotkeyStatus {
+    pub action: HotkeyAction,
+    pub hotkey: String,
+    pub registered: bool,
+    pub error: Option<String>,
+}
+
+/// Parse a hotkey, checking it can be used as a global shortcut
+pub fn parse(hotkey: &str) -> Result<Shortcut, String> {
+    let mut modifiers = Modifiers::empty();
+    let mut key = None;
+
+    for token in hotkey.split('+').map(str::trim) {
+        if token.is_empty() {
+            return Err(format!("\"{}\" has an empty key", hotkey));
+        }
+        if key.is_some() {
+            return Err(format!("\"{}\" has more than one key (modifiers go first)", hotkey));
+        }
+        match token.to_lowercase().as_str() {
+            "option" | "opt" | "alt" | "⌥" => modifiers |= Modifiers::ALT,
+            "control" | "ctrl" | "⌃" => modifiers |= Modifiers::CONTROL,
+            "shift" | "⇧" => modifiers |= Modifiers::SHIFT,
+            "command" | "cmd" | "meta" | "super" | "win" | "⌘" => modifiers |= Modifiers::SUPER,
+            "commandorcontrol" | "cmdorctrl" => {
+                modifiers |= if cfg!(target_os = "macos") { Modifiers::SUPER } else { Modifiers::CONTROL }
+            }
+            _ => {
+                let code = token
+                    .parse::<Shortcut>()
+                    .map_err(|_| format!("Unknown key \"{}\" in \"{}\"", token, hotkey))?
+                    .key;
+                key = Some(code);
+            }
+        }
+    }
+
+    let key = key.ok_or_else(|| format!("\"{}\" has no key, only modifiers", hotkey))?;
+    if modifiers.is_empty() && !works_alone(key) {
+        return Err(format!(
+            "\"{}\" needs a modifier, or it would take over that key everywhere",
+            hotkey
+        ));
+    }
+    Ok(Shortcut::new(Some(modifiers), key))
+}
+
+// Keys that don't type anything and so can be a hotkey by themselves
+fn works_alone(key: Code) -> bool {
+    let name = format!("{:?}", key);
+    let function_key = name.strip_prefix('F').is_some_and(|n| n.parse::<u8>().is_ok());
+    function_key
+        || matches!(
+            key,
+            Code::Escape | Code::Pause | Code::ScrollLock | Code::Insert | Code::PrintScreen | Code::ContextMenu
+        )
+}
+
+/// Register a hotkey for each action, replacing the current ones
+///
+/// An action whose hotkey is empty is left unbound. Actions after the first
+/// one using a given shortcut report the conflict instead of registering.
+pub fn register_all(
+    app: &AppHandle,
+    bindings: &[(HotkeyAction, String)],
+    on_event: impl Fn(&AppHandle, HotkeyAction, bool) + Clone + Send + Sync + 'static,
+) -> Vec<HotkeyStatus> {
+    let mut registered = REGISTERED.lock().unwrap();
+    let shortcuts = app.global_shortcut();
+    for shortcut in registered.take().unwrap_or_default().values() {
+        let _ = shortcuts.unregister(*shortcut);
+    }
+
+    let mut active: HashMap<HotkeyAction, Shortcut> = HashMap::new();
+    let mut statuses = Vec::new();
+    for (action, hotkey) in bindings {
+        let result = bind(app, *action, hotkey, &active, on_event.clone());
+        if let Ok(Some(shortcut)) = result {
+            active.insert(*action, shortcut);
+        }
+        let status = HotkeyStatus {
+            action: *action,
+            hotkey: hotkey.clone(),
+            registered: matches!(result, Ok(Some(_))),
+            error: result.err(),
+        };
+        if let Some(error) = &status.error {
+            eprintln!("[HOTKEY] {} hotkey not registered: {}", action.label(), error);
+        }
+        statuses.push(status);
+    }
+
+    *registered = Some(active);
+    statuses
+}
+
+fn bind(
+    app: &AppHandle,
+    action: HotkeyAction,
+    hotkey: &str,
+    active: &HashMap<HotkeyAction, Shortcut>,
+    on_event: impl Fn(&AppHandle, HotkeyAction, bool) + Send + Sync + 'static,
+) -> Result<Option<Shortcut>, String> {
+    if hotkey.trim().is_empty() {
+        return Ok(None);
+    }
+    let shortcut = parse(hotkey)?;
+    if let Some((other, _)) = active.iter().find(|(_, s)| s.id() == shortcut.id()) {
+        return Err(format!("\"{}\" is already the {} hotkey", hotkey, other.label()));
+    }
+
+    app.global_shortcut()
+        .on_shortcut(shortcut, move |app, _, event| {
+            on_event(app, action, event.state() == ShortcutState::Pressed);
+        })
+        .map_err(|e| format!("\"{}\" couldn't be registered (another app may be using it): {}", hotkey, e))?;
+    eprintln!("[HOTKEY] {} → {}", hotkey, action.label());
+    Ok(Some(shortcut))
+}
@@ -0,0 +1,209 @@
+//! Shared HTTP client for all backend traffic
+//!
+//! One lazily-built `reqwest::Client` keeps connections to the Kokoro server,
+//! speech and transcription servers and OpenCode alive between requests
+//! instead of paying connection setup for every sentence. Each request is tagged with an
+//! `Endpoint` that decides its timeout and retry budget, and latency is
+//! recorded per endpoint.
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Shared client - cloning a reqwest::Client shares its connection pool
+static CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .pool_idle_timeout(Duration::from_secs(90))
+        .pool_max_idle_per_host(4)
+        .tcp_keepalive(Duration::from_secs(60))
+        .tcp_nodelay(true)
+        .build()
+        .unwrap_or_else(|e| {
+            eprintln!("[HTTP] Failed to build tuned client ({}), using defaults", e);
+            reqwest::Client::new()
+        })
+});
+
+// Latency and error counters, keyed by endpoint
+static METRICS: Lazy<Mutex<HashMap<Endpoint, EndpointMetrics>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+// Base delay for retry backoff (doubled per attempt, plus jitter)
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(150);
+const RETRY_MAX_JITTER_MS: u64 = 100;
+
+/// Backend services we talk to, each with its own timeout and retry policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Endpoint {
+    Kokoro,   // local Kokoro server (streaming synthesis)
+    Speech,   // OpenAI-compatible speech server (may be remote)
+    Transcription,  // OpenAI-compatible transcription server
+    OpenCode, // OpenCode server API
+    Health,   // quick liveness probes
+}
+
+impl Endpoint {
+    /// Total time allowed for a request, including reading the body
+    fn timeout(self) -> Duration {
+        match self {
+            Endpoint::Kokoro => Duration::from_secs(30),
+            Endpoint::Speech => Duration::from_secs(60),
+            Endpoint::Transcription => Duration::from_secs(60),
+            Endpoint::OpenCode => Duration::from_secs(10),
+            Endpoint::Health => Duration::from_secs(2),
+        }
+    }
+
+    /// How many times a failed request is retried
+    fn max_retries(self) -> u32 {
+        match self {
+            // A dead local server should fall back to Piper quickly
+            Endpoint::Kokoro => 1,
+            Endpoint::Speech => 2,
+            // Multipart uploads can't be replayed
+            Endpoint::Transcription => 0,
+            Endpoint::OpenCode => 1,
+            Endpoint::Health => 0,
+        }
+    }
+
+    /// Whether a request that timed out is worth retrying. A synthesis server
+    /// that stalled once will likely stall again, and retrying would keep the
+    /// voice silent for minutes before falling back to another engine.
+    fn retries_timeouts(self) -> bool {
+        !matches!(self, Endpoint::Kokoro | Endpoint::Speech)
+    }
+}
+
+/// Request statistics for one endpoint
+///
+/// Latency is measured until the response headers arrive, so streamed bodies
+/// report time-to-first-byte rather than total download time.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EndpointMetrics {
+    pub requests: u64,
+    pub failures: u64,
+    pub retries: u64,
+    pub total_latency_ms: u64,
+    pub max_latency_ms: u64,
+    pub last_latency_ms: u64,
+}
+
+/// Metrics snapshot for one endpoint, as returned to the frontend
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EndpointReport {
+    pub endpoint: Endpoint,
+    #[serde(flatten)]
+    pub metrics: EndpointMetrics,
+    pub average_latency_ms: u64,
+}
+
+/// The shared client, for building requests
+pub fn client() -> &'static reqwest::Client {
+    &CLIENT
+}
+
+/// Send a request with the endpoint's timeout, retrying connection failures,
+/// timeouts (except for synthesis) and 5xx responses with exponential backoff
+/// and jitter
+///
+/// Requests whose body can't be cloned (streams) are sent once.
+pub async fn send(
+    endpoint: Endpoint,
+    request: reqwest::RequestBuilder,
+) -> Result<reqwest::Response, reqwest::Error> {
+    send_with_retries(endpoint, request, endpoint.max_retries()).await
+}
+
+/// Send a request once with the endpoint's timeout, for requests that
+/// mustn't reach the server twice (a retried timeout may have got through)
+pub async fn send_once(
+    endpoint: Endpoint,
+    request: reqwest::RequestBuilder,
+) -> Result<reqwest::Response, reqwest::Error> {
+    send_with_retries(endpoint, request, 0).await
+}
+
+async fn send_with_retries(
+    endpoint: Endpoint,
+    request: reqwest::RequestBuilder,
+    max_retries: u32,
+) -> Result<reqwest::Response, reqwest::Error> {
+    let mut pending = request.timeout(endpoint.timeout());
+    let mut attempt = 0;
+
+    loop {
+        // Keep a copy in case this attempt needs retrying
+        let retry = if attempt < max_retries {
+            pending.try_clone()
+        } else {
+            None
+        };
+
+        let started = Instant::now();
+        let result = pending.send().await;
+        let latency = started.elapsed();
+
+        let failed = match &result {
+            Ok(response) => response.status().is_server_error(),
+            Err(e) => e.is_connect() || (e.is_timeout() && endpoint.retries_timeouts()),
+        };
+
+        match retry {
+            Some(next) if failed => {
+                record(endpoint, latency, true, true);
+                attempt += 1;
+                tokio::time::sleep(backoff(attempt)).await;
+                pending = next;
+            }
+            _ => {
+                record(endpoint, latency, failed || result.is_err(), false);
+                return result;
+            }
+        }
+    }
+}
+
+/// Delay before retry number `attempt` (1-based)
+fn backoff(attempt: u32) -> Duration {
+    let exponential = RETRY_BASE_DELAY * 2u32.pow(attempt.saturating_sub(1).min(5));
+    exponential + Duration::from_millis(fastrand::u64(0..=RETRY_MAX_JITTER_MS))
+}
+
+// Record the outcome of one attempt
+fn record(endpoint: Endpoint, latency: Duration, failed: bool, retried: bool) {
+    let latency_ms = latency.as_millis() as u64;
+    let mut metrics = METRICS.lock().unwrap();
+    let entry = metrics.entry(endpoint).or_default();
+
+    entry.requests += 1;
+    entry.total_latency_ms += latency_ms;
+    entry.max_latency_ms = entry.max_latency_ms.max(latency_ms);
+    entry.last_latency_ms = latency_ms;
+    if failed {
+        entry.failures += 1;
+    }
+    if retried {
+        entry.retries += 1;
+    }
+}
+
+/// Snapshot of request metrics for every endpoint used so far
+pub fn metrics() -> Vec<EndpointReport> {
+    let metrics = METRICS.lock().unwrap();
+    let mut reports: Vec<EndpointReport> = metrics
+        .iter()
+        .map(|(endpoint, m)| EndpointReport {
+            endpoint: *endpoint,
+            metrics: m.clone(),
+            average_latency_ms: m.total_latency_ms.checked_div(m.requests).unwrap_or(0),
+        })
+        .collect();
+    reports.sort_by_key(|r| r.endpoint as u8);
+    reports
+}
@@ -0,0 +1,308 @@
+//! Voice commands handled by the app itself
+//!
+//! Transcriptions are checked against a small grammar before they are sent to
+//! OpenCode, so "stop", "louder", "new session" or "switch to piper" take
+//! effect immediately instead of becoming a prompt. Only an utterance that is
+//! a command as a whole matches ("stop" does, "stop the server" doesn't), with
+//! an optional "please" or "can you" around it.
+//!
+//! The built-in phrases can be replaced per intent in `commands.toml` next to
+//! the global lexicon; an empty list turns an intent off:
+//!
+//! ```toml
+//! [intents]
+//! stop = ["stop", "hush", "enough"]
+//! switch_engine = ["switch to {engine}", "talk with {engine}"]
+//! louder = []
+//! ```
+//!
+//! `{engine}` matches a TTS engine by name ("piper", "open ai") and `{voice}`
+//! one of the current engine's voices ("samantha", "amy"). A phrase whose slot
+//! doesn't name one isn't a command, and the text goes to OpenCode as usual.
+
+use crate::config_file;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const FILE_NAMES: &[&str] = &["commands.toml", "commands.json"];
+
+// Intents in matching order, with their built-in phrases
+const INTENTS: &[(&str, &[&str])] = &[
+    ("stop", &["stop", "stop talking", "stop speaking", "be quiet", "quiet", "shut up", "cancel", "never mind"]),
+    ("repeat", &["repeat", "repeat that", "repeat it", "say that again", "say it again", "come again", "again"]),
+    (
+        "repeat_slower",
+        &["slower", "repeat slower", "repeat that slower", "say that again slower", "say it again slower"],
+    ),
+    ("louder", &["louder", "speak up", "volume up", "turn it up"]),
+    ("quieter", &["quieter", "softer", "volume down", "turn it down"]),
+    ("faster", &["faster", "speak faster", "talk faster", "speed up"]),
+    ("slower", &["speak slower", "talk slower", "slow down"]),
+    (
+        "new_session",
+        &["new session", "new conversation", "start over", "start a new session", "start a new conversation"],
+    ),
+    ("mute", &["mute", "mute voice", "mute yourself"]),
+    ("unmute", &["unmute", "unmute voice", "unmute yourself"]),
+    (
+        "switch_engine",
+        &["switch to {engine}", "use {engine}", "switch engine to {engine}", "change engine to {engine}"],
+    ),
+    (
+        "switch_voice",
+        &["switch voice to {voice}", "change voice to {voice}", "use voice {voice}", "use the voice {voice}"],
+    ),
+];
+
+// What engines may be called, besides their id
+const ENGINE_NAMES: &[(&str, &[&str])] = &[
+    ("kokoro", &["kokoro", "cocoro"]),
+    ("piper", &["piper"]),
+    ("edge", &["edge", "edge tts", "microsoft edge"]),
+    ("macos", &["macos", "mac os", "mac", "say", "system voice"]),
+    ("espeak", &["espeak", "e speak", "espeak ng"]),
+    ("command", &["command", "custom command"]),
+    ("openai", &["openai", "open ai"]),
+];
+
+// Compiled grammar, rebuilt when the file it was built from changes
+static CACHE: config_file::Cache<Grammar> = config_file::Cache::new();
+
+/// On-disk grammar file format
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct GrammarFile {
+    intents: HashMap<String, Vec<String>>,
+}
+
+/// A recognised voice command
+#[derive(Debug, Clone, PartialEq)]
+pub enum Intent {
+    Stop,
+    Repeat,
+    RepeatSlower,
+    Louder,
+    Quieter,
+    Faster,
+    Slower,
+    NewSession,
+    Mute,
+    Unmute,
+    SwitchEngine(String),
+    SwitchVoice(String),
+}
+
+/// The phrases of one intent, for the settings window
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntentPhrases {
+    pub intent: String,
+    pub phrases: Vec<String>,
+}
+
+/// The grammar in use
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrammarInfo {
+    pub intents: Vec<IntentPhrases>,
+    /// Where the grammar file is read from (it may not exist yet)
+    pub path: Option<String>,
+    /// Grammar file that was loaded, if any
+    pub file: Option<String>,
+    /// Problems found while loading it (bad syntax, unknown intents or slots)
+    pub errors: Vec<String>,
+}
+
+struct Rule {
+    intent: &'static str,
+    re: Regex,
+}
+
+#[derive(Default)]
+struct Grammar {
+    phrases: Vec<(&'static str, Vec<String>)>,
+    rules: Vec<Rule>,
+    file: Option<String>,
+    errors: Vec<String>,
+}
+
+/// Path of the grammar file (may not exist yet)
+pub fn global_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("opencode-talk").join(FILE_NAMES[0]))
+}
+
+/// The command an utterance is, if it is one; `engine` is the current TTS
+/// engine, whose voices `{voice}` can name
+pub fn match_command(text: &str, engine: &str) -> Option<Intent> {
+    let phrase = normalize(text);
+    if phrase.is_empty() {
+        return None;
+    }
+
+    let grammar = current();
+    grammar.rules.iter().find_map(|rule| {
+        let captures = rule.re.captures(&phrase)?;
+        let slot = |name: &str| captures.name(name).map(|m| m.as_str());
+        let intent = match rule.intent {
+            "stop" => Intent::Stop,
+            "repeat" => Intent::Repeat,
+            "repeat_slower" => Intent::RepeatSlower,
+            "louder" => Intent::Louder,
+            "quieter" => Intent::Quieter,
+            "faster" => Intent::Faster,
+            "slower" => Intent::Slower,
+            "new_session" => Intent::NewSession,
+            "mute" => Intent::Mute,
+            "unmute" => Intent::Unmute,
+            "switch_engine" => Intent::SwitchEngine(resolve_engine(slot("engine")?)?.to_string()),
+            "switch_voice" => Intent::SwitchVoice(resolve_voice(slot("voice")?, engine)?.to_string()),
+            _ => return None,
+        };
+        Some(intent)
+    })
+}
+
+/// The phrases in use for each intent, with the file they came from
+pub fn grammar_info() -> GrammarInfo {
+    let grammar = current();
+    GrammarInfo {
+        intents: grammar
+            .phrases
+            .iter()
+            .map(|(intent, phrases)| IntentPhrases {
+                intent: intent.to_string(),
+                phrases: phrases.clone(),
+            })
+            .collect(),
+        path: global_path().map(|p| p.display().to_string()),
+        file: grammar.file.clone(),
+        errors: grammar.errors.clone(),
+    }
+}
+
+// Lowercase words without punctuation, as phrases are written
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric() && c != '{' && c != '}'))
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn resolve_engine(name: &str) -> Option<&'static str> {
+    let name = name
+        .strip_suffix(" engine")
+        .or_else(|| name.strip_suffix(" voice"))
+        .unwrap_or(name);
+    ENGINE_NAMES
+        .iter()
+        .find(|(_, names)| names.contains(&name))
+        .map(|(engine, _)| *engine)
+}
+
+// A voice is named in full ("en us amy medium") or by one of its parts ("amy")
+fn resolve_voice(name: &str, engine: &str) -> Option<&'static str> {
+    let words = |voice: &str| -> Vec<String> {
+        voice
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect()
+    };
+    let name = name.strip_prefix("the ").unwrap_or(name);
+
+    crate::tray::engine_voices(engine).iter().copied().find(|voice| {
+        let parts = words(voice);
+        parts.join(" ") == name || (!name.contains(' ') && parts.iter().any(|part| part == name))
+    })
+}
+
+// The grammar, rebuilt if the file changed
+fn current() -> Arc<Grammar> {
+    let candidates = global_path()
+        .as_ref()
+        .and_then(|p| p.parent())
+        .map(|dir| config_file::candidates(dir, FILE_NAMES))
+        .unwrap_or_default();
+
+    CACHE.get(candidates, (), |existing, _| {
+        let grammar = Grammar::load(existing.first().copied());
+        for error in &grammar.errors {
+            eprintln!("[INTENT] {}", error);
+        }
+        grammar
+    })
+}
+
+impl Grammar {
+    fn load(path: Option<&Path>) -> Self {
+        let mut grammar = Grammar::default();
+
+        let mut overrides = HashMap::new();
+        if let Some(path) = path {
+            match config_file::read::<GrammarFile>(path) {
+                Ok(file) => {
+                    grammar.file = Some(path.display().to_string());
+                    overrides = file.intents;
+                }
+                Err(e) => grammar.errors.push(format!("{}: {}", path.display(), e)),
+            }
+        }
+        for intent in overrides.keys() {
+            if !INTENTS.iter().any(|(name, _)| name == intent) {
+                grammar.errors.push(format!("unknown intent {:?}", intent));
+            }
+        }
+
+        for (intent, defaults) in INTENTS {
+            let phrases = match overrides.remove(*intent) {
+                Some(phrases) => phrases,
+                None => defaults.iter().map(|p| p.to_string()).collect(),
+            };
+            for phrase in &phrases {
+                match compile(phrase) {
+                    Ok(re) => grammar.rules.push(Rule { intent, re }),
+                    Err(e) => grammar.errors.push(format!("{}: {:?}: {}", intent, phrase, e)),
+                }
+            }
+            grammar.phrases.push((intent, phrases));
+        }
+
+        grammar
+    }
+}
+
+// A phrase as an anchored regex, with slots as named groups
+fn compile(phrase: &str) -> Result<Regex, String> {
+    let words = normalize(phrase);
+    if words.is_empty() {
+        return Err("empty phrase".to_string());
+    }
+
+    let mut pattern = String::new();
+    for (i, word) in words.split(' ').enumerate() {
+        if i > 0 {
+            pattern.push(' ');
+        }
+        match word.strip_prefix('{').and_then(|w| w.strip_suffix('}')) {
+            Some(slot @ ("engine" | "voice")) => {
+                if pattern.contains(&format!("(?P<{}>", slot)) {
+                    return Err(format!("{{{}}} used twice", slot));
+                }
+                pattern.push_str(&format!("(?P<{}>.+?)", slot));
+            }
+            Some(slot) => return Err(format!("unknown slot {{{}}}", slot)),
+            None => pattern.push_str(&regex::escape(word)),
+        }
+    }
+
+    Regex::new(&format!(
+        "^(?:(?:please|can you|could you) )?{}(?: please)?$",
+        pattern
+    ))
+    .map_err(|e| e.to_string())
+}
@@ -0,0 +1,180 @@
+//! Per-sentence language detection and voice routing
+//!
+//! Answers often mix languages, and every engine's default voice is English.
+//! Each sentence is run through a language detector restricted to the
+//! languages the user has configured voices for, and routed to that language's
+//! engine and voice. Short or ambiguous sentences keep the language of the
+//! previous sentence rather than flipping voices mid-answer.
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use whatlang::{Detector, Lang};
+
+// Sentences shorter than this are too ambiguous to detect on their own
+const MIN_DETECTION_WORDS: usize = 3;
+const MIN_CONFIDENCE: f64 = 0.2;
+
+// ISO 639-1 code, detector language, Kokoro lang_code (None = no Kokoro support)
+const LANGUAGES: &[(&str, Lang, Option<&str>)] = &[
+    ("en", Lang::Eng, Some("a")),
+    ("de", Lang::Deu, None),
+    ("es", Lang::Spa, Some("e")),
+    ("fr", Lang::Fra, Some("f")),
+    ("it", Lang::Ita, Some("i")),
+    ("pt", Lang::Por, Some("p")),
+    ("nl", Lang::Nld, None),
+    ("pl", Lang::Pol, None),
+    ("ru", Lang::Rus, None),
+    ("hi", Lang::Hin, Some("h")),
+    ("ja", Lang::Jpn, Some("j")),
+    ("zh", Lang::Cmn, Some("z")),
+];
+
+// Every Kokoro lang_code, including British English ("b") which has no
+// language of its own above
+const KOKORO_LANG_CODES: &[&str] = &["a", "b", "e", "f", "h", "i", "j", "p", "z"];
+
+// Language routing settings (set from frontend settings)
+static CONFIG: Lazy<Mutex<LanguageConfig>> = Lazy::new(|| Mutex::new(LanguageConfig::default()));
+
+// Language of the last sentence that was detected confidently
+static LAST_LANGUAGE: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+
+/// Engine and voice to use for one language
+/// An empty engine means "the engine selected in settings".
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LanguageVoice {
+    pub engine: String,
+    pub voice: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct LanguageConfig {
+    /// Language of the main voice (ISO 639-1)
+    pub default_language: String,
+    /// Voices for other languages, keyed by ISO 639-1 code
+    pub voices: HashMap<String, LanguageVoice>,
+}
+
+impl Default for LanguageConfig {
+    fn default() -> Self {
+        Self {
+            default_language: "en".to_string(),
+            voices: HashMap::new(),
+        }
+    }
+}
+
+/// Where a sentence should be spoken
+#[derive(Debug, Clone)]
+pub struct Route {
+    pub engine: String,
+    pub voice: String,
+    /// ISO 639-1 code of the sentence
+    pub language: String,
+}
+
+/// Set the default language and per-language voices
+pub fn set_config(config: LanguageConfig) {
+    *CONFIG.lock().unwrap() = config;
+    *LAST_LANGUAGE.lock().unwrap() = None;
+}
+
+/// Pick the engine and voice for a sentence based on its language
+/// Without any per-language voices configured no detection is done.
+pub fn route(text: &str, engine: &str, voice: &str) -> Route {
+    let config = CONFIG.lock().unwrap().clone();
+    if config.voices.is_empty() {
+        return Route {
+            engine: engine.to_string(),
+            voice: voice.to_string(),
+            language: config.default_language,
+        };
+    }
+
+    let language = detect(text, &config);
+    match config.voices.get(&language).filter(|v| !v.voice.is_empty()) {
+        Some(configured) => Route {
+            engine: if configured.engine.is_empty() {
+                engine.to_string()
+            } else {
+                configured.engine.clone()
+            },
+            voice: configured.voice.clone(),
+            language,
+        },
+        None => Route {
+            engine: engine.to_string(),
+            voice: voice.to_string(),
+            language,
+        },
+    }
+}
+
+/// Kokoro `lang_code` for a sentence
+/// Kokoro voice names carry their language ("ef_dora" is Spanish), which wins
+/// over the detected language.
+pub fn kokoro_lang_code(language: &str, voice: &str) -> &'static str {
+    if matches!(voice.get(1..3), Some("f_" | "m_")) {
+        if let Some(code) = KOKORO_LANG_CODES.iter().find(|code| voice.starts_with(**code)) {
+            return code;
+        }
+    }
+
+    LANGUAGES
+        .iter()
+        .find(|(iso, _, _)| *iso == language)
+        .and_then(|(_, _, kokoro)| *kokoro)
+        .unwrap_or("a")
+}
+
+/// espeak-ng voice for a language, for the last-resort fallback
+pub fn espeak_voice(language: &str) -> &str {
+    match language {
+        "" | "en" => "en-us",
+        "zh" => "cmn",
+        other => other,
+    }
+}
+
+// Detect among the configured languages, sticking with the previous
+// sentence's language when the text is too short or ambiguous
+fn detect(text: &str, config: &LanguageConfig) -> String {
+    let mut last = LAST_LANGUAGE.lock().unwrap();
+    let fallback = last.clone().unwrap_or_else(|| config.default_language.clone());
+
+    if text.split_whitespace().count() < MIN_DETECTION_WORDS {
+        return fallback;
+    }
+
+    let allowlist: Vec<Lang> = std::iter::once(&config.default_language)
+        .chain(config.voices.keys())
+        .filter_map(|code| to_detector_lang(code))
+        .collect();
+    if allowlist.len() < 2 {
+        return fallback;
+    }
+
+    match Detector::with_allowlist(allowlist).detect(text) {
+        Some(info) if info.is_reliable() || info.confidence() >= MIN_CONFIDENCE => {
+            let language = from_detector_lang(info.lang()).unwrap_or(fallback);
+            *last = Some(language.clone());
+            language
+        }
+        _ => fallback,
+    }
+}
+
+fn to_detector_lang(code: &str) -> Option<Lang> {
+    LANGUAGES.iter().find(|(iso, _, _)| *iso == code).map(|(_, lang, _)| *lang)
+}
+
+fn from_detector_lang(lang: Lang) -> Option<String> {
+    LANGUAGES
+        .iter()
+        .find(|(_, l, _)| *l == lang)
+        .map(|(iso, _, _)| iso.to_string())
+}
@@ -0,0 +1,216 @@
+//! User-editable pronunciation lexicon
+//!
+//! TTS engines mangle jargon like "kubectl", "nginx" and product names. A
+//! lexicon file maps words (or regexes) to how they should be said:
+//!
+//! ```toml
+//! [words]
+//! kubectl = "cube control"
+//! nginx = "engine x"
+//!
+//! [[patterns]]
+//! match = "\\bk8s\\b"
+//! say = "kubernetes"
+//! ```
+//!
+//! There is a global lexicon in the config directory and an optional one per
+//! project (`<project>/.opencode-talk/lexicon.toml`); project entries win. JSON
+//! files with the same shape work too. Files are re-read when they change, and
+//! the lexicon is applied after markdown normalization, so entries match the
+//! text as it is about to be spoken.
+
+use crate::config_file;
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+const FILE_NAMES: &[&str] = &["lexicon.toml", "lexicon.json"];
+const PROJECT_DIR_NAME: &str = ".opencode-talk";
+
+// Project whose lexicon is active
+static PROJECT_DIR: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+
+// Compiled lexicon, rebuilt when the files it was built from change
+static CACHE: config_file::Cache<Lexicon> = config_file::Cache::new();
+
+/// On-disk lexicon file format
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct LexiconFile {
+    words: HashMap<String, String>,
+    patterns: Vec<PatternEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PatternEntry {
+    #[serde(rename = "match")]
+    pattern: String,
+    say: String,
+}
+
+/// A compiled lexicon, ready to apply
+#[derive(Default)]
+struct Lexicon {
+    // All plain words as one case-insensitive alternation
+    words_re: Option<Regex>,
+    // Lowercased word -> replacement
+    words: HashMap<String, String>,
+    // Regex entries, project entries first
+    patterns: Vec<(Regex, String)>,
+    files: Vec<String>,
+    errors: Vec<String>,
+}
+
+/// What the backend will actually say for a piece of text
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PronunciationPreview {
+    pub text: String,
+    pub spoken: String,
+    /// Lexicon files that were loaded
+    pub files: Vec<String>,
+    /// Problems found while loading them (bad syntax, invalid regexes)
+    pub errors: Vec<String>,
+}
+
+/// Set the project directory whose lexicon should be used alongside the global one
+pub fn set_project_dir(dir: Option<&str>) {
+    *PROJECT_DIR.lock().unwrap() = dir.filter(|d| !d.is_empty()).map(PathBuf::from);
+}
+
+/// Apply the global and project lexicons to text about to be spoken
+pub fn apply(text: &str) -> String {
+    current().apply(text)
+}
+
+/// Describe how `text` will be spoken, with the lexicon files that shaped it
+pub fn preview(text: &str, spoken: String) -> PronunciationPreview {
+    let lexicon = current();
+    PronunciationPreview {
+        text: text.to_string(),
+        spoken,
+        files: lexicon.files.clone(),
+        errors: lexicon.errors.clone(),
+    }
+}
+
+/// Path of the global lexicon file (may not exist yet)
+pub fn global_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("opencode-talk").join(FILE_NAMES[0]))
+}
+
+// Candidate files, project first so its entries take precedence
+fn candidate_files() -> Vec<PathBuf> {
+    let mut files = Vec::new();
+
+    if let Some(project) = PROJECT_DIR.lock().unwrap().as_ref() {
+        files.extend(config_file::candidates(&project.join(PROJECT_DIR_NAME), FILE_NAMES));
+    }
+    if let Some(dir) = global_path().as_ref().and_then(|p| p.parent()) {
+        files.extend(config_file::candidates(dir, FILE_NAMES));
+    }
+
+    files
+}
+
+// The compiled lexicon, rebuilt if the project or any file changed
+fn current() -> Arc<Lexicon> {
+    CACHE.get(candidate_files(), (), |existing, _| {
+        let lexicon = Lexicon::load(existing);
+        for error in &lexicon.errors {
+            eprintln!("[LEXICON] {}", error);
+        }
+        lexicon
+    })
+}
+
+impl Lexicon {
+    // Build from files in priority order (earlier files win)
+    fn load(paths: &[&Path]) -> Self {
+        let mut lexicon = Lexicon::default();
+
+        for path in paths {
+            let file: LexiconFile = match config_file::read(path) {
+                Ok(file) => file,
+                Err(e) => {
+                    lexicon.errors.push(format!("{}: {}", path.display(), e));
+                    continue;
+                }
+            };
+            lexicon.files.push(path.display().to_string());
+
+            for (word, say) in file.words {
+                let word = word.trim().to_lowercase();
+                if !word.is_empty() {
+                    lexicon.words.entry(word).or_insert(say);
+                }
+            }
+            for entry in file.patterns {
+                match Regex::new(&entry.pattern) {
+                    Ok(re) => lexicon.patterns.push((re, entry.say)),
+                    Err(e) => lexicon.errors.push(format!(
+                        "{}: invalid pattern {:?}: {}",
+                        path.display(),
+                        entry.pattern,
+                        e
+                    )),
+                }
+            }
+        }
+
+        lexicon.words_re = build_words_regex(lexicon.words.keys());
+        lexicon
+    }
+
+    fn apply(&self, text: &str) -> String {
+        let mut text = match &self.words_re {
+            Some(re) => re
+                .replace_all(text, |c: &Captures| {
+                    let matched = &c[0];
+                    self.words
+                        .get(&matched.to_lowercase())
+                        .cloned()
+                        .unwrap_or_else(|| matched.to_string())
+                })
+                .into_owned(),
+            None => text.to_string(),
+        };
+
+        // Regex entries may use $1-style capture references in `say`
+        for (re, say) in &self.patterns {
+            text = re.replace_all(&text, say.as_str()).into_owned();
+        }
+
+        text
+    }
+}
+
+// One regex matching any lexicon word on its own, longest first so "k8s dashboard"
+// beats "k8s". Word boundaries are only required next to word characters, so
+// entries like "C++" still match.
+fn build_words_regex<'a>(words: impl Iterator<Item = &'a String>) -> Option<Regex> {
+    let mut words: Vec<&String> = words.collect();
+    if words.is_empty() {
+        return None;
+    }
+    words.sort_by_key(|w| std::cmp::Reverse(w.len()));
+
+    let alternatives: Vec<String> = words
+        .iter()
+        .map(|word| {
+            let starts_word = word.starts_with(|c: char| c.is_alphanumeric() || c == '_');
+            let ends_word = word.ends_with(|c: char| c.is_alphanumeric() || c == '_');
+            format!(
+                "{}{}{}",
+                if starts_word { r"\b" } else { "" },
+                regex::escape(word),
+                if ends_word { r"\b" } else { "" }
+            )
+        })
+        .collect();
+
+    Regex::new(&format!("(?i)(?:{})", alternatives.join("|"))).ok()
+}
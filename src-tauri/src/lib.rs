@@ -1,15 +1,34 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
-use tauri::{
-    image::Image,
-    menu::{Menu, MenuItem},
-    tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
-    AppHandle, Emitter, Manager, State,
-};
+use tauri::{AppHandle, Emitter, Manager, State};
 
 mod audio;
+mod audit;
+mod config_file;
+mod echo;
+mod history;
+mod hotkeys;
+mod http;
+mod intents;
+mod language;
+mod lexicon;
+mod mic;
+mod opencode_events;
+mod pcm_stream;
+mod permissions;
+mod policy;
+mod prosody;
+mod replay;
+mod sentence_buffer;
+mod stt;
+mod text_normalizer;
 mod tts;
 mod transcription_server;
+mod tray;
+mod vad;
+mod voice_profile;
+pub mod wake_word;
 
 // Application state
 #[derive(Default)]
@@ -18,6 +37,13 @@ pub struct AppState {
     pub is_speaking: bool,
     pub is_processing: bool,
     pub current_session_id: Option<String>,
+    pub session_title: Option<String>,
+    pub settings: Settings,
+    pub sentence_buffer: sentence_buffer::SentenceBuffer,
+    pub recording: Option<mic::MicCapture>,
+    pub continuous: Option<vad::ContinuousListener>,
+    pub wake_word: Option<wake_word::WakeWordListener>,
+    pub hotkey_status: Vec<hotkeys::HotkeyStatus>,
 }
 
 pub type SharedState = Arc<Mutex<AppState>>;
@@ -32,22 +58,49 @@ pub enum VoiceState {
     Speaking,
 }
 
-// Settings struct
+// Settings struct (mirrors the frontend settings store)
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct Settings {
     pub stt_engine: String,
+    pub input_device: String,
+    pub whisper_model: String,
+    pub whisper_language: String,
+    pub stt_openai_base_url: String,
+    pub stt_openai_model: String,
+    pub stt_command_template: String,
+    pub vad_silence_ms: u32,
+    pub vad_min_speech_ms: u32,
+    pub echo_suppression: bool,
+    pub wake_word_enabled: bool,
+    pub wake_word: String,
+    pub wake_word_sensitivity: f32,
     pub push_to_talk_hotkey: String,
     pub continuous_mode_hotkey: String,
     pub interrupt_hotkey: String,
+    pub approve_hotkey: String,
+    pub deny_hotkey: String,
     pub tts_engine: String,
     pub tts_voice: String,
     pub tts_speed: f32,
+    pub tts_volume: f32,
+    pub voice_muted: bool,
+    pub tts_language: String,
+    pub language_voices: HashMap<String, language::LanguageVoice>,
+    pub agent_voices: HashMap<String, voice_profile::VoiceProfile>,
+    pub role_voices: HashMap<voice_profile::Role, voice_profile::VoiceProfile>,
+    pub tts_command_template: String,
+    pub tts_command_format: String,
+    pub openai_api_key: Option<String>,
+    pub openai_base_url: String,
+    pub openai_model: String,
     pub server_url: String,
     pub model: String,
     pub agent: String,
     pub confirm_file_writes: bool,
     pub confirm_shell_commands: bool,
     pub confirm_git_operations: bool,
+    pub confirmation_timeout_secs: u32,
     pub show_floating_panel: bool,
     pub play_sound_on_response: bool,
     pub auto_start_on_login: bool,
@@ -59,18 +112,44 @@ impl Default for Settings {
     fn default() -> Self {
         Self {
             stt_engine: "superwhisper".to_string(),
+            input_device: String::new(),
+            whisper_model: "base.en".to_string(),
+            whisper_language: "en".to_string(),
+            stt_openai_base_url: "https://api.openai.com".to_string(),
+            stt_openai_model: "whisper-1".to_string(),
+            stt_command_template: String::new(),
+            vad_silence_ms: 800,
+            vad_min_speech_ms: 300,
+            echo_suppression: true,
+            wake_word_enabled: false,
+            wake_word: "hey opencode".to_string(),
+            wake_word_sensitivity: 0.5,
             push_to_talk_hotkey: "Option+Space".to_string(),
             continuous_mode_hotkey: "Option+Shift+Space".to_string(),
             interrupt_hotkey: "Escape".to_string(),
+            approve_hotkey: "Option+Shift+Y".to_string(),
+            deny_hotkey: "Option+Shift+N".to_string(),
             tts_engine: "macos".to_string(),
             tts_voice: "Samantha".to_string(),
             tts_speed: 1.0,
+            tts_volume: 1.0,
+            voice_muted: false,
+            tts_language: "en".to_string(),
+            language_voices: HashMap::new(),
+            agent_voices: HashMap::new(),
+            role_voices: HashMap::new(),
+            tts_command_template: String::new(),
+            tts_command_format: "wav".to_string(),
+            openai_api_key: None,
+            openai_base_url: "https://api.openai.com".to_string(),
+            openai_model: "tts-1".to_string(),
             server_url: "http://localhost:4096".to_string(),
             model: "anthropic/claude-sonnet-4-20250514".to_string(),
             agent: "default".to_string(),
             confirm_file_writes: true,
             confirm_shell_commands: true,
             confirm_git_operations: true,
+            confirmation_timeout_secs: 30,
             show_floating_panel: true,
             play_sound_on_response: true,
             auto_start_on_login: false,
@@ -83,7 +162,10 @@ impl Default for Settings {
 // Get the current voice state
 #[tauri::command]
 fn get_voice_state(state: State<SharedState>) -> VoiceState {
-    let app_state = state.lock().unwrap();
+    voice_state_of(&state.lock().unwrap())
+}
+
+fn voice_state_of(app_state: &AppState) -> VoiceState {
     if app_state.is_listening {
         VoiceState::Listening
     } else if app_state.is_processing {
@@ -98,60 +180,52 @@ fn get_voice_state(state: State<SharedState>) -> VoiceState {
 // Set voice state
 #[tauri::command]
 fn set_voice_state(state: State<SharedState>, voice_state: VoiceState, app_handle: AppHandle) {
-    let mut app_state = state.lock().unwrap();
-    
-    match voice_state {
-        VoiceState::Idle => {
-            app_state.is_listening = false;
-            app_state.is_processing = false;
-            app_state.is_speaking = false;
-        }
-        VoiceState::Listening => {
-            app_state.is_listening = true;
-            app_state.is_processing = false;
-            app_state.is_speaking = false;
-        }
-        VoiceState::Processing => {
-            app_state.is_listening = false;
-            app_state.is_processing = true;
-            app_state.is_speaking = false;
-        }
-        VoiceState::Speaking => {
-            app_state.is_listening = false;
-            app_state.is_processing = false;
-            app_state.is_speaking = true;
-        }
-    }
+    apply_voice_state(&state, voice_state, &app_handle);
+}
+
+// Update the state flags, notify the frontend and update the tray icon
+fn apply_voice_state(state: &SharedState, voice_state: VoiceState, app_handle: &AppHandle) {
+    let session_title = {
+        let mut app_state = state.lock().unwrap();
+        app_state.is_listening = matches!(voice_state, VoiceState::Listening);
+        app_state.is_processing = matches!(voice_state, VoiceState::Processing);
+        app_state.is_speaking = matches!(voice_state, VoiceState::Speaking);
+        app_state.session_title.clone()
+    };
     
     // Emit state change to frontend
     let _ = app_handle.emit("voice-state-changed", &voice_state);
     
     // Update tray icon based on state
-    update_tray_icon(&app_handle, &voice_state);
+    tray::show_state(app_handle, &voice_state, session_title.as_deref());
 }
 
-// Update tray icon based on voice state
-fn update_tray_icon(app_handle: &AppHandle, state: &VoiceState) {
-    if let Some(tray) = app_handle.tray_by_id("main-tray") {
-        let icon_name = match state {
-            VoiceState::Idle => "icon.png",
-            VoiceState::Listening => "icon-listening.png",
-            VoiceState::Processing => "icon-processing.png",
-            VoiceState::Speaking => "icon-speaking.png",
-        };
-        
-        // Try to load the icon, fall back to default if not found
-        if let Ok(icon) = Image::from_path(format!("icons/{}", icon_name)) {
-            let _ = tray.set_icon(Some(icon));
-        }
-    }
-}
 
-// Set current session ID
+// Set current session ID and title (shown in the tray tooltip)
 #[tauri::command]
-fn set_session_id(state: State<SharedState>, session_id: Option<String>) {
-    let mut app_state = state.lock().unwrap();
-    app_state.current_session_id = session_id;
+fn set_session_id(
+    state: State<SharedState>,
+    session_id: Option<String>,
+    title: Option<String>,
+    app_handle: AppHandle,
+) {
+    apply_session(&state, session_id, title, &app_handle);
+}
+
+fn apply_session(state: &SharedState, session_id: Option<String>, title: Option<String>, app_handle: &AppHandle) {
+    let voice_state = {
+        let mut app_state = state.lock().unwrap();
+        app_state.current_session_id = session_id.clone();
+        app_state.session_title = title.clone();
+        voice_state_of(&app_state)
+    };
+    if let (Some(id), Some(title)) = (&session_id, &title) {
+        if let Err(e) = history::set_session_title(id, title) {
+            eprintln!("[HISTORY] {}", e);
+        }
+    }
+    tray::show_state(app_handle, &voice_state, title.as_deref());
+    tray::refresh_menu(app_handle);
 }
 
 // Get current session ID
@@ -161,25 +235,196 @@ fn get_session_id(state: State<SharedState>) -> Option<String> {
     app_state.current_session_id.clone()
 }
 
+// Record something in the conversation history (answers and confirmation
+// decisions, which the frontend sees first), for the current session
+#[tauri::command]
+fn record_history_entry(entry: history::NewEntry, app_handle: AppHandle) -> Option<i64> {
+    record_history(&app_handle, entry)
+}
+
+fn record_history(app_handle: &AppHandle, entry: history::NewEntry) -> Option<i64> {
+    let session_id = app_handle.state::<SharedState>().lock().unwrap().current_session_id.clone();
+    history::record(session_id.as_deref(), entry)
+        .map_err(|e| eprintln!("[HISTORY] {}", e))
+        .ok()
+}
+
+// List the sessions that have history, most recently active first
+#[tauri::command]
+fn list_history_sessions(limit: Option<usize>) -> Result<Vec<history::SessionSummary>, String> {
+    history::sessions(limit.unwrap_or(50))
+}
+
+// Get a session's history (or the latest entries of all sessions)
+#[tauri::command]
+fn get_history(session_id: Option<String>, limit: Option<usize>) -> Result<Vec<history::Entry>, String> {
+    history::entries(session_id.as_deref(), limit.unwrap_or(500))
+}
+
+// Find history entries containing all the words of a query
+#[tauri::command]
+fn search_history(query: String, limit: Option<usize>) -> Result<Vec<history::Entry>, String> {
+    history::search(&query, limit.unwrap_or(100))
+}
+
+// Export a session's history (or all of it) to a JSON or Markdown file in the
+// app's data directory, returning the path written
+#[tauri::command]
+fn export_history(session_id: Option<String>, format: history::ExportFormat) -> Result<String, String> {
+    let path = history::export_file(session_id.as_deref(), format)?;
+    Ok(path.display().to_string())
+}
+
+// Receive the latest settings from the frontend
+#[tauri::command]
+fn update_settings(state: State<SharedState>, settings: Settings, app_handle: AppHandle) {
+    stt::set_config(stt::SttConfig {
+        engine: settings.stt_engine.clone(),
+        whisper_model: settings.whisper_model.clone(),
+        language: settings.whisper_language.clone(),
+        openai_base_url: settings.stt_openai_base_url.clone(),
+        openai_model: settings.stt_openai_model.clone(),
+        openai_api_key: settings.openai_api_key.clone(),
+        command_template: settings.stt_command_template.clone(),
+    });
+    tts::set_custom_command(&settings.tts_command_template, &settings.tts_command_format);
+    tts::set_muted(settings.voice_muted);
+    tts::set_volume(settings.tts_volume);
+    tts::set_openai_config(tts::OpenAiSpeechConfig {
+        base_url: settings.openai_base_url.clone(),
+        model: settings.openai_model.clone(),
+        api_key: settings.openai_api_key.clone(),
+    });
+    language::set_config(language::LanguageConfig {
+        default_language: settings.tts_language.clone(),
+        voices: settings.language_voices.clone(),
+    });
+    voice_profile::set_profiles(voice_profile::VoiceProfiles {
+        agents: settings.agent_voices.clone(),
+        roles: settings.role_voices.clone(),
+    });
+    
+    let hotkeys_changed = {
+        let mut app_state = state.lock().unwrap();
+        let changed = app_state.hotkey_status.is_empty()
+            || hotkey_bindings(&app_state.settings) != hotkey_bindings(&settings);
+        app_state.settings = settings;
+        changed
+    };
+    if hotkeys_changed {
+        register_hotkeys(&state, &app_handle);
+    }
+    tray::refresh_menu(&app_handle);
+}
+
+// Get the settings the backend is currently using
+#[tauri::command]
+fn get_settings(state: State<SharedState>) -> Settings {
+    let app_state = state.lock().unwrap();
+    app_state.settings.clone()
+}
+
+// Tell the backend which project is active (selects its pronunciation lexicon)
+#[tauri::command]
+fn set_project_path(path: Option<String>) {
+    lexicon::set_project_dir(path.as_deref());
+    policy::set_project_dir(path.as_deref());
+}
+
+// Speak a word or phrase with the pronunciation lexicon applied, returning what was said
+#[tauri::command]
+async fn preview_pronunciation(
+    text: String,
+    engine: String,
+    voice: String,
+    speed: f32,
+    state: State<'_, SharedState>,
+) -> Result<lexicon::PronunciationPreview, String> {
+    let agent = state.lock().unwrap().settings.agent.clone();
+    let spoken = prosody::plain_text(&tts::prepare_for_speech(&text));
+    let preview = lexicon::preview(&text, spoken);
+    
+    tts::speak(&text, &engine, &voice, speed, &agent, voice_profile::Role::Assistant).await?;
+    Ok(preview)
+}
+
+// Get the path of the global pronunciation lexicon file
+#[tauri::command]
+fn get_lexicon_path() -> Option<String> {
+    lexicon::global_path().map(|p| p.display().to_string())
+}
+
+// Get the voice command grammar in use (built-in phrases or commands.toml)
+#[tauri::command]
+fn get_voice_commands() -> intents::GrammarInfo {
+    intents::grammar_info()
+}
+
+// Get audit log records (transcriptions, tool calls, permission requests and
+// decisions), newest first
+#[tauri::command]
+fn query_audit_log(query: Option<audit::Query>) -> Result<Vec<audit::Record>, String> {
+    audit::query(&query.unwrap_or_default())
+}
+
+// Get the path of the current audit log file
+#[tauri::command]
+fn get_audit_log_path() -> Option<String> {
+    audit::log_path().map(|p| p.display().to_string())
+}
+
+// Dry run of the permission policy: how a request for `tool` with this
+// command or file would be decided, without asking or answering anything
+#[tauri::command]
+fn evaluate_policy(
+    tool: String,
+    command: Option<String>,
+    path: Option<String>,
+    state: State<'_, SharedState>,
+) -> policy::Evaluation {
+    let request = permissions::PermissionRequest {
+        id: String::new(),
+        session_id: String::new(),
+        title: command.clone().or_else(|| path.clone()).unwrap_or_else(|| tool.clone()),
+        tool,
+        metadata: serde_json::json!({ "command": command, "filePath": path }),
+    };
+    let assessment = permissions::assess(&request, &state.lock().unwrap().settings);
+    policy::dry_run(&request, assessment)
+}
+
 // Speak text using TTS (delegates to the tts module)
+// `role` picks the voice profile (assistant reply, tool summary, system notice or error)
 #[tauri::command]
 async fn speak(
     text: String,
     engine: String,
     voice: String,
     speed: f32,
+    role: Option<voice_profile::Role>,
     state: State<'_, SharedState>,
     app_handle: AppHandle,
 ) -> Result<(), String> {
     // Set speaking state
-    {
+    let agent = {
         let mut app_state = state.lock().unwrap();
         app_state.is_speaking = true;
-    }
+        app_state.settings.agent.clone()
+    };
     let _ = app_handle.emit("voice-state-changed", VoiceState::Speaking);
     
     // Perform TTS
-    let result = tts::speak(&text, &engine, &voice, speed).await;
+    let role = role.unwrap_or_default();
+    let started = std::time::Instant::now();
+    let result = tts::speak(&text, &engine, &voice, speed, &agent, role).await;
+    
+    // Answers are recorded with their response; this is for everything else
+    if role != voice_profile::Role::Assistant && result.is_ok() && !tts::is_muted() {
+        let mut entry = history::NewEntry::new(history::EntryKind::Spoken, text.as_str());
+        entry.role = serde_json::to_value(role).ok().and_then(|r| r.as_str().map(String::from));
+        entry.duration_ms = Some(started.elapsed().as_millis() as u64);
+        record_history(&app_handle, entry);
+    }
     
     // Reset speaking state
     {
@@ -191,6 +436,773 @@ async fn speak(
     result
 }
 
+// Recordings shorter than this are discarded without transcribing
+const MIN_RECORDING_SECS: f32 = 0.3;
+
+// Start recording from the microphone (push-to-talk pressed)
+// While recording, the input level is emitted as `mic-level` events.
+#[tauri::command]
+fn start_recording(state: State<SharedState>, app_handle: AppHandle) -> Result<(), String> {
+    begin_recording(&state, &app_handle)
+}
+
+fn begin_recording(state: &SharedState, app_handle: &AppHandle) -> Result<(), String> {
+    let input_device = {
+        let app_state = state.lock().unwrap();
+        if app_state.recording.is_some() {
+            return Ok(());
+        }
+        if app_state.continuous.is_some() {
+            return Err("Continuous listening is on".to_string());
+        }
+        app_state.settings.input_device.clone()
+    };
+    
+    // Opening the device can take a while; the state stays unlocked meanwhile
+    let level_handle = app_handle.clone();
+    let capture = mic::MicCapture::start(&input_device, move |level| {
+        let _ = level_handle.emit("mic-level", level);
+    })?;
+    {
+        let mut app_state = state.lock().unwrap();
+        // Started again, or continuous listening began, while it opened
+        if app_state.recording.is_some() || app_state.continuous.is_some() {
+            drop(app_state);
+            let _ = capture.stop();
+            return Ok(());
+        }
+        app_state.recording = Some(capture);
+    }
+    
+    apply_voice_state(state, VoiceState::Listening, app_handle);
+    Ok(())
+}
+
+// Stop recording (push-to-talk released) and transcribe what was said
+// A non-empty transcript is emitted as a `transcription` event, just like one
+// posted by Macrowhisper, and also returned.
+#[tauri::command]
+async fn stop_recording(state: State<'_, SharedState>, app_handle: AppHandle) -> Result<String, String> {
+    finish_recording(&state, &app_handle).await
+}
+
+async fn finish_recording(state: &SharedState, app_handle: &AppHandle) -> Result<String, String> {
+    let capture = state
+        .lock()
+        .unwrap()
+        .recording
+        .take()
+        .ok_or("Not recording")?;
+    apply_voice_state(state, VoiceState::Processing, app_handle);
+    
+    let started_at = capture.started_at();
+    let result = match capture.stop() {
+        // An accidental tap of the hotkey isn't worth a transcription
+        Ok(recording) if recording.duration_secs() < MIN_RECORDING_SECS => Ok(stt::Transcript::default()),
+        Ok(recording) => stt::transcribe(recording).await,
+        Err(e) => Err(e),
+    };
+    
+    match result {
+        Ok(mut transcript) if !transcript.text.is_empty() => {
+            transcript.started_at = Some(started_at);
+            emit_transcription(app_handle, &transcript, "push-to-talk");
+            Ok(transcript.text)
+        }
+        other => {
+            apply_voice_state(state, VoiceState::Idle, app_handle);
+            other.map(|t| t.text)
+        }
+    }
+}
+
+// Transcribe a WAV file as if it had been spoken into the microphone
+// (for testing speech input without a mic)
+#[tauri::command]
+async fn transcribe_wav(path: String, emit: Option<bool>, app_handle: AppHandle) -> Result<stt::Transcript, String> {
+    let recording = stt::read_wav(std::path::Path::new(&path))?;
+    let transcript = stt::transcribe(recording).await?;
+    
+    if emit.unwrap_or(true) && !transcript.text.is_empty() {
+        emit_transcription(&app_handle, &transcript, "wav file");
+    }
+    Ok(transcript)
+}
+
+// Start hands-free listening: every utterance the VAD detects is transcribed
+// and emitted as a `transcription` event. The assistant's own voice is ignored.
+#[tauri::command]
+fn start_continuous_listening(state: State<SharedState>, app_handle: AppHandle) -> Result<(), String> {
+    begin_continuous(&state, &app_handle)
+}
+
+fn begin_continuous(state: &SharedState, app_handle: &AppHandle) -> Result<(), String> {
+    {
+        let settings = {
+            let app_state = state.lock().unwrap();
+            if app_state.continuous.is_some() {
+                return Ok(());
+            }
+            if app_state.recording.is_some() {
+                return Err("Push-to-talk recording in progress".to_string());
+            }
+            app_state.settings.clone()
+        };
+        
+        // Transcribe utterances one at a time, in the order they were spoken
+        let (utterance_tx, mut utterance_rx) = tokio::sync::mpsc::unbounded_channel::<vad::Utterance>();
+        let transcribe_handle = app_handle.clone();
+        let transcribe_state = state.clone();
+        tauri::async_runtime::spawn(async move {
+            while let Some(utterance) = utterance_rx.recv().await {
+                let started_at = utterance.started_at;
+                match stt::transcribe(utterance.recording).await {
+                    Ok(mut transcript) if !transcript.text.is_empty() => {
+                        transcript.started_at = started_at;
+                        // Talking over the assistant (heard through echo suppression) interrupts it
+                        if tts::is_playing() {
+                            eprintln!("[VAD] Speech during playback, interrupting");
+                            let _ = tts::clear_audio_queue().await;
+                            transcribe_state.lock().unwrap().sentence_buffer.clear();
+                        }
+                        emit_transcription(&transcribe_handle, &transcript, "continuous");
+                    }
+                    Ok(_) => {}
+                    Err(e) => eprintln!("[STT] Transcription failed: {}", e),
+                }
+            }
+        });
+        
+        // The device is opened without holding the state lock
+        let level_handle = app_handle.clone();
+        let listener = vad::ContinuousListener::start(
+            &settings.input_device,
+            vad_config(&settings),
+            playback_gate(&settings),
+            move |level| {
+                let _ = level_handle.emit("mic-level", level);
+            },
+            move |utterance| {
+                let _ = utterance_tx.send(utterance);
+            },
+        )?;
+        let mut app_state = state.lock().unwrap();
+        // Started again, or push-to-talk began, while it opened
+        if app_state.continuous.is_some() || app_state.recording.is_some() {
+            drop(app_state);
+            listener.stop();
+            return Ok(());
+        }
+        app_state.continuous = Some(listener);
+    }
+    
+    let _ = app_handle.emit("continuous-listening-changed", true);
+    apply_voice_state(state, VoiceState::Listening, app_handle);
+    Ok(())
+}
+
+// Stop hands-free listening (an utterance in progress is still transcribed)
+#[tauri::command]
+fn stop_continuous_listening(state: State<SharedState>, app_handle: AppHandle) {
+    end_continuous(&state, &app_handle);
+}
+
+fn end_continuous(state: &SharedState, app_handle: &AppHandle) {
+    let listener = state.lock().unwrap().continuous.take();
+    if let Some(listener) = listener {
+        listener.stop();
+        let _ = app_handle.emit("continuous-listening-changed", false);
+        apply_voice_state(state, VoiceState::Idle, app_handle);
+    }
+}
+
+// Registration status of the global hotkeys
+#[tauri::command]
+fn get_hotkey_status(state: State<SharedState>) -> Vec<hotkeys::HotkeyStatus> {
+    state.lock().unwrap().hotkey_status.clone()
+}
+
+// Bind an action to a new hotkey, returning it as it will be stored
+// The old binding is kept if the new one is invalid, already used by another
+// action, or can't be registered with the OS.
+#[tauri::command]
+fn rebind_hotkey(
+    action: hotkeys::HotkeyAction,
+    hotkey: String,
+    state: State<SharedState>,
+    app_handle: AppHandle,
+) -> Result<String, String> {
+    let hotkey = hotkey.trim().to_string();
+    if hotkey.is_empty() {
+        if action == hotkeys::HotkeyAction::PushToTalk {
+            return Err("Push-to-talk needs a hotkey".to_string());
+        }
+    } else {
+        hotkeys::parse(&hotkey)?;
+    }
+    
+    let previous = {
+        let mut app_state = state.lock().unwrap();
+        let previous = app_state.settings.clone();
+        set_hotkey(&mut app_state.settings, action, &hotkey);
+        previous
+    };
+    
+    let statuses = register_hotkeys(&state, &app_handle);
+    let failed = statuses
+        .iter()
+        .find(|status| status.action == action)
+        .and_then(|status| status.error.clone());
+    if let Some(error) = failed {
+        state.lock().unwrap().settings = previous;
+        register_hotkeys(&state, &app_handle);
+        return Err(error);
+    }
+    
+    Ok(hotkey)
+}
+
+fn hotkey_bindings(settings: &Settings) -> Vec<(hotkeys::HotkeyAction, String)> {
+    hotkeys::HotkeyAction::ALL
+        .iter()
+        .map(|&action| {
+            let hotkey = match action {
+                hotkeys::HotkeyAction::PushToTalk => &settings.push_to_talk_hotkey,
+                hotkeys::HotkeyAction::ContinuousMode => &settings.continuous_mode_hotkey,
+                hotkeys::HotkeyAction::Interrupt => &settings.interrupt_hotkey,
+                hotkeys::HotkeyAction::Approve => &settings.approve_hotkey,
+                hotkeys::HotkeyAction::Deny => &settings.deny_hotkey,
+            };
+            (action, hotkey.clone())
+        })
+        .collect()
+}
+
+fn set_hotkey(settings: &mut Settings, action: hotkeys::HotkeyAction, hotkey: &str) {
+    let field = match action {
+        hotkeys::HotkeyAction::PushToTalk => &mut settings.push_to_talk_hotkey,
+        hotkeys::HotkeyAction::ContinuousMode => &mut settings.continuous_mode_hotkey,
+        hotkeys::HotkeyAction::Interrupt => &mut settings.interrupt_hotkey,
+        hotkeys::HotkeyAction::Approve => &mut settings.approve_hotkey,
+        hotkeys::HotkeyAction::Deny => &mut settings.deny_hotkey,
+    };
+    *field = hotkey.to_string();
+}
+
+// (Re-)register the hotkeys from the settings and tell the frontend how it went
+fn register_hotkeys(state: &SharedState, app_handle: &AppHandle) -> Vec<hotkeys::HotkeyStatus> {
+    let bindings = hotkey_bindings(&state.lock().unwrap().settings);
+    let statuses = hotkeys::register_all(app_handle, &bindings, handle_hotkey);
+    state.lock().unwrap().hotkey_status = statuses.clone();
+    let _ = app_handle.emit("hotkeys-changed", &statuses);
+    statuses
+}
+
+// A global hotkey was pressed or released
+fn handle_hotkey(app_handle: &AppHandle, action: hotkeys::HotkeyAction, pressed: bool) {
+    let state = app_handle.state::<SharedState>().inner().clone();
+    let backend_stt = stt::is_backend_engine(&state.lock().unwrap().settings.stt_engine);
+    
+    match (action, pressed) {
+        (hotkeys::HotkeyAction::PushToTalk, true) => {
+            // SuperWhisper and dictation listen for the same hotkey and record by themselves
+            if !backend_stt {
+                apply_voice_state(&state, VoiceState::Listening, app_handle);
+            } else if let Err(e) = begin_recording(&state, app_handle) {
+                eprintln!("[HOTKEY] Can't start recording: {}", e);
+                let _ = app_handle.emit("voice-error", e);
+            }
+        }
+        (hotkeys::HotkeyAction::PushToTalk, false) => {
+            if !backend_stt {
+                apply_voice_state(&state, VoiceState::Idle, app_handle);
+                return;
+            }
+            let app_handle = app_handle.clone();
+            tauri::async_runtime::spawn(async move {
+                if state.lock().unwrap().recording.is_none() {
+                    return;
+                }
+                if let Err(e) = finish_recording(&state, &app_handle).await {
+                    eprintln!("[STT] Transcription failed: {}", e);
+                    let _ = app_handle.emit("voice-error", e);
+                }
+            });
+        }
+        (hotkeys::HotkeyAction::ContinuousMode, true) => {
+            if state.lock().unwrap().continuous.is_some() {
+                end_continuous(&state, app_handle);
+            } else if !backend_stt {
+                let _ = app_handle.emit("voice-error", "Continuous mode needs the Whisper, OpenAI or command STT engine");
+            } else if let Err(e) = begin_continuous(&state, app_handle) {
+                eprintln!("[HOTKEY] Can't start continuous listening: {}", e);
+                let _ = app_handle.emit("voice-error", e);
+            }
+        }
+        (hotkeys::HotkeyAction::Interrupt, true) => {
+            let app_handle = app_handle.clone();
+            tauri::async_runtime::spawn(async move {
+                interrupt(&state, &app_handle).await;
+            });
+        }
+        (hotkeys::HotkeyAction::Approve, true) => {
+            permissions::answer(permissions::Answer::Yes, permissions::AnswerSource::Hotkey);
+        }
+        (hotkeys::HotkeyAction::Deny, true) => {
+            permissions::answer(permissions::Answer::No, permissions::AnswerSource::Hotkey);
+        }
+        _ => {}
+    }
+}
+
+// Stop speaking and abandon a push-to-talk recording; continuous listening
+// carries on
+async fn interrupt(state: &SharedState, app_handle: &AppHandle) {
+    let _ = tts::clear_audio_queue().await;
+    let _ = tts::stop_speaking().await;
+    permissions::answer(permissions::Answer::No, permissions::AnswerSource::Interrupt);
+    
+    let (recording, continuous) = {
+        let mut app_state = state.lock().unwrap();
+        app_state.sentence_buffer.clear();
+        (app_state.recording.take(), app_state.continuous.is_some())
+    };
+    if let Some(recording) = recording {
+        let _ = recording.stop();
+    }
+    
+    let _ = app_handle.emit("interrupted", ());
+    let voice_state = if continuous { VoiceState::Listening } else { VoiceState::Idle };
+    apply_voice_state(state, voice_state, app_handle);
+}
+
+// Something was picked from the tray menu. Settings changed here are sent to
+// the frontend as `settings-changed`, since its store is the one that's saved.
+fn handle_tray_action(app_handle: &AppHandle, action: tray::TrayAction) {
+    let state = app_handle.state::<SharedState>().inner().clone();
+    
+    match action {
+        tray::TrayAction::SelectSession { id, title } => {
+            let voice_state = {
+                let mut app_state = state.lock().unwrap();
+                app_state.current_session_id = Some(id.clone());
+                app_state.session_title = title.clone();
+                voice_state_of(&app_state)
+            };
+            tray::show_state(app_handle, &voice_state, title.as_deref());
+            let _ = app_handle.emit("session-selected", serde_json::json!({ "id": id, "title": title }));
+        }
+        tray::TrayAction::SetEngine(engine) => set_tts_engine(&state, app_handle, &engine),
+        tray::TrayAction::SetVoice(voice) => set_tts_voice(&state, app_handle, &voice),
+        tray::TrayAction::ToggleMute => {
+            let muted = !state.lock().unwrap().settings.voice_muted;
+            set_voice_muted(&state, app_handle, muted);
+        }
+        tray::TrayAction::StopSpeaking => {
+            let app_handle = app_handle.clone();
+            tauri::async_runtime::spawn(async move {
+                interrupt(&state, &app_handle).await;
+            });
+        }
+        tray::TrayAction::RepeatLastAnswer => {
+            let app_handle = app_handle.clone();
+            tauri::async_runtime::spawn(async move {
+                replay_last(&state, &app_handle, false).await;
+            });
+        }
+    }
+}
+
+// Settings changed by voice or from the tray are applied here and sent to
+// the frontend, which saves them and syncs them back through update_settings
+
+fn set_tts_engine(state: &SharedState, app_handle: &AppHandle, engine: &str) {
+    // As in the settings window, a new engine starts on its default voice
+    {
+        let mut app_state = state.lock().unwrap();
+        app_state.settings.tts_engine = engine.to_string();
+        app_state.settings.tts_voice = String::new();
+    }
+    let _ = app_handle.emit("settings-changed", serde_json::json!({ "ttsEngine": engine, "ttsVoice": "" }));
+}
+
+fn set_tts_voice(state: &SharedState, app_handle: &AppHandle, voice: &str) {
+    state.lock().unwrap().settings.tts_voice = voice.to_string();
+    let _ = app_handle.emit("settings-changed", serde_json::json!({ "ttsVoice": voice }));
+}
+
+fn set_voice_muted(state: &SharedState, app_handle: &AppHandle, muted: bool) {
+    state.lock().unwrap().settings.voice_muted = muted;
+    tts::set_muted(muted);
+    if muted {
+        tauri::async_runtime::spawn(async {
+            let _ = tts::clear_audio_queue().await;
+            let _ = tts::stop_speaking().await;
+        });
+    }
+    let _ = app_handle.emit("settings-changed", serde_json::json!({ "voiceMuted": muted }));
+}
+
+// Volume and speed are stepped by voice within the settings window's ranges
+const VOLUME_STEP: f32 = 0.25;
+const VOLUME_RANGE: (f32, f32) = (0.25, 2.0);
+const SPEED_STEP: f32 = 0.1;
+const SPEED_RANGE: (f32, f32) = (0.5, 2.0);
+
+fn step_tts_volume(state: &SharedState, app_handle: &AppHandle, steps: f32) -> f32 {
+    let volume = {
+        let mut app_state = state.lock().unwrap();
+        let volume = (app_state.settings.tts_volume + steps * VOLUME_STEP).clamp(VOLUME_RANGE.0, VOLUME_RANGE.1);
+        app_state.settings.tts_volume = volume;
+        volume
+    };
+    tts::set_volume(volume);
+    let _ = app_handle.emit("settings-changed", serde_json::json!({ "ttsVolume": volume }));
+    volume
+}
+
+fn step_tts_speed(state: &SharedState, app_handle: &AppHandle, steps: f32) -> f32 {
+    let speed = {
+        let mut app_state = state.lock().unwrap();
+        let speed = (app_state.settings.tts_speed + steps * SPEED_STEP).clamp(SPEED_RANGE.0, SPEED_RANGE.1);
+        let speed = (speed * 10.0).round() / 10.0;
+        app_state.settings.tts_speed = speed;
+        speed
+    };
+    let _ = app_handle.emit("settings-changed", serde_json::json!({ "ttsSpeed": speed }));
+    speed
+}
+
+// Carry out a voice command recognised in a transcription, confirming it
+// aloud where the effect isn't obvious
+async fn run_voice_command(state: &SharedState, app_handle: &AppHandle, intent: intents::Intent) {
+    eprintln!("[INTENT] {:?}", intent);
+    
+    let confirmation = match intent {
+        intents::Intent::Stop => {
+            interrupt(state, app_handle).await;
+            return;
+        }
+        intents::Intent::Repeat | intents::Intent::RepeatSlower => {
+            replay_last(state, app_handle, intent == intents::Intent::RepeatSlower).await;
+            return;
+        }
+        intents::Intent::Louder => {
+            let volume = step_tts_volume(state, app_handle, 1.0);
+            Some(format!("Volume {}%.", (volume * 100.0).round()))
+        }
+        intents::Intent::Quieter => {
+            let volume = step_tts_volume(state, app_handle, -1.0);
+            Some(format!("Volume {}%.", (volume * 100.0).round()))
+        }
+        intents::Intent::Faster => {
+            let speed = step_tts_speed(state, app_handle, 1.0);
+            Some(format!("Speed {:.1}.", speed))
+        }
+        intents::Intent::Slower => {
+            let speed = step_tts_speed(state, app_handle, -1.0);
+            Some(format!("Speed {:.1}.", speed))
+        }
+        intents::Intent::NewSession => {
+            // The frontend creates the session with the next message
+            apply_session(state, None, None, app_handle);
+            let _ = app_handle.emit("session-selected", serde_json::json!({ "id": null, "title": null }));
+            Some("Starting a new conversation.".to_string())
+        }
+        intents::Intent::Mute => {
+            set_voice_muted(state, app_handle, true);
+            None
+        }
+        intents::Intent::Unmute => {
+            set_voice_muted(state, app_handle, false);
+            Some("Voice on.".to_string())
+        }
+        intents::Intent::SwitchEngine(engine) => {
+            set_tts_engine(state, app_handle, &engine);
+            Some(format!("Switched to {}.", tray::engine_label(&engine)))
+        }
+        intents::Intent::SwitchVoice(voice) => {
+            set_tts_voice(state, app_handle, &voice);
+            Some("Switched voice.".to_string())
+        }
+    };
+    tray::refresh_menu(app_handle);
+    
+    match confirmation {
+        Some(text) => speak_notice(state, app_handle, &text, voice_profile::Role::System).await,
+        None => settle_voice_state(state, app_handle),
+    }
+}
+
+// Say something from the app itself (a notice or a question) with the
+// current voice settings
+async fn speak_notice(state: &SharedState, app_handle: &AppHandle, text: &str, role: voice_profile::Role) {
+    let settings = state.lock().unwrap().settings.clone();
+    apply_voice_state(state, VoiceState::Speaking, app_handle);
+    if let Err(e) = tts::speak(
+        text,
+        &settings.tts_engine,
+        &settings.tts_voice,
+        settings.tts_speed,
+        &settings.agent,
+        role,
+    )
+    .await
+    {
+        eprintln!("[TTS] {}", e);
+    }
+    settle_voice_state(state, app_handle);
+}
+
+// Back to listening if continuous mode is on, otherwise idle
+fn settle_voice_state(state: &SharedState, app_handle: &AppHandle) {
+    let continuous = state.lock().unwrap().continuous.is_some();
+    let voice_state = if continuous { VoiceState::Listening } else { VoiceState::Idle };
+    apply_voice_state(state, voice_state, app_handle);
+}
+
+// Start listening for the wake word. Hearing it moves to Listening and the
+// command that follows is transcribed and emitted as a `transcription` event.
+#[tauri::command]
+fn start_wake_word(state: State<SharedState>, app_handle: AppHandle) -> Result<(), String> {
+    if !cfg!(feature = "local-stt") {
+        return Err("The wake word needs local speech recognition (the local-stt feature is off)".to_string());
+    }
+    
+    let settings = {
+        let app_state = state.lock().unwrap();
+        if app_state.wake_word.is_some() {
+            return Ok(());
+        }
+        app_state.settings.clone()
+    };
+    
+    let config = wake_word::WakeWordConfig {
+        phrase: settings.wake_word.clone(),
+        sensitivity: settings.wake_word_sensitivity,
+    };
+    let spotter = wake_word::Spotter::new(config.clone(), stt::model_path()?, &settings.whisper_language);
+    
+    // Events are handled in order on the async runtime, where transcription runs
+    let (event_tx, mut event_rx) = tokio::sync::mpsc::unbounded_channel::<wake_word::WakeEvent>();
+    let event_state = state.inner().clone();
+    let event_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        while let Some(event) = event_rx.recv().await {
+            // Push-to-talk and continuous mode already capture speech themselves
+            let busy = {
+                let app_state = event_state.lock().unwrap();
+                app_state.recording.is_some() || app_state.continuous.is_some()
+            };
+            if busy {
+                continue;
+            }
+            
+            match event {
+                wake_word::WakeEvent::Woke(detection) => {
+                    let _ = event_handle.emit("wake-word", &detection);
+                    apply_voice_state(&event_state, VoiceState::Listening, &event_handle);
+                }
+                wake_word::WakeEvent::Command(utterance) => {
+                    apply_voice_state(&event_state, VoiceState::Processing, &event_handle);
+                    let started_at = utterance.started_at;
+                    let mut transcript = stt::transcribe(utterance.recording).await.unwrap_or_else(|e| {
+                        eprintln!("[STT] Transcription failed: {}", e);
+                        stt::Transcript::default()
+                    });
+                    transcript.started_at = started_at;
+                    // Drop the wake phrase when it was said in the same breath
+                    if let Some(found) = wake_word::match_phrase(&transcript.text, &config) {
+                        transcript.text = found.command;
+                    }
+                    if transcript.text.is_empty() {
+                        apply_voice_state(&event_state, VoiceState::Idle, &event_handle);
+                    } else {
+                        emit_transcription(&event_handle, &transcript, "wake word");
+                    }
+                }
+                wake_word::WakeEvent::TimedOut => {
+                    let _ = event_handle.emit("wake-word-timeout", ());
+                    apply_voice_state(&event_state, VoiceState::Idle, &event_handle);
+                }
+            }
+        }
+    });
+    
+    // The device is opened without holding the state lock
+    let listener = wake_word::WakeWordListener::start(
+        &settings.input_device,
+        spotter,
+        vad_config(&settings),
+        playback_gate(&settings),
+        |_| {},
+        move |event| {
+            let _ = event_tx.send(event);
+        },
+    )?;
+    let mut app_state = state.lock().unwrap();
+    // Started twice at once: keep the first
+    if app_state.wake_word.is_some() {
+        drop(app_state);
+        listener.stop();
+        return Ok(());
+    }
+    app_state.wake_word = Some(listener);
+    Ok(())
+}
+
+// Stop listening for the wake word
+#[tauri::command]
+fn stop_wake_word(state: State<SharedState>) {
+    let listener = state.lock().unwrap().wake_word.take();
+    if let Some(listener) = listener {
+        listener.stop();
+    }
+}
+
+// An utterance found in a WAV file
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UtteranceReport {
+    start_secs: f32,
+    end_secs: f32,
+    text: Option<String>,
+}
+
+// Split a WAV file into utterances with the VAD, optionally transcribing each
+// (for testing continuous mode without a mic)
+#[tauri::command]
+async fn segment_wav(
+    path: String,
+    transcribe: Option<bool>,
+    state: State<'_, SharedState>,
+) -> Result<Vec<UtteranceReport>, String> {
+    let config = vad_config(&state.lock().unwrap().settings);
+    let recording = stt::read_wav(std::path::Path::new(&path))?;
+    
+    let mut reports = Vec::new();
+    for utterance in vad::segment(&recording, &config) {
+        let text = if transcribe.unwrap_or(false) {
+            Some(stt::transcribe(utterance.recording).await?.text)
+        } else {
+            None
+        };
+        reports.push(UtteranceReport {
+            start_secs: utterance.start_secs,
+            end_secs: utterance.end_secs,
+            text,
+        });
+    }
+    Ok(reports)
+}
+
+fn vad_config(settings: &Settings) -> vad::VadConfig {
+    vad::VadConfig {
+        silence_timeout: std::time::Duration::from_millis(settings.vad_silence_ms as u64),
+        min_speech: std::time::Duration::from_millis(settings.vad_min_speech_ms as u64),
+        echo_suppression: settings.echo_suppression,
+        ..Default::default()
+    }
+}
+
+// When live input is ignored outright: with echo suppression only while
+// playback it has no reference for is running, otherwise whenever TTS plays
+fn playback_gate(settings: &Settings) -> fn() -> bool {
+    if settings.echo_suppression {
+        tts::is_playing_unreferenced
+    } else {
+        tts::is_playing
+    }
+}
+
+// List the names of the available microphones
+#[tauri::command]
+fn list_input_devices() -> Vec<String> {
+    mic::input_devices()
+}
+
+// Get the path of the configured whisper model, or why it can't be used
+#[tauri::command]
+fn check_whisper_model() -> Result<String, String> {
+    stt::model_path().map(|p| p.display().to_string())
+}
+
+// Send what the user said to the frontend, recording it in the history.
+// `source` is how it was heard (push-to-talk, continuous, wake word, ...).
+fn emit_transcription(app_handle: &AppHandle, transcript: &stt::Transcript, source: &str) {
+    let mut entry = history::NewEntry::new(history::EntryKind::Transcription, transcript.text.as_str());
+    entry.role = Some(source.to_string());
+    if transcript.audio_secs > 0.0 {
+        entry.duration_ms = Some((transcript.audio_secs * 1000.0) as u64);
+        entry.detail = Some(serde_json::json!({ "transcribeMs": transcript.elapsed_ms }));
+    }
+    record_history(app_handle, entry);
+    
+    let state = app_handle.state::<SharedState>().inner().clone();
+    let session_id = state.lock().unwrap().current_session_id.clone();
+    let audit = |handled| {
+        audit::record(
+            session_id.as_deref(),
+            audit::Event::Transcription {
+                text: transcript.text.clone(),
+                source: source.to_string(),
+                handled,
+            },
+        )
+    };
+    
+    // While OpenCode waits for a permission, what's said answers it. Audio
+    // from elsewhere has no capture time, so it's reckoned back from now.
+    if permissions::is_pending() {
+        let spoken_at = transcript.started_at.unwrap_or_else(|| {
+            let taken = std::time::Duration::from_millis(transcript.elapsed_ms)
+                + std::time::Duration::from_secs_f32(transcript.audio_secs);
+            let now = std::time::Instant::now();
+            now.checked_sub(taken).unwrap_or(now)
+        });
+        audit(permissions::answer_by_voice(&transcript.text, spoken_at));
+        return;
+    }
+    
+    // Commands like "stop" or "switch to piper" are carried out here rather
+    // than sent to OpenCode
+    let engine = state.lock().unwrap().settings.tts_engine.clone();
+    if let Some(intent) = intents::match_command(&transcript.text, &engine) {
+        audit(audit::Handling::Command);
+        let app_handle = app_handle.clone();
+        tauri::async_runtime::spawn(async move {
+            run_voice_command(&state, &app_handle, intent).await;
+        });
+        return;
+    }
+    
+    audit(audit::Handling::Prompt);
+    let payload = transcription_server::TranscriptionPayload {
+        text: transcript.text.clone(),
+        confidence: None,
+    };
+    if let Err(e) = app_handle.emit("transcription", &payload) {
+        eprintln!("Failed to emit transcription event: {}", e);
+    }
+}
+
+// Answer the pending permission prompt from the confirmation dialog ("yes",
+// "no") or with text typed while it is open. Returns false if the answer
+// wasn't understood, and the question is asked again.
+#[tauri::command]
+fn answer_confirmation(answer: String) -> Result<bool, String> {
+    if !permissions::is_pending() {
+        return Err("No confirmation is pending".to_string());
+    }
+    match permissions::parse_answer(&answer) {
+        Some(answer) => Ok(permissions::answer(answer, permissions::AnswerSource::Window)),
+        None => {
+            permissions::unclear();
+            Ok(false)
+        }
+    }
+}
+
 // Stop any currently playing audio
 #[tauri::command]
 async fn stop_speaking(state: State<'_, SharedState>, app_handle: AppHandle) -> Result<(), String> {
@@ -200,12 +1212,60 @@ async fn stop_speaking(state: State<'_, SharedState>, app_handle: AppHandle) ->
     
     let mut app_state = state.lock().unwrap();
     app_state.is_speaking = false;
+    app_state.sentence_buffer.clear();
     
     let _ = app_handle.emit("voice-state-changed", VoiceState::Idle);
     
     Ok(())
 }
 
+// Speak a past assistant answer again with the current voice settings,
+// from the replay cache when it was played with them before
+#[tauri::command]
+async fn replay_message(message_id: i64, state: State<'_, SharedState>, app_handle: AppHandle) -> Result<(), String> {
+    let entry = history::entry(message_id)?.ok_or("Message not found in history")?;
+    if entry.kind != history::EntryKind::Response {
+        return Err("Only assistant answers can be replayed".to_string());
+    }
+    replay_entry(state.inner(), &app_handle, &entry, 1.0).await
+}
+
+// Repeat the latest answer in the current session ("repeat that", "repeat
+// slower" and the tray menu)
+async fn replay_last(state: &SharedState, app_handle: &AppHandle, slower: bool) {
+    let session_id = state.lock().unwrap().current_session_id.clone();
+    let rate = if slower { replay::SLOWER } else { 1.0 };
+    let result = match history::latest(session_id.as_deref(), history::EntryKind::Response) {
+        Ok(Some(entry)) => replay_entry(state, app_handle, &entry, rate).await,
+        Ok(None) => {
+            speak_notice(state, app_handle, "There's nothing to repeat yet.", voice_profile::Role::System).await;
+            Ok(())
+        }
+        Err(e) => Err(e),
+    };
+    
+    if let Err(e) = result {
+        eprintln!("[TTS] Replay failed: {}", e);
+        let _ = app_handle.emit("voice-error", e);
+    }
+}
+
+async fn replay_entry(state: &SharedState, app_handle: &AppHandle, entry: &history::Entry, rate: f32) -> Result<(), String> {
+    let (engine, voice, speed, agent) = {
+        let settings = &state.lock().unwrap().settings;
+        (settings.tts_engine.clone(), settings.tts_voice.clone(), settings.tts_speed * rate, settings.agent.clone())
+    };
+    // What was spoken of the answer when it came in, if it was shortened for
+    // speech; streamed answers were spoken in full
+    let text = entry.spoken.as_deref().filter(|spoken| !spoken.trim().is_empty()).unwrap_or(&entry.text);
+    let key = replay::cache_key(entry.id, &engine, &voice, speed, &agent);
+    
+    apply_voice_state(state, VoiceState::Speaking, app_handle);
+    let result = tts::replay(key, text, &engine, &voice, speed, &agent).await;
+    settle_voice_state(state, app_handle);
+    result
+}
+
 // Generate and queue a single sentence for TTS playback (for streaming)
 #[tauri::command]
 async fn speak_sentence(
@@ -213,8 +1273,64 @@ async fn speak_sentence(
     voice: String,
     speed: f32,
     engine: String,
+    role: Option<voice_profile::Role>,
+    state: State<'_, SharedState>,
+) -> Result<(), String> {
+    let agent = state.lock().unwrap().settings.agent.clone();
+    tts::speak_sentence(&text, &voice, speed, &engine, &agent, role.unwrap_or_default()).await
+}
+
+// Feed streamed response text to the sentence segmenter, queueing complete sentences for TTS
+// Deliberately synchronous: sync commands run in the order they were invoked, so
+// fire-and-forget deltas from the frontend can't overtake each other.
+#[tauri::command]
+fn push_delta(
+    delta: String,
+    voice: String,
+    speed: f32,
+    engine: String,
+    role: Option<voice_profile::Role>,
+    state: State<SharedState>,
 ) -> Result<(), String> {
-    tts::speak_sentence(&text, &voice, speed, &engine).await
+    let (sentences, agent) = {
+        let mut app_state = state.lock().unwrap();
+        (app_state.sentence_buffer.push_delta(&delta), app_state.settings.agent.clone())
+    };
+    let role = role.unwrap_or_default();
+    
+    for sentence in sentences {
+        tts::queue_sentence(&sentence, &voice, speed, &engine, &agent, role)?;
+    }
+    
+    Ok(())
+}
+
+// Speak whatever is left in the sentence segmenter (call when the response ends)
+#[tauri::command]
+fn flush_sentences(
+    voice: String,
+    speed: f32,
+    engine: String,
+    role: Option<voice_profile::Role>,
+    state: State<SharedState>,
+) -> Result<(), String> {
+    let (sentences, agent) = {
+        let mut app_state = state.lock().unwrap();
+        (app_state.sentence_buffer.flush(), app_state.settings.agent.clone())
+    };
+    let role = role.unwrap_or_default();
+    
+    for sentence in sentences {
+        tts::queue_sentence(&sentence, &voice, speed, &engine, &agent, role)?;
+    }
+    
+    Ok(())
+}
+
+// Drop any partial sentence without speaking it
+#[tauri::command]
+fn clear_sentences(state: State<SharedState>) {
+    state.lock().unwrap().sentence_buffer.clear();
 }
 
 // Show the main floating panel
@@ -303,6 +1419,30 @@ fn is_command_available(command: &str) -> bool {
         .unwrap_or(false)
 }
 
+// Check if the Kokoro TTS server is ready
+#[tauri::command]
+async fn check_kokoro_health() -> bool {
+    tts::kokoro_health().await
+}
+
+// Check if the OpenCode server is reachable
+#[tauri::command]
+async fn check_opencode_health(state: State<'_, SharedState>) -> Result<bool, String> {
+    let server_url = state.lock().unwrap().settings.server_url.clone();
+    let request = http::client().get(format!("{}/global/health", server_url.trim_end_matches('/')));
+    
+    Ok(http::send(http::Endpoint::Health, request)
+        .await
+        .map(|response| response.status().is_success())
+        .unwrap_or(false))
+}
+
+// Get request latency metrics for backend HTTP traffic
+#[tauri::command]
+fn get_http_metrics() -> Vec<http::EndpointReport> {
+    http::metrics()
+}
+
 // Play a notification sound
 #[tauri::command]
 async fn play_notification_sound(sound_name: String) -> Result<(), String> {
@@ -341,60 +1481,6 @@ fn start_kokoro_server() {
     });
 }
 
-// Setup the tray icon and menu
-fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
-    let show_item = MenuItem::with_id(app, "show", "Show Panel", true, None::<&str>)?;
-    let settings_item = MenuItem::with_id(app, "settings", "Settings...", true, None::<&str>)?;
-    let separator = MenuItem::with_id(app, "sep", "---", false, None::<&str>)?;
-    let quit_item = MenuItem::with_id(app, "quit", "Quit OpenCode Talk", true, None::<&str>)?;
-    
-    let menu = Menu::with_items(app, &[&show_item, &settings_item, &separator, &quit_item])?;
-    
-    let _tray = TrayIconBuilder::with_id("main-tray")
-        .icon(app.default_window_icon().unwrap().clone())
-        .menu(&menu)
-        .show_menu_on_left_click(false)
-        .on_tray_icon_event(|tray, event| {
-            if let TrayIconEvent::Click {
-                button: MouseButton::Left,
-                button_state: MouseButtonState::Up,
-                ..
-            } = event
-            {
-                let app = tray.app_handle();
-                if let Some(window) = app.get_webview_window("main") {
-                    if window.is_visible().unwrap_or(false) {
-                        let _ = window.hide();
-                    } else {
-                        let _ = window.show();
-                        let _ = window.set_focus();
-                    }
-                }
-            }
-        })
-        .on_menu_event(|app, event| match event.id.as_ref() {
-            "show" => {
-                if let Some(window) = app.get_webview_window("main") {
-                    let _ = window.show();
-                    let _ = window.set_focus();
-                }
-            }
-            "settings" => {
-                if let Some(window) = app.get_webview_window("settings") {
-                    let _ = window.show();
-                    let _ = window.set_focus();
-                }
-            }
-            "quit" => {
-                app.exit(0);
-            }
-            _ => {}
-        })
-        .build(app)?;
-    
-    Ok(())
-}
-
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     let state: SharedState = Arc::new(Mutex::new(AppState::default()));
@@ -413,9 +1499,33 @@ pub fn run() {
             set_voice_state,
             set_session_id,
             get_session_id,
+            update_settings,
+            get_settings,
+            set_project_path,
+            preview_pronunciation,
+            get_lexicon_path,
+            get_voice_commands,
+            evaluate_policy,
+            query_audit_log,
+            get_audit_log_path,
             speak,
             speak_sentence,
+            push_delta,
+            flush_sentences,
+            clear_sentences,
             stop_speaking,
+            start_recording,
+            stop_recording,
+            transcribe_wav,
+            check_whisper_model,
+            list_input_devices,
+            start_continuous_listening,
+            stop_continuous_listening,
+            segment_wav,
+            start_wake_word,
+            stop_wake_word,
+            get_hotkey_status,
+            rebind_hotkey,
             show_panel,
             hide_panel,
             toggle_panel,
@@ -426,16 +1536,29 @@ pub fn run() {
             is_app_installed,
             is_command_available,
             play_notification_sound,
+            check_kokoro_health,
+            check_opencode_health,
+            get_http_metrics,
+            record_history_entry,
+            replay_message,
+            answer_confirmation,
+            list_history_sessions,
+            get_history,
+            search_history,
+            export_history,
         ])
         .setup(|app| {
             // Setup tray icon
-            if let Err(e) = setup_tray(app.handle()) {
+            if let Err(e) = tray::setup(app.handle(), handle_tray_action) {
                 eprintln!("Failed to setup tray: {}", e);
             }
             
             // Start the transcription server for Macrowhisper integration
             transcription_server::start_server(app.handle().clone());
             
+            // Follow OpenCode's events for permission requests
+            opencode_events::start(app.handle().clone());
+            
             // Initialize the audio player for streaming TTS
             tauri::async_runtime::spawn(async {
                 tts::init_audio_player().await;
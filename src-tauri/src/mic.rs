@@ -0,0 +1,268 @@
+//! Microphone capture with cpal
+//!
+//! A cpal input stream isn't `Send`, so each capture runs on its own thread
+//! which owns the stream until it's told to stop. Samples are downmixed to mono
+//! and buffered in memory as they arrive, and the input level is reported
+//! every few milliseconds for the UI meter. Resampling for the recognizer
+//! happens in `stt`.
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{SampleFormat, StreamConfig};
+use serde::Serialize;
+use std::sync::mpsc as std_mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+
+// How often the input level is reported
+const LEVEL_INTERVAL_MS: u32 = 50;
+
+// Longer recordings are cut off rather than growing without bound
+const MAX_RECORDING_SECS: u32 = 300;
+
+/// Input level over the last metering interval (0.0 - 1.0)
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct MicLevel {
+    pub rms: f32,
+    pub peak: f32,
+}
+
+type LevelCallback = Box<dyn Fn(MicLevel) + Send>;
+type SampleCallback = Box<dyn FnMut(&[f32]) + Send>;
+
+// Samples captured so far (or where to send them) plus the running level meter
+struct CaptureBuffer {
+    samples: Vec<f32>,
+    max_samples: usize,
+    on_samples: Option<SampleCallback>,
+    window: usize,
+    sum_squares: f32,
+    peak: f32,
+    counted: usize,
+    on_level: LevelCallback,
+}
+
+impl CaptureBuffer {
+    fn push(&mut self, chunk: &[f32]) {
+        match self.on_samples.as_mut() {
+            Some(on_samples) => on_samples(chunk),
+            None => {
+                let room = self.max_samples.saturating_sub(self.samples.len());
+                self.samples.extend_from_slice(&chunk[..chunk.len().min(room)]);
+            }
+        }
+        for &sample in chunk {
+            self.meter(sample);
+        }
+    }
+
+    fn meter(&mut self, sample: f32) {
+        self.sum_squares += sample * sample;
+        self.peak = self.peak.max(sample.abs());
+        self.counted += 1;
+        if self.counted >= self.window {
+            let level = MicLevel {
+                rms: (self.sum_squares / self.counted as f32).sqrt().min(1.0),
+                peak: self.peak.min(1.0),
+            };
+            (self.on_level)(level);
+            self.sum_squares = 0.0;
+            self.peak = 0.0;
+            self.counted = 0;
+        }
+    }
+}
+
+/// Mono audio at its native sample rate
+#[derive(Debug, Clone, Default)]
+pub struct Recording {
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+}
+
+impl Recording {
+    pub fn duration_secs(&self) -> f32 {
+        if self.sample_rate == 0 {
+            0.0
+        } else {
+            self.samples.len() as f32 / self.sample_rate as f32
+        }
+    }
+}
+
+/// A running microphone capture
+pub struct MicCapture {
+    stop_tx: std_mpsc::Sender<()>,
+    thread: JoinHandle<Recording>,
+    sample_rate: u32,
+    started_at: Instant,
+}
+
+impl MicCapture {
+    /// Start recording from the named input device (empty = system default)
+    /// into memory. `on_level` is called from the audio thread with the input level.
+    /// Returns once the stream is running, or with the reason it couldn't start.
+    pub fn start(device_name: &str, on_level: impl Fn(MicLevel) + Send + 'static) -> Result<Self, String> {
+        Self::open(device_name, Box::new(on_level), None)
+    }
+
+    /// Like `start`, but hand mono samples to `on_samples` as they arrive
+    /// instead of keeping them (for continuous listening)
+    pub fn stream(
+        device_name: &str,
+        on_level: impl Fn(MicLevel) + Send + 'static,
+        on_samples: impl FnMut(&[f32]) + Send + 'static,
+    ) -> Result<Self, String> {
+        Self::open(device_name, Box::new(on_level), Some(Box::new(on_samples)))
+    }
+
+    fn open(device_name: &str, on_level: LevelCallback, on_samples: Option<SampleCallback>) -> Result<Self, String> {
+        let (stop_tx, stop_rx) = std_mpsc::channel::<()>();
+        let (ready_tx, ready_rx) = std_mpsc::channel::<Result<u32, String>>();
+        let device_name = device_name.to_string();
+
+        let thread = thread::spawn(move || {
+            let (stream, sample_rate, buffer) = match open_input_stream(&device_name, on_level, on_samples) {
+                Ok(opened) => opened,
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e));
+                    return Recording::default();
+                }
+            };
+            let _ = ready_tx.send(Ok(sample_rate));
+
+            // Keep the stream alive until stopped (or the capture is dropped)
+            let _ = stop_rx.recv();
+            drop(stream);
+
+            // Dropping the buffer also drops `on_samples`, telling its consumer
+            // the stream has ended
+            let mut buffer = buffer.lock().unwrap();
+            buffer.on_samples = None;
+            let samples = std::mem::take(&mut buffer.samples);
+            Recording { samples, sample_rate }
+        });
+
+        match ready_rx.recv() {
+            Ok(Ok(sample_rate)) => Ok(Self {
+                stop_tx,
+                thread,
+                sample_rate,
+                started_at: Instant::now(),
+            }),
+            Ok(Err(e)) => Err(e),
+            Err(_) => Err("Microphone thread exited unexpectedly".to_string()),
+        }
+    }
+
+    /// Native sample rate of the device
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// When the stream started running
+    pub fn started_at(&self) -> Instant {
+        self.started_at
+    }
+
+    /// Stop recording and return everything captured (nothing when streaming)
+    pub fn stop(self) -> Result<Recording, String> {
+        let _ = self.stop_tx.send(());
+        self.thread
+            .join()
+            .map_err(|_| "Microphone thread panicked".to_string())
+    }
+}
+
+/// Names of the available input devices
+pub fn input_devices() -> Vec<String> {
+    cpal::default_host()
+        .input_devices()
+        .map(|devices| devices.filter_map(|d| d.name().ok()).collect())
+        .unwrap_or_default()
+}
+
+// The named input device, falling back to the default if it's gone
+fn find_input_device(name: &str) -> Option<cpal::Device> {
+    let host = cpal::default_host();
+    if !name.is_empty() {
+        let found = host
+            .input_devices()
+            .ok()
+            .and_then(|mut devices| devices.find(|d| d.name().is_ok_and(|n| n == name)));
+        if found.is_some() {
+            return found;
+        }
+        eprintln!("[MIC] Input device {:?} not found, using the default", name);
+    }
+    host.default_input_device()
+}
+
+// Open an input device, collecting mono samples into the returned buffer
+fn open_input_stream(
+    device_name: &str,
+    on_level: LevelCallback,
+    on_samples: Option<SampleCallback>,
+) -> Result<(cpal::Stream, u32, Arc<Mutex<CaptureBuffer>>), String> {
+    let device = find_input_device(device_name).ok_or("No microphone found")?;
+    let supported = device
+        .default_input_config()
+        .map_err(|e| format!("Failed to query microphone: {}", e))?;
+
+    let sample_format = supported.sample_format();
+    let config: StreamConfig = supported.into();
+    let channels = config.channels.max(1) as usize;
+    let sample_rate = config.sample_rate.0;
+
+    let buffer = Arc::new(Mutex::new(CaptureBuffer {
+        samples: Vec::new(),
+        max_samples: (sample_rate * MAX_RECORDING_SECS) as usize,
+        on_samples,
+        window: (sample_rate * LEVEL_INTERVAL_MS / 1000).max(1) as usize,
+        sum_squares: 0.0,
+        peak: 0.0,
+        counted: 0,
+        on_level,
+    }));
+    let samples = buffer.clone();
+
+    let on_error = |e| eprintln!("[MIC] Stream error: {}", e);
+
+    let stream = match sample_format {
+        SampleFormat::F32 => device.build_input_stream(
+            &config,
+            move |data: &[f32], _| push_mono(&samples, data, channels, |s| s),
+            on_error,
+            None,
+        ),
+        SampleFormat::I16 => device.build_input_stream(
+            &config,
+            move |data: &[i16], _| push_mono(&samples, data, channels, |s| s as f32 / 32768.0),
+            on_error,
+            None,
+        ),
+        SampleFormat::U16 => device.build_input_stream(
+            &config,
+            move |data: &[u16], _| push_mono(&samples, data, channels, |s| (s as f32 - 32768.0) / 32768.0),
+            on_error,
+            None,
+        ),
+        other => return Err(format!("Unsupported microphone sample format: {:?}", other)),
+    }
+    .map_err(|e| format!("Failed to open microphone: {}", e))?;
+
+    stream
+        .play()
+        .map_err(|e| format!("Failed to start microphone: {}", e))?;
+
+    Ok((stream, sample_rate, buffer))
+}
+
+// Average interleaved frames down to mono
+fn push_mono<T: Copy>(buffer: &Mutex<CaptureBuffer>, data: &[T], channels: usize, to_f32: impl Fn(T) -> f32) {
+    let mono: Vec<f32> = data
+        .chunks(channels)
+        .map(|frame| frame.iter().map(|s| to_f32(*s)).sum::<f32>() / frame.len() as f32)
+        .collect();
+    buffer.lock().unwrap().push(&mono);
+}
@@ -0,0 +1,126 @@
+//! The OpenCode server's event stream, as seen by the backend
+//!
+//! The frontend opens `/event` only while it waits for an answer. Permission
+//! requests can't wait for that, so the backend keeps its own subscription
+//! open for as long as the app runs, reconnecting when the server goes away
+//! or the server URL changes, and hands the events it cares about to
+//! `permissions`, and the tool calls OpenCode starts to the audit log.
+
+use crate::{audit, permissions};
+use crate::SharedState;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(3);
+// How often an open stream checks whether the server URL has changed
+const URL_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Subscribe to the OpenCode server's events in the background
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let url = server_url(&app);
+            if let Err(e) = follow(&app, &url).await {
+                eprintln!("[EVENTS] {}", e);
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    });
+}
+
+fn server_url(app: &AppHandle) -> String {
+    let state = app.state::<SharedState>();
+    let url = state.lock().unwrap().settings.server_url.clone();
+    url.trim_end_matches('/').to_string()
+}
+
+// Read events until the stream ends, fails or the server URL changes
+async fn follow(app: &AppHandle, url: &str) -> Result<(), String> {
+    // A stream that stays open has no overall timeout, so it's sent directly
+    // rather than through `http::send`
+    let mut response = crate::http::client()
+        .get(format!("{}/event", url))
+        .header("Accept", "text/event-stream")
+        .send()
+        .await
+        .map_err(|e| format!("Can't subscribe to {}: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("Event stream refused: HTTP {}", response.status()));
+    }
+    eprintln!("[EVENTS] Subscribed to {}", url);
+
+    // Bytes, since a chunk can end in the middle of a character
+    let mut pending: Vec<u8> = Vec::new();
+    let mut url_check = tokio::time::interval(URL_CHECK_INTERVAL);
+    loop {
+        tokio::select! {
+            chunk = response.chunk() => {
+                let chunk = match chunk {
+                    Ok(Some(chunk)) => chunk,
+                    Ok(None) => return Err("Event stream closed".to_string()),
+                    Err(e) => return Err(format!("Event stream interrupted: {}", e)),
+                };
+                pending.extend(chunk.iter().filter(|&&b| b != b'\r'));
+
+                // Events are separated by a blank line; only `data:` lines matter
+                while let Some(end) = pending.windows(2).position(|w| w == b"\n\n") {
+                    let event: Vec<u8> = pending.drain(..end + 2).collect();
+                    let data: String = String::from_utf8_lossy(&event)
+                        .lines()
+                        .filter_map(|line| line.strip_prefix("data:"))
+                        .map(str::trim_start)
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    if data.is_empty() {
+                        continue;
+                    }
+                    match serde_json::from_str::<serde_json::Value>(&data) {
+                        Ok(event) => dispatch(app, &event),
+                        Err(e) => eprintln!("[EVENTS] Unreadable event: {}", e),
+                    }
+                }
+            }
+            _ = url_check.tick() => {
+                if server_url(app) != url {
+                    return Err("Server URL changed, reconnecting".to_string());
+                }
+            }
+        }
+    }
+}
+
+fn dispatch(app: &AppHandle, event: &serde_json::Value) {
+    let properties = &event["properties"];
+    match event["type"].as_str() {
+        Some("permission.updated") => match permissions::PermissionRequest::from_event(properties) {
+            Some(request) => permissions::request(app, request),
+            None => eprintln!("[EVENTS] Permission request without an id or session"),
+        },
+        Some("permission.replied") => {
+            if let Some(id) = properties["permissionID"].as_str() {
+                permissions::replied_elsewhere(id);
+            }
+        }
+        Some("message.part.updated") => {
+            let part = &properties["part"];
+            if part["type"] != "tool" || part["state"]["status"] != "running" {
+                return;
+            }
+            // Calls in sessions used from other clients aren't ours to account for
+            let session_id = app.state::<SharedState>().lock().unwrap().current_session_id.clone();
+            let (Some(session), Some(call_id)) = (part["sessionID"].as_str(), part["callID"].as_str()) else {
+                return;
+            };
+            if session_id.as_deref() == Some(session) {
+                audit::record_tool_call(
+                    session,
+                    call_id,
+                    part["tool"].as_str().unwrap_or_default(),
+                    part["state"]["title"].as_str(),
+                    &part["state"]["input"],
+                );
+            }
+        }
+        _ => {}
+    }
+}
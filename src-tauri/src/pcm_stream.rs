@@ -0,0 +1,110 @@
+//! Incremental PCM playback for streaming TTS engines
+//!
+//! Network engines deliver audio in chunks. Instead of waiting for the whole
+//! sentence and writing a temp file, the bytes are pushed through a channel into
+//! a rodio `Source` that the audio thread appends to the sink right away, so
+//! playback starts with the first chunk.
+
+use rodio::Source;
+use std::sync::mpsc as std_mpsc;
+use std::time::Duration;
+
+/// Create a connected writer/source pair for 16-bit little-endian PCM
+pub fn pcm_stream(sample_rate: u32, channels: u16) -> (PcmStreamWriter, PcmStreamSource) {
+    let (tx, rx) = std_mpsc::channel();
+    (
+        PcmStreamWriter { tx, carry: None },
+        PcmStreamSource {
+            rx,
+            buffer: Vec::new().into_iter(),
+            sample_rate,
+            channels,
+        },
+    )
+}
+
+/// Sending half - accepts raw byte chunks as they arrive
+pub struct PcmStreamWriter {
+    tx: std_mpsc::Sender<Vec<i16>>,
+    // Odd trailing byte from the previous chunk (chunks can split a sample)
+    carry: Option<u8>,
+}
+
+impl PcmStreamWriter {
+    /// Push a chunk of raw PCM bytes
+    /// Returns false once playback has been stopped and the source dropped
+    pub fn write(&mut self, bytes: &[u8]) -> bool {
+        let mut samples = Vec::with_capacity(bytes.len() / 2 + 1);
+        let mut bytes = bytes;
+
+        if let Some(low) = self.carry.take() {
+            match bytes.split_first() {
+                Some((&high, rest)) => {
+                    samples.push(i16::from_le_bytes([low, high]));
+                    bytes = rest;
+                }
+                None => {
+                    self.carry = Some(low);
+                    return true;
+                }
+            }
+        }
+
+        let mut pairs = bytes.chunks_exact(2);
+        for pair in &mut pairs {
+            samples.push(i16::from_le_bytes([pair[0], pair[1]]));
+        }
+        self.carry = pairs.remainder().first().copied();
+
+        if samples.is_empty() {
+            return true;
+        }
+        self.tx.send(samples).is_ok()
+    }
+}
+
+/// Receiving half - a rodio source that plays samples as they arrive
+///
+/// While the next chunk is still in flight the source yields silence rather than
+/// blocking the output callback. It ends when the writer is dropped.
+pub struct PcmStreamSource {
+    rx: std_mpsc::Receiver<Vec<i16>>,
+    buffer: std::vec::IntoIter<i16>,
+    sample_rate: u32,
+    channels: u16,
+}
+
+impl Iterator for PcmStreamSource {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        loop {
+            if let Some(sample) = self.buffer.next() {
+                return Some(sample);
+            }
+            match self.rx.try_recv() {
+                Ok(chunk) => self.buffer = chunk.into_iter(),
+                Err(std_mpsc::TryRecvError::Empty) => return Some(0),
+                Err(std_mpsc::TryRecvError::Disconnected) => return None,
+            }
+        }
+    }
+}
+
+impl Source for PcmStreamSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
@@ -0,0 +1,614 @@
+//! Confirming OpenCode's tool calls by voice
+//!
+//! When OpenCode wants permission to run a command or edit a file it posts a
+//! `permission.updated` event and waits. For the session this app is talking
+//! to, the request is assessed here (the same danger patterns the frontend
+//! used to check) and decided by the permission policy (see `policy`): allowed
+//! straight away, refused, or read out as a question. Without a policy rule
+//! for it, a request is asked about unless the matching "require
+//! confirmation" setting is off.
+//!
+//! The answer can be spoken ("yes", "no", "details"), given with the approve
+//! and deny hotkeys or the confirmation dialog. Only speech that starts after
+//! the question has finished playing counts, so the prompt's own "proceed"
+//! leaking past echo suppression isn't taken as a yes, and only a short reply
+//! is an answer. If nothing is said before the timeout the request is denied.
+//! The decision goes back to OpenCode through its permission API. One request
+//! is asked about at a time; a request answered in another OpenCode client
+//! meanwhile is dropped silently.
+
+use crate::audit;
+use crate::http::{self, Endpoint};
+use crate::policy::{self, Action, Verdict};
+use crate::tts;
+use crate::voice_profile::Role;
+use crate::{history, SharedState, Settings};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::mpsc;
+
+// Shortest timeout allowed, so a typo in the settings can't deny everything at once
+const MIN_TIMEOUT_SECS: u32 = 5;
+
+// Anything longer is talking about the request rather than answering it
+const MAX_ANSWER_WORDS: usize = 4;
+
+const REPEAT_ANSWER_PROMPT: &str = "Sorry, I didn't understand. Please say yes, no, or details.";
+
+// Requests are asked about one at a time, in the order they came in
+static TURN: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+// The request being asked about, and where its answers go
+static PENDING: Mutex<Option<Pending>> = Mutex::new(None);
+
+// Requests answered in another client before their turn came
+static ANSWERED_ELSEWHERE: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+struct Pending {
+    id: String,
+    replies: mpsc::UnboundedSender<Reply>,
+    // When the last question finished playing; None while one is being read out
+    listening_from: Option<Instant>,
+}
+
+enum Reply {
+    Answer(Answer, AnswerSource),
+    // Said or typed, but not an answer: the question is asked again
+    Unclear,
+    Elsewhere,
+}
+
+/// What was said (or clicked) in reply to a confirmation prompt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Answer {
+    Yes,
+    No,
+    Details,
+}
+
+/// How a confirmation was decided
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnswerSource {
+    Voice,
+    Hotkey,
+    /// The confirmation dialog, or text typed while it was open
+    Window,
+    /// The interrupt hotkey or the tray's Stop Speaking
+    Interrupt,
+    Timeout,
+    /// No confirmation needed with the current settings
+    Settings,
+    /// Decided by a rule in the policy file
+    Policy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+}
+
+/// A permission request from OpenCode
+#[derive(Debug, Clone)]
+pub struct PermissionRequest {
+    pub id: String,
+    pub session_id: String,
+    /// Kind of permission (the tool asking): "bash", "edit", "webfetch", ...
+    pub tool: String,
+    pub title: String,
+    pub metadata: serde_json::Value,
+}
+
+impl PermissionRequest {
+    /// Read a request from a `permission.updated` event's properties
+    pub fn from_event(properties: &serde_json::Value) -> Option<Self> {
+        Some(Self {
+            id: properties["id"].as_str()?.to_string(),
+            session_id: properties["sessionID"].as_str()?.to_string(),
+            tool: properties["type"].as_str().unwrap_or_default().to_string(),
+            title: properties["title"].as_str().unwrap_or_default().to_string(),
+            metadata: properties["metadata"].clone(),
+        })
+    }
+
+    fn metadata_str(&self, keys: &[&str]) -> Option<&str> {
+        keys.iter().find_map(|key| self.metadata[*key].as_str())
+    }
+
+    /// The shell command, for `bash` requests
+    pub fn command(&self) -> &str {
+        self.metadata_str(&["command"]).unwrap_or(&self.title)
+    }
+
+    /// The file, for `edit` and `write` requests
+    pub fn path(&self) -> &str {
+        self.metadata_str(&["filePath", "filepath", "path"]).unwrap_or(&self.title)
+    }
+}
+
+/// How a request is described and whether to ask about it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Assessment {
+    /// "bash", "git" or "write" for the kinds the settings cover, otherwise
+    /// the request's own type
+    pub tool_name: String,
+    pub description: String,
+    pub severity: Severity,
+    pub ask: bool,
+}
+
+/// What the confirmation dialog shows
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Prompt<'a> {
+    id: &'a str,
+    tool_name: &'a str,
+    description: &'a str,
+    severity: Severity,
+    details: &'a str,
+    timeout_secs: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Resolution<'a> {
+    id: &'a str,
+    approved: bool,
+    via: AnswerSource,
+}
+
+type Patterns = Vec<(Regex, &'static str, Severity)>;
+
+fn compile(patterns: &[(&str, &'static str, Severity)]) -> Patterns {
+    patterns
+        .iter()
+        .map(|(pattern, description, severity)| (Regex::new(pattern).unwrap(), *description, *severity))
+        .collect()
+}
+
+static SHELL_PATTERNS: Lazy<Patterns> = Lazy::new(|| {
+    compile(&[
+        (r"rm\s+-rf?", "Delete files/directories", Severity::High),
+        (r"rm\s+", "Delete files", Severity::Medium),
+        (r">\s*/", "Overwrite system files", Severity::High),
+        (r"sudo\s+", "Run with elevated permissions", Severity::High),
+        (r"chmod\s+", "Change file permissions", Severity::Medium),
+        (r"chown\s+", "Change file ownership", Severity::Medium),
+        (r"mv\s+.*/dev/null", "Move to null device", Severity::High),
+        (r":\s*>\s*", "Truncate file", Severity::Medium),
+        (r"mkfs", "Format filesystem", Severity::High),
+        (r"dd\s+", "Direct disk write", Severity::High),
+        (r"curl.*\|\s*(?:ba)?sh", "Execute remote script", Severity::High),
+        (r"wget.*\|\s*(?:ba)?sh", "Execute remote script", Severity::High),
+        (r"npm\s+publish", "Publish package", Severity::Medium),
+        (r"npx\s+", "Execute npm package", Severity::Low),
+    ])
+});
+
+static GIT_PATTERNS: Lazy<Patterns> = Lazy::new(|| {
+    compile(&[
+        (r"push.*--force", "Force push to remote", Severity::High),
+        (r"push.*-f\b", "Force push to remote", Severity::High),
+        (r"reset.*--hard", "Hard reset (loses changes)", Severity::High),
+        (r"clean.*-fd", "Delete untracked files", Severity::Medium),
+        (r"branch.*-[dD]", "Delete branch", Severity::Low),
+        (r"checkout\s+--\s+\.", "Discard all changes", Severity::Medium),
+        (r"stash\s+drop", "Delete stash", Severity::Low),
+        (r"rebase", "Rebase commits", Severity::Medium),
+    ])
+});
+
+static FILE_PATTERNS: Lazy<Patterns> = Lazy::new(|| {
+    compile(&[
+        (r"\.env", "Environment file", Severity::High),
+        (r"credentials", "Credentials file", Severity::High),
+        (r"secrets?", "Secrets file", Severity::High),
+        (r"\.ssh", "SSH configuration", Severity::High),
+        (r"\.aws", "AWS configuration", Severity::High),
+        (r"\.npmrc", "NPM configuration", Severity::Medium),
+        (r"package\.json", "Package manifest", Severity::Low),
+        (r"tsconfig\.json", "TypeScript config", Severity::Low),
+    ])
+});
+
+fn first_match(patterns: &Patterns, text: &str) -> Option<(&'static str, Severity)> {
+    patterns
+        .iter()
+        .find(|(re, _, _)| re.is_match(text))
+        .map(|(_, description, severity)| (*description, *severity))
+}
+
+/// Describe a request and decide, from the confirmation settings, whether to
+/// ask about it. Kinds the settings don't cover are always asked about.
+pub fn assess(request: &PermissionRequest, settings: &Settings) -> Assessment {
+    let assessment = |tool_name: &str, description: String, severity, ask| Assessment {
+        tool_name: tool_name.to_string(),
+        description,
+        severity,
+        ask,
+    };
+
+    match request.tool.as_str() {
+        "bash" => {
+            let command = request.command();
+            if command.contains("git ") {
+                let (description, severity) = first_match(&GIT_PATTERNS, command).unwrap_or(("Run", Severity::Low));
+                return assessment(
+                    "git",
+                    format!("{}: {}", description, command),
+                    severity,
+                    settings.confirm_git_operations,
+                );
+            }
+            let (description, severity) = first_match(&SHELL_PATTERNS, command).unwrap_or(("Run", Severity::Low));
+            assessment(
+                "bash",
+                format!("{}: {}", description, command),
+                severity,
+                settings.confirm_shell_commands,
+            )
+        }
+        "edit" | "write" | "patch" => {
+            let path = request.path();
+            let description = match first_match(&FILE_PATTERNS, path) {
+                Some((description, severity)) => (format!("Writing to {}: {}", description, path), severity),
+                None => (format!("Create/modify file: {}", path), Severity::Low),
+            };
+            assessment("write", description.0, description.1, settings.confirm_file_writes)
+        }
+        other => assessment(other, request.title.clone(), Severity::Low, true),
+    }
+}
+
+/// The question read out for a request
+pub fn prompt_text(assessment: &Assessment) -> String {
+    let prefix = match assessment.severity {
+        Severity::High => "Warning! ",
+        Severity::Medium => "Attention: ",
+        Severity::Low => "",
+    };
+    format!("{}I'd like to {}. Should I proceed?", prefix, lowercase_first(&assessment.description))
+}
+
+fn lowercase_first(text: &str) -> String {
+    let mut text = text.to_string();
+    if let Some(first) = text.get(..1) {
+        text.replace_range(..1, &first.to_lowercase());
+    }
+    text
+}
+
+/// What "details" reads out: the full command, the file and size of the
+/// change, or the address
+pub fn details_text(request: &PermissionRequest) -> String {
+    match request.tool.as_str() {
+        "bash" => format!("The command is: {}", request.command()),
+        "edit" | "write" | "patch" => {
+            let path = request.path();
+            match request.metadata["diff"].as_str() {
+                Some(diff) => {
+                    let count = |sign: char, header: &str| {
+                        diff.lines()
+                            .filter(|line| line.starts_with(sign) && !line.starts_with(header))
+                            .count()
+                    };
+                    format!(
+                        "It changes {}, adding {} lines and removing {}.",
+                        path,
+                        count('+', "+++"),
+                        count('-', "---")
+                    )
+                }
+                None => format!("The file is {}.", path),
+            }
+        }
+        "webfetch" => match request.metadata_str(&["url"]) {
+            Some(url) => format!("The address is {}.", url),
+            None => request.title.clone(),
+        },
+        _ => request.title.clone(),
+    }
+}
+
+/// Read a spoken or typed reply to a confirmation prompt
+///
+/// Refusals are checked first, so "don't do it" and "not sure" aren't taken
+/// as a yes. Replies of more than a few words aren't answers.
+pub fn parse_answer(text: &str) -> Option<Answer> {
+    const NO: &[&str] = &[
+        "no", "nope", "nah", "not", "don't", "dont", "stop", "cancel", "abort", "deny", "reject", "negative",
+        "skip", "pass", "never mind", "forget it", "hold on", "wait",
+    ];
+    const DETAILS: &[&str] = &[
+        "details", "detail", "more", "explain", "what is it", "what's that", "which file", "what command",
+        "show me", "read it",
+    ];
+    const YES: &[&str] = &[
+        "yes", "yeah", "yep", "yup", "sure", "ok", "okay", "go ahead", "do it", "proceed", "continue", "confirm",
+        "approved", "approve", "allow", "affirmative", "absolutely", "definitely", "please do", "go for it",
+    ];
+
+    let words: Vec<String> = text
+        .split_whitespace()
+        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric() && c != '\'').to_lowercase())
+        .filter(|word| !word.is_empty())
+        .collect();
+    if words.len() > MAX_ANSWER_WORDS {
+        return None;
+    }
+    let padded = format!(" {} ", words.join(" "));
+    let says = |phrases: &[&str]| phrases.iter().any(|phrase| padded.contains(&format!(" {} ", phrase)));
+
+    if says(NO) {
+        Some(Answer::No)
+    } else if says(DETAILS) {
+        Some(Answer::Details)
+    } else if says(YES) {
+        Some(Answer::Yes)
+    } else {
+        None
+    }
+}
+
+/// Whether a confirmation prompt is waiting for an answer
+pub fn is_pending() -> bool {
+    PENDING.lock().unwrap().is_some()
+}
+
+/// Answer the pending prompt; false if there is none
+pub fn answer(answer: Answer, via: AnswerSource) -> bool {
+    match PENDING.lock().unwrap().as_ref() {
+        Some(pending) => pending.replies.send(Reply::Answer(answer, via)).is_ok(),
+        None => false,
+    }
+}
+
+/// Take what was said, starting at `spoken_at`, as the answer to the pending
+/// prompt. Speech that began before the question finished playing is ignored.
+pub fn answer_by_voice(text: &str, spoken_at: Instant) -> audit::Handling {
+    let pending = PENDING.lock().unwrap();
+    let Some(pending) = pending.as_ref() else {
+        return audit::Handling::Unclear;
+    };
+    if pending.listening_from.is_none_or(|from| spoken_at < from) {
+        eprintln!("[PERMISSION] Ignoring speech from before the question ended: {}", text);
+        return audit::Handling::Unclear;
+    }
+    match parse_answer(text) {
+        Some(answer) => {
+            let _ = pending.replies.send(Reply::Answer(answer, AnswerSource::Voice));
+            audit::Handling::Answer
+        }
+        None => {
+            let _ = pending.replies.send(Reply::Unclear);
+            audit::Handling::Unclear
+        }
+    }
+}
+
+/// The reply to the pending prompt wasn't understood, so ask again; false if
+/// there is none
+pub fn unclear() -> bool {
+    match PENDING.lock().unwrap().as_ref() {
+        Some(pending) => pending.replies.send(Reply::Unclear).is_ok(),
+        None => false,
+    }
+}
+
+/// A request was answered in another OpenCode client
+pub fn replied_elsewhere(id: &str) {
+    let pending = PENDING.lock().unwrap();
+    match pending.as_ref() {
+        Some(pending) if pending.id == id => {
+            let _ = pending.replies.send(Reply::Elsewhere);
+        }
+        _ => {
+            ANSWERED_ELSEWHERE.lock().unwrap().insert(id.to_string());
+        }
+    }
+}
+
+/// Handle a permission request in the background
+pub fn request(app: &AppHandle, request: PermissionRequest) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let _turn = TURN.lock().await;
+        if ANSWERED_ELSEWHERE.lock().unwrap().remove(&request.id) {
+            return;
+        }
+        confirm(&app, &request).await;
+    });
+}
+
+async fn confirm(app: &AppHandle, request: &PermissionRequest) {
+    let state = app.state::<SharedState>().inner().clone();
+    let (settings, session_id) = {
+        let app_state = state.lock().unwrap();
+        (app_state.settings.clone(), app_state.current_session_id.clone())
+    };
+    // Other sessions belong to whoever is using them in another client
+    if session_id.as_deref() != Some(request.session_id.as_str()) {
+        return;
+    }
+
+    let assessment = assess(request, &settings);
+    let verdict = policy::evaluate(request, &assessment);
+    eprintln!(
+        "[PERMISSION] {} ({:?}): {:?} by {}{}",
+        assessment.description,
+        assessment.severity,
+        verdict.action,
+        verdict.rule.as_deref().unwrap_or("settings"),
+        if verdict.downgraded { ", policy file has errors" } else { "" }
+    );
+    audit::record(
+        Some(&request.session_id),
+        audit::Event::Permission {
+            permission_id: request.id.clone(),
+            tool: assessment.tool_name.clone(),
+            description: assessment.description.clone(),
+            severity: assessment.severity,
+            action: verdict.action,
+            rule: verdict.rule.clone(),
+        },
+    );
+    let decided_by = if verdict.rule.is_some() {
+        AnswerSource::Policy
+    } else {
+        AnswerSource::Settings
+    };
+    match verdict.action {
+        Action::Allow => return decide(app, &settings, request, &assessment, &verdict, true, decided_by).await,
+        Action::Deny => return decide(app, &settings, request, &assessment, &verdict, false, decided_by).await,
+        Action::Ask => {}
+    }
+
+    let (replies_tx, mut replies) = mpsc::unbounded_channel();
+    *PENDING.lock().unwrap() = Some(Pending {
+        id: request.id.clone(),
+        replies: replies_tx,
+        listening_from: None,
+    });
+
+    let timeout_secs = settings.confirmation_timeout_secs.max(MIN_TIMEOUT_SECS);
+    let details = details_text(request);
+    let _ = app.emit(
+        "confirmation-requested",
+        Prompt {
+            id: &request.id,
+            tool_name: &assessment.tool_name,
+            description: &assessment.description,
+            severity: assessment.severity,
+            details: &details,
+            timeout_secs,
+        },
+    );
+    ask(&state, app, request, &prompt_text(&assessment)).await;
+
+    // The timeout starts over after each question that follows
+    let decision = loop {
+        match tokio::time::timeout(Duration::from_secs(timeout_secs as u64), replies.recv()).await {
+            Ok(Some(Reply::Answer(Answer::Details, _))) => {
+                ask(&state, app, request, &format!("{} Should I proceed?", details)).await;
+            }
+            Ok(Some(Reply::Unclear)) => ask(&state, app, request, REPEAT_ANSWER_PROMPT).await,
+            Ok(Some(Reply::Answer(answer, via))) => break Some((answer == Answer::Yes, via)),
+            Ok(Some(Reply::Elsewhere)) | Ok(None) => break None,
+            Err(_) => break Some((false, AnswerSource::Timeout)),
+        }
+    };
+    *PENDING.lock().unwrap() = None;
+
+    match decision {
+        Some((approved, via)) => decide(app, &settings, request, &assessment, &verdict, approved, via).await,
+        None => {
+            eprintln!("[PERMISSION] Answered in another client");
+            let _ = app.emit("confirmation-resolved", serde_json::json!({ "id": request.id }));
+        }
+    }
+}
+
+// Read out a question about a request, and listen for the answer once it
+// has played
+async fn ask(state: &SharedState, app: &AppHandle, request: &PermissionRequest, text: &str) {
+    set_listening_from(None);
+    audit::record(
+        Some(&request.session_id),
+        audit::Event::Prompt {
+            permission_id: request.id.clone(),
+            text: text.to_string(),
+        },
+    );
+    crate::speak_notice(state, app, text, Role::Tool).await;
+    set_listening_from(Some(tts::played_out().await));
+}
+
+fn set_listening_from(from: Option<Instant>) {
+    if let Some(pending) = PENDING.lock().unwrap().as_mut() {
+        pending.listening_from = from;
+    }
+}
+
+// Send the decision to OpenCode, say what happens and record it
+async fn decide(
+    app: &AppHandle,
+    settings: &Settings,
+    request: &PermissionRequest,
+    assessment: &Assessment,
+    verdict: &Verdict,
+    approved: bool,
+    via: AnswerSource,
+) {
+    if let Err(e) = reply(&settings.server_url, request, approved).await {
+        eprintln!("[PERMISSION] {}", e);
+        let _ = app.emit("voice-error", e);
+    }
+    let _ = app.emit(
+        "confirmation-resolved",
+        Resolution {
+            id: &request.id,
+            approved,
+            via,
+        },
+    );
+
+    audit::record(
+        Some(&request.session_id),
+        audit::Event::Decision {
+            permission_id: request.id.clone(),
+            approved,
+            via,
+            rule: verdict.rule.clone(),
+        },
+    );
+    let mut entry = history::NewEntry::new(history::EntryKind::Confirmation, assessment.description.as_str());
+    entry.detail = Some(serde_json::json!({
+        "toolName": assessment.tool_name,
+        "severity": assessment.severity,
+        "approved": approved,
+        "via": via,
+        "rule": verdict.rule,
+    }));
+    crate::record_history(app, entry);
+
+    let notice = match (approved, via) {
+        (_, AnswerSource::Settings) | (_, AnswerSource::Interrupt) | (true, AnswerSource::Policy) => return,
+        (false, AnswerSource::Policy) => "The permission policy doesn't allow that, so I refused.",
+        (true, _) => "Okay, proceeding.",
+        (false, AnswerSource::Timeout) => "I didn't hear an answer, so I won't do that.",
+        (false, _) => "Okay, I won't do that.",
+    };
+    let state = app.state::<SharedState>().inner().clone();
+    crate::speak_notice(&state, app, notice, Role::System).await;
+}
+
+async fn reply(server_url: &str, request: &PermissionRequest, approved: bool) -> Result<(), String> {
+    let url = format!(
+        "{}/session/{}/permissions/{}",
+        server_url.trim_end_matches('/'),
+        request.session_id,
+        request.id
+    );
+    let body = serde_json::json!({ "response": if approved { "once" } else { "reject" } });
+    // Not retried: OpenCode may have taken an answer whose response timed out,
+    // and would reject the second one
+    let response = http::send_once(Endpoint::OpenCode, http::client().post(url).json(&body))
+        .await
+        .map_err(|e| format!("Couldn't answer OpenCode's permission request: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "OpenCode didn't accept the permission answer: HTTP {}",
+            response.status()
+        ));
+    }
+    Ok(())
+}
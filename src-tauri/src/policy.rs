@@ -0,0 +1,619 @@
+//! Permission policy: rules that decide OpenCode's permission requests
+//!
+//! The three "require confirmation" settings only say whether shell commands,
+//! git commands and file writes are asked about. A policy file in the config
+//! directory (`policy.toml`, or `policy.json` with the same shape) can say more
+//! precisely what is allowed, asked about or refused:
+//!
+//! ```toml
+//! [[rules]]
+//! name = "tests"
+//! action = "allow"
+//! tool = "bash"
+//! command = "^cargo test(\\s+[\\w=.-]+)*$"
+//!
+//! [[rules]]
+//! action = "ask"
+//! command = "^git push\\b"
+//!
+//! [[rules]]
+//! action = "deny"
+//! command = "rm\\s+-[a-z]*r[a-z]*f?\\s+/(\\s|$)"
+//!
+//! [[rules]]
+//! name = "project writes"
+//! action = "allow"
+//! tool = ["edit", "write", "patch"]
+//! path = "{project}/**"
+//!
+//! [[rules]]
+//! action = "deny"
+//! tool = ["edit", "write", "patch"]
+//! ```
+//!
+//! Rules are tried in order and the first one whose conditions all hold
+//! decides. `tool` is the request's type or the kind it was assessed as
+//! ("bash", "git", "write", "webfetch", ...), `command` a regex for the shell
+//! command, `path` globs for the file (relative paths are taken from the
+//! project, `{project}` is the project directory) and `severity` the lowest
+//! assessed severity the rule applies to. A request no rule matches is
+//! decided by the settings as before.
+//!
+//! Shell commands are split where another command can start (`;`, `&&`,
+//! `||`, `|`, `&`, newlines, `$(`, `<(`, `>(` and backticks). An allow rule's
+//! `command` has to match every one of them, so `cargo test && rm -rf ~` isn't
+//! allowed by a rule for `cargo test`; an ask or deny rule's matches if any
+//! does. An allow rule also doesn't match a command that redirects output to
+//! a file (`cargo test > ~/.bashrc`) unless it says `redirects = true`.
+//!
+//! A policy file with mistakes in it is still used, but nothing is allowed
+//! without asking while it has them, so a deny rule that failed to load can't
+//! let a request through.
+
+use crate::config_file;
+use crate::permissions::{Assessment, PermissionRequest, Severity};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+const FILE_NAMES: &[&str] = &["policy.toml", "policy.json"];
+
+// Where another command can start in a shell command line
+static COMMAND_SEPARATORS: Lazy<Regex> = Lazy::new(|| Regex::new(r"&&|\|\||\$\(|<\(|>\(|[;|&\n`]").unwrap());
+
+// Copying one output stream to another (`2>&1`) or closing it (`>&-`)
+static STREAM_DUPLICATION: Lazy<Regex> = Lazy::new(|| Regex::new(r"\d*[<>]&(\d+|-)").unwrap());
+
+// An output redirection (`>`, `>>`, `2>`, `&>`, `>|`) and where it goes
+static OUTPUT_REDIRECT: Lazy<Regex> = Lazy::new(|| Regex::new(r">>?\|?\s*(\S*)").unwrap());
+
+// Project that `{project}` and relative paths refer to
+static PROJECT_DIR: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+
+// Compiled policy, rebuilt when its file or the project changes
+static CACHE: config_file::Cache<Policy, Option<PathBuf>> = config_file::Cache::new();
+
+/// On-disk policy file format
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct PolicyFile {
+    rules: Vec<RuleEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleEntry {
+    name: Option<String>,
+    action: Action,
+    #[serde(default)]
+    tool: OneOrMany,
+    command: Option<String>,
+    #[serde(default)]
+    path: OneOrMany,
+    severity: Option<Severity>,
+    #[serde(default)]
+    redirects: bool,
+}
+
+// `tool = "bash"` or `tool = ["edit", "write"]`
+#[derive(Debug, Default, Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    #[default]
+    None,
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OneOrMany {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            OneOrMany::None => Vec::new(),
+            OneOrMany::One(value) => vec![value],
+            OneOrMany::Many(values) => values,
+        }
+    }
+}
+
+/// What a rule does with the requests it matches
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Allow,
+    Ask,
+    Deny,
+}
+
+struct Rule {
+    name: String,
+    action: Action,
+    tools: Vec<String>,
+    command: Option<Regex>,
+    // None: any file or none; empty: `{project}` globs with no project open
+    paths: Option<Vec<glob::Pattern>>,
+    severity: Option<Severity>,
+    // An allow rule may match commands that write output to files
+    redirects: bool,
+}
+
+#[derive(Default)]
+struct Policy {
+    rules: Vec<Rule>,
+    file: Option<String>,
+    errors: Vec<String>,
+}
+
+/// How a request is to be handled, and why
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Verdict {
+    pub action: Action,
+    /// Name of the rule that decided, or None when the settings did
+    pub rule: Option<String>,
+    /// An allow was turned into ask because the policy file has errors
+    pub downgraded: bool,
+}
+
+/// The result of a dry run, for the settings window
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Evaluation {
+    pub verdict: Verdict,
+    pub assessment: Assessment,
+    /// Where the policy file is read from (it may not exist yet)
+    pub path: Option<String>,
+    /// Policy file that was loaded, if any
+    pub file: Option<String>,
+    /// Problems found while loading it (bad syntax, invalid regexes or globs)
+    pub errors: Vec<String>,
+}
+
+/// Set the project directory that `{project}` and relative paths refer to
+pub fn set_project_dir(dir: Option<&str>) {
+    *PROJECT_DIR.lock().unwrap() = dir.filter(|d| !d.is_empty()).map(PathBuf::from);
+}
+
+/// Path of the policy file (may not exist yet)
+pub fn global_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("opencode-talk").join(FILE_NAMES[0]))
+}
+
+/// Decide a request: by the first matching rule, otherwise by the settings
+/// (through the assessment's `ask`)
+pub fn evaluate(request: &PermissionRequest, assessment: &Assessment) -> Verdict {
+    let project = PROJECT_DIR.lock().unwrap().clone();
+    current().evaluate(request, assessment, project.as_deref())
+}
+
+/// Evaluate a request without acting on it, with the policy file's state
+pub fn dry_run(request: &PermissionRequest, assessment: Assessment) -> Evaluation {
+    let verdict = evaluate(request, &assessment);
+    let policy = current();
+    Evaluation {
+        verdict,
+        assessment,
+        path: global_path().map(|p| p.display().to_string()),
+        file: policy.file.clone(),
+        errors: policy.errors.clone(),
+    }
+}
+
+impl Policy {
+    fn evaluate(&self, request: &PermissionRequest, assessment: &Assessment, project: Option<&Path>) -> Verdict {
+        let (action, rule) = match self
+            .rules
+            .iter()
+            .find(|rule| rule.matches(request, assessment, project))
+        {
+            Some(rule) => (rule.action, Some(rule.name.clone())),
+            None if assessment.ask => (Action::Ask, None),
+            None => (Action::Allow, None),
+        };
+
+        let downgraded = action == Action::Allow && !self.errors.is_empty();
+        Verdict {
+            action: if downgraded { Action::Ask } else { action },
+            rule,
+            downgraded,
+        }
+    }
+}
+
+impl Rule {
+    fn matches(&self, request: &PermissionRequest, assessment: &Assessment, project: Option<&Path>) -> bool {
+        if !self.tools.is_empty()
+            && !self
+                .tools
+                .iter()
+                .any(|tool| *tool == request.tool || *tool == assessment.tool_name)
+        {
+            return false;
+        }
+        if let Some(minimum) = self.severity {
+            if assessment.severity < minimum {
+                return false;
+            }
+        }
+        if let Some(command) = &self.command {
+            if request.tool != "bash" {
+                return false;
+            }
+            let line = request.command();
+            let parts = subcommands(line);
+            let matched = match self.action {
+                Action::Allow => {
+                    !parts.is_empty()
+                        && parts.iter().all(|part| command.is_match(part))
+                        && (self.redirects || !writes_to_file(line))
+                }
+                Action::Ask | Action::Deny => command.is_match(line) || parts.iter().any(|part| command.is_match(part)),
+            };
+            if !matched {
+                return false;
+            }
+        }
+        if let Some(paths) = &self.paths {
+            let Some(path) = request_path(request, project) else {
+                return false;
+            };
+            let options = glob::MatchOptions {
+                case_sensitive: true,
+                require_literal_separator: true,
+                require_literal_leading_dot: false,
+            };
+            if !paths.iter().any(|glob| glob.matches_path_with(&path, options)) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+// The commands in a shell command line. Separators inside quotes split it
+// too, which only means an allow rule asks more often.
+fn subcommands(line: &str) -> Vec<String> {
+    // `2>&1` isn't a command running in the background
+    let line = STREAM_DUPLICATION.replace_all(line, " ");
+    COMMAND_SEPARATORS
+        .split(&line)
+        .map(|part| part.trim_matches(|c: char| c.is_whitespace() || c == '(' || c == ')'))
+        .filter(|part| !part.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// Whether a command line writes output to a file. Duplicating a stream
+// (`2>&1`) or throwing it away (`> /dev/null`) doesn't count.
+fn writes_to_file(line: &str) -> bool {
+    OUTPUT_REDIRECT.captures_iter(line).any(|redirect| {
+        let target = &redirect[1];
+        !(target.starts_with('&') || target == "/dev/null")
+    })
+}
+
+// The file a request touches, absolute and without `.` or `..`, so
+// "{project}/../elsewhere" can't pass for a project path
+fn request_path(request: &PermissionRequest, project: Option<&Path>) -> Option<PathBuf> {
+    if !matches!(request.tool.as_str(), "edit" | "write" | "patch") {
+        return None;
+    }
+    let path = Path::new(request.path());
+    let path = match project {
+        Some(project) if path.is_relative() => project.join(path),
+        _ => path.to_path_buf(),
+    };
+
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    Some(normalized)
+}
+
+// The policy, rebuilt if the file or the project changed
+fn current() -> Arc<Policy> {
+    let candidates = global_path()
+        .as_ref()
+        .and_then(|p| p.parent())
+        .map(|dir| config_file::candidates(dir, FILE_NAMES))
+        .unwrap_or_default();
+    let project = PROJECT_DIR.lock().unwrap().clone();
+
+    CACHE.get(candidates, project, |existing, project| {
+        let policy = Policy::load(existing.first().copied(), project.as_deref());
+        for error in &policy.errors {
+            eprintln!("[POLICY] {}", error);
+        }
+        policy
+    })
+}
+
+impl Policy {
+    fn load(path: Option<&Path>, project: Option<&Path>) -> Self {
+        let Some(path) = path else {
+            return Policy::default();
+        };
+
+        match config_file::read(path) {
+            Ok(file) => {
+                let mut policy = Policy::compile(file, project);
+                policy.file = Some(path.display().to_string());
+                policy
+            }
+            Err(e) => Policy {
+                errors: vec![format!("{}: {}", path.display(), e)],
+                ..Policy::default()
+            },
+        }
+    }
+
+    fn compile(file: PolicyFile, project: Option<&Path>) -> Self {
+        let mut policy = Policy::default();
+        for (i, entry) in file.rules.into_iter().enumerate() {
+            let name = entry.name.clone().unwrap_or_else(|| format!("rule {}", i + 1));
+            match Rule::compile(name.clone(), entry, project) {
+                Ok(rule) => policy.rules.push(rule),
+                Err(e) => policy.errors.push(format!("{}: {}", name, e)),
+            }
+        }
+
+        policy
+    }
+}
+
+impl Rule {
+    fn compile(name: String, entry: RuleEntry, project: Option<&Path>) -> Result<Self, String> {
+        let command = entry
+            .command
+            .map(|pattern| Regex::new(&pattern).map_err(|e| format!("invalid command {:?}: {}", pattern, e)))
+            .transpose()?;
+
+        let patterns = entry.path.into_vec();
+        let mut paths = Vec::new();
+        for pattern in &patterns {
+            let expanded = if pattern.contains("{project}") {
+                // Without a project there is nothing for the rule to match
+                let Some(project) = project else {
+                    continue;
+                };
+                pattern.replace("{project}", &glob::Pattern::escape(&project.display().to_string()))
+            } else {
+                pattern.to_string()
+            };
+            let expanded = match project {
+                Some(project) if Path::new(&expanded).is_relative() => {
+                    format!("{}/{}", glob::Pattern::escape(&project.display().to_string()), expanded)
+                }
+                _ => expanded,
+            };
+            paths.push(glob::Pattern::new(&expanded).map_err(|e| format!("invalid path {:?}: {}", pattern, e))?);
+        }
+
+        Ok(Self {
+            name,
+            action: entry.action,
+            tools: entry.tool.into_vec(),
+            command,
+            paths: (!patterns.is_empty()).then_some(paths),
+            severity: entry.severity,
+            redirects: entry.redirects,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(toml: &str, project: Option<&Path>) -> Policy {
+        Policy::compile(toml::from_str(toml).unwrap(), project)
+    }
+
+    fn request(tool: &str, command: Option<&str>, path: Option<&str>) -> PermissionRequest {
+        PermissionRequest {
+            id: "per_1".to_string(),
+            session_id: "ses_1".to_string(),
+            tool: tool.to_string(),
+            title: String::new(),
+            metadata: serde_json::json!({ "command": command, "filePath": path }),
+        }
+    }
+
+    // As `permissions::assess` describes requests, with the settings asking
+    fn assessment(request: &PermissionRequest) -> Assessment {
+        Assessment {
+            tool_name: request.tool.clone(),
+            description: String::new(),
+            severity: Severity::Low,
+            ask: true,
+        }
+    }
+
+    fn evaluate(policy: &Policy, request: &PermissionRequest, project: Option<&Path>) -> Verdict {
+        policy.evaluate(request, &assessment(request), project)
+    }
+
+    fn bash(policy: &Policy, command: &str) -> Verdict {
+        evaluate(policy, &request("bash", Some(command), None), None)
+    }
+
+    #[test]
+    fn first_matching_rule_decides() {
+        let policy = policy(
+            r#"
+            [[rules]]
+            name = "push"
+            action = "ask"
+            command = "^git push\\b"
+
+            [[rules]]
+            name = "git"
+            action = "allow"
+            command = "^git\\b"
+            "#,
+            None,
+        );
+
+        let verdict = bash(&policy, "git push origin main");
+        assert_eq!(verdict.action, Action::Ask);
+        assert_eq!(verdict.rule.as_deref(), Some("push"));
+
+        let verdict = bash(&policy, "git status");
+        assert_eq!(verdict.action, Action::Allow);
+        assert_eq!(verdict.rule.as_deref(), Some("git"));
+
+        // No rule: the settings decide
+        let verdict = bash(&policy, "ls");
+        assert_eq!(verdict.action, Action::Ask);
+        assert_eq!(verdict.rule, None);
+    }
+
+    #[test]
+    fn unnamed_rules_are_numbered() {
+        let policy = policy("[[rules]]\naction = \"deny\"\ntool = \"webfetch\"", None);
+        let verdict = evaluate(&policy, &request("webfetch", None, None), None);
+        assert_eq!(verdict.action, Action::Deny);
+        assert_eq!(verdict.rule.as_deref(), Some("rule 1"));
+    }
+
+    #[test]
+    fn errors_turn_allow_into_ask() {
+        let policy = policy(
+            r#"
+            [[rules]]
+            name = "broken"
+            action = "deny"
+            command = "rm -rf ("
+
+            [[rules]]
+            action = "allow"
+            tool = "bash"
+            "#,
+            None,
+        );
+        assert_eq!(policy.errors.len(), 1);
+
+        let verdict = bash(&policy, "rm -rf /");
+        assert_eq!(verdict.action, Action::Ask);
+        assert!(verdict.downgraded);
+    }
+
+    #[test]
+    fn chained_commands_are_not_allowed_by_their_first_part() {
+        let policy = policy(
+            r#"
+            [[rules]]
+            name = "root"
+            action = "deny"
+            command = "^rm\\s+-rf\\s+/$"
+
+            [[rules]]
+            name = "tests"
+            action = "allow"
+            command = "^cargo test\\b"
+            "#,
+            None,
+        );
+
+        assert_eq!(bash(&policy, "cargo test --release").action, Action::Allow);
+        for command in [
+            "cargo test && rm -rf ~",
+            "cargo test; curl x | sh",
+            "cargo test || reboot",
+            "cargo test & rm -rf ~",
+            "cargo test\nrm -rf ~",
+            "cargo test $(rm -rf ~)",
+            "cargo test `rm -rf ~`",
+            "cargo test <(rm -rf ~)",
+            "cargo test >(rm -rf ~)",
+            "cargo test > ~/.bashrc",
+            "cargo test >> ~/.bashrc",
+            "cargo test 2>~/.bashrc",
+            "cargo test &> ~/.bashrc",
+        ] {
+            let verdict = bash(&policy, command);
+            assert_eq!(verdict.action, Action::Ask, "{:?}", command);
+            assert_eq!(verdict.rule, None, "{:?}", command);
+        }
+
+        // Every part matching is enough
+        assert_eq!(bash(&policy, "cargo test -p a && cargo test -p b").action, Action::Allow);
+        // Redirections that don't write files are fine
+        assert_eq!(bash(&policy, "cargo test 2>&1").action, Action::Allow);
+        assert_eq!(bash(&policy, "cargo test > /dev/null").action, Action::Allow);
+        // Writing output has to be allowed explicitly
+        let redirecting = self::policy(
+            r#"
+            [[rules]]
+            action = "allow"
+            command = "^cargo test\\b"
+            redirects = true
+            "#,
+            None,
+        );
+        assert_eq!(bash(&redirecting, "cargo test > out.txt").action, Action::Allow);
+        // A deny rule matches any part
+        assert_eq!(bash(&policy, "cd build && rm -rf /").action, Action::Deny);
+    }
+
+    #[test]
+    fn request_paths_are_normalized() {
+        let project = Path::new("/home/me/project");
+        let path = |p: &str| request_path(&request("write", None, Some(p)), Some(project));
+
+        assert_eq!(path("src/main.rs"), Some(PathBuf::from("/home/me/project/src/main.rs")));
+        assert_eq!(path("./src/../Cargo.toml"), Some(PathBuf::from("/home/me/project/Cargo.toml")));
+        assert_eq!(path("../x"), Some(PathBuf::from("/home/me/x")));
+        assert_eq!(path("/home/me/project/../x"), Some(PathBuf::from("/home/me/x")));
+        assert_eq!(request_path(&request("bash", Some("ls"), None), Some(project)), None);
+    }
+
+    #[test]
+    fn project_globs_stay_inside_the_project() {
+        let project = Path::new("/home/me/project");
+        let policy = policy(
+            r#"
+            [[rules]]
+            name = "project writes"
+            action = "allow"
+            tool = ["edit", "write"]
+            path = "{project}/**"
+            "#,
+            Some(project),
+        );
+        let write = |p: &str| evaluate(&policy, &request("write", None, Some(p)), Some(project)).action;
+
+        assert_eq!(write("src/main.rs"), Action::Allow);
+        assert_eq!(write("/home/me/project/src/main.rs"), Action::Allow);
+        assert_eq!(write("../x"), Action::Ask);
+        assert_eq!(write("/home/me/project/../x"), Action::Ask);
+        assert_eq!(write("/etc/passwd"), Action::Ask);
+    }
+
+    #[test]
+    fn project_globs_without_a_project_match_nothing() {
+        let policy = policy(
+            r#"
+            [[rules]]
+            name = "project writes"
+            action = "allow"
+            tool = "write"
+            path = "{project}/**"
+            "#,
+            None,
+        );
+        assert!(policy.errors.is_empty());
+
+        let verdict = evaluate(&policy, &request("write", None, Some("/etc/passwd")), None);
+        assert_eq!(verdict.action, Action::Ask);
+        assert_eq!(verdict.rule, None);
+    }
+}
@@ -0,0 +1,289 @@
+//! Engine-neutral prosody markup
+//!
+//! The response formatter can shape delivery with a few inline tags:
+//!
+//! - `{{pause}}` / `{{pause 500ms}}` / `{{pause 1.5s}}` - a silence
+//! - `{{emphasis}}...{{/emphasis}}` - stress the enclosed words
+//! - `{{rate 0.8}}...{{/rate}}` - speak the enclosed words slower or faster
+//! - `{{spell}}...{{/spell}}` - read the enclosed text letter by letter
+//!
+//! Tags are swapped for placeholder characters before markdown normalization
+//! and the lexicon run (so neither can mangle them), then the prepared text is
+//! split into `Span`s. Engines with their own markup (`say`, espeak-ng's SSML)
+//! render the spans natively; the rest synthesize each span separately and the
+//! audio thread inserts the pauses.
+
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use std::time::Duration;
+
+// Silence for a bare {{pause}}
+const DEFAULT_PAUSE: Duration = Duration::from_millis(400);
+const MAX_PAUSE: Duration = Duration::from_secs(5);
+
+// Emphasis is approximated on engines without native support by slowing down
+const EMPHASIS_RATE: f32 = 0.85;
+
+// Placeholders live in the Unicode private use area, which no engine speaks
+// and none of the normalizer's patterns treat as word characters
+const PLACEHOLDER_BASE: u32 = 0xE000;
+const MAX_TAGS: usize = 0x1000;
+
+static SPELL_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)\{\{\s*spell\s*\}\}(.*?)\{\{\s*/spell\s*\}\}").unwrap());
+static TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{\{\s*(/?)\s*([a-z]+)(?:\s+([^{}]*?))?\s*\}\}").unwrap());
+
+/// A piece of a sentence with uniform delivery
+#[derive(Debug, Clone, PartialEq)]
+pub enum Span {
+    Text(TextSpan),
+    Pause(Duration),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextSpan {
+    pub text: String,
+    /// Speed multiplier on top of the user's speed setting
+    pub rate: f32,
+    pub emphasis: bool,
+    /// Read letter by letter
+    pub spell: bool,
+}
+
+impl TextSpan {
+    /// Text for engines without native spell-out: "API" -> "A P I"
+    pub fn spoken_text(&self) -> String {
+        if self.spell {
+            spelled_out(&self.text)
+        } else {
+            self.text.clone()
+        }
+    }
+
+    /// Speed multiplier for engines without native emphasis
+    pub fn effective_rate(&self) -> f32 {
+        if self.emphasis {
+            self.rate * EMPHASIS_RATE
+        } else {
+            self.rate
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Tag {
+    Pause(Duration),
+    EmphasisStart,
+    EmphasisEnd,
+    RateStart(f32),
+    RateEnd,
+    Spell(String),
+}
+
+/// Text with its markup tags replaced by placeholders
+pub struct Protected {
+    pub text: String,
+    tags: Vec<Tag>,
+}
+
+/// Swap markup tags for placeholder characters so the text can be normalized
+/// without the tags being read out or rewritten. Unknown tags are dropped.
+pub fn protect(text: &str) -> Protected {
+    let mut tags = Vec::new();
+
+    let text = SPELL_RE.replace_all(text, |c: &Captures| {
+        placeholder(&mut tags, Tag::Spell(c[1].trim().to_string()))
+    });
+    let text = TAG_RE.replace_all(&text, |c: &Captures| {
+        let closing = !c[1].is_empty();
+        let argument = c.get(3).map(|m| m.as_str().trim()).unwrap_or("");
+        let tag = match (&c[2], closing) {
+            ("pause", false) => Some(Tag::Pause(parse_pause(argument))),
+            ("emphasis", false) => Some(Tag::EmphasisStart),
+            ("emphasis", true) => Some(Tag::EmphasisEnd),
+            ("rate", false) => argument.parse::<f32>().ok().map(|r| Tag::RateStart(r.clamp(0.25, 4.0))),
+            ("rate", true) => Some(Tag::RateEnd),
+            _ => None,
+        };
+        tag.map(|tag| placeholder(&mut tags, tag)).unwrap_or_default()
+    });
+
+    Protected {
+        text: text.into_owned(),
+        tags,
+    }
+}
+
+impl Protected {
+    /// Split prepared text back into spans, resolving the placeholders
+    pub fn into_spans(self, prepared: &str) -> Vec<Span> {
+        let mut spans = Vec::new();
+        let mut current = String::new();
+        let mut emphasis = false;
+        let mut rates: Vec<f32> = Vec::new();
+
+        for c in prepared.chars() {
+            let Some(tag) = self.tag_for(c) else {
+                current.push(c);
+                continue;
+            };
+
+            let rate = rates.last().copied().unwrap_or(1.0);
+            push_text(&mut spans, std::mem::take(&mut current), rate, emphasis, false);
+
+            match tag {
+                Tag::Pause(duration) => spans.push(Span::Pause(*duration)),
+                Tag::EmphasisStart => emphasis = true,
+                Tag::EmphasisEnd => emphasis = false,
+                Tag::RateStart(r) => rates.push(*r),
+                Tag::RateEnd => {
+                    rates.pop();
+                }
+                Tag::Spell(text) => push_text(&mut spans, text.clone(), rate, emphasis, true),
+            }
+        }
+
+        let rate = rates.last().copied().unwrap_or(1.0);
+        push_text(&mut spans, current, rate, emphasis, false);
+        spans
+    }
+
+    fn tag_for(&self, c: char) -> Option<&Tag> {
+        let index = (c as u32).checked_sub(PLACEHOLDER_BASE)? as usize;
+        if index < MAX_TAGS {
+            self.tags.get(index)
+        } else {
+            None
+        }
+    }
+}
+
+/// The spans as plain text, for display
+pub fn plain_text(spans: &[Span]) -> String {
+    spans
+        .iter()
+        .filter_map(|span| match span {
+            Span::Text(t) => Some(t.spoken_text()),
+            Span::Pause(_) => None,
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Render spans for macOS `say`, which has its own embedded commands
+pub fn to_say_markup(spans: &[Span], base_rate_wpm: u32) -> String {
+    let mut out = String::new();
+
+    for span in spans {
+        match span {
+            Span::Pause(duration) => out.push_str(&format!("[[slnc {}]] ", duration.as_millis())),
+            Span::Text(t) => {
+                let text = t.text.replace("[[", "").replace("]]", "");
+                let rate = (base_rate_wpm as f32 * t.rate) as u32;
+                if rate != base_rate_wpm {
+                    out.push_str(&format!("[[rate {}]] ", rate));
+                }
+                if t.emphasis {
+                    out.push_str("[[emph +]] ");
+                }
+                if t.spell {
+                    out.push_str(&format!("[[char LTRL]] {} [[char NORM]] ", text));
+                } else {
+                    out.push_str(&text);
+                    out.push(' ');
+                }
+                if rate != base_rate_wpm {
+                    out.push_str(&format!("[[rate {}]] ", base_rate_wpm));
+                }
+            }
+        }
+    }
+
+    out.trim_end().to_string()
+}
+
+/// Render spans as SSML (espeak-ng with `-m`)
+pub fn to_ssml(spans: &[Span]) -> String {
+    let mut out = String::from("<speak>");
+
+    for span in spans {
+        match span {
+            Span::Pause(duration) => out.push_str(&format!("<break time=\"{}ms\"/>", duration.as_millis())),
+            Span::Text(t) => {
+                let mut text = escape_xml(&t.text);
+                if t.spell {
+                    text = format!("<say-as interpret-as=\"characters\">{}</say-as>", text);
+                }
+                if t.emphasis {
+                    text = format!("<emphasis>{}</emphasis>", text);
+                }
+                if t.rate != 1.0 {
+                    text = format!("<prosody rate=\"{}%\">{}</prosody>", (t.rate * 100.0).round(), text);
+                }
+                out.push_str(&text);
+                out.push(' ');
+            }
+        }
+    }
+
+    out.push_str("</speak>");
+    out
+}
+
+fn placeholder(tags: &mut Vec<Tag>, tag: Tag) -> String {
+    if tags.len() >= MAX_TAGS {
+        return String::new();
+    }
+    let c = char::from_u32(PLACEHOLDER_BASE + tags.len() as u32).unwrap_or(' ');
+    tags.push(tag);
+    // Spaced so a tag never glues two words into one identifier
+    format!(" {} ", c)
+}
+
+// Keep spans that contain something speakable ("." left between two tags isn't).
+// The normalizer ends a heading or paragraph after any trailing tag, so a span
+// can start with that stray punctuation.
+fn push_text(spans: &mut Vec<Span>, text: String, rate: f32, emphasis: bool, spell: bool) {
+    let text = text.trim_start_matches(|c: char| matches!(c, '.' | ',' | ';' | ':') || c.is_whitespace());
+    let text = text.trim_end();
+    if !text.chars().any(|c| c.is_alphanumeric()) {
+        return;
+    }
+    spans.push(Span::Text(TextSpan {
+        text: text.to_string(),
+        rate,
+        emphasis,
+        spell,
+    }));
+}
+
+// "500", "500ms", "1.5s"
+fn parse_pause(argument: &str) -> Duration {
+    let argument = argument.trim();
+    let parsed = if let Some(ms) = argument.strip_suffix("ms") {
+        ms.trim().parse::<f64>().ok().map(|ms| ms / 1000.0)
+    } else if let Some(s) = argument.strip_suffix('s') {
+        s.trim().parse::<f64>().ok()
+    } else {
+        argument.parse::<f64>().ok().map(|ms| ms / 1000.0)
+    };
+
+    parsed
+        .filter(|secs| secs.is_finite() && *secs >= 0.0)
+        .map(|secs| Duration::from_secs_f64(secs).min(MAX_PAUSE))
+        .unwrap_or(DEFAULT_PAUSE)
+}
+
+fn spelled_out(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
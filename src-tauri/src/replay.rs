@@ -0,0 +1,190 @@
+//! Replaying past answers
+//!
+//! While a message is replayed, every source the audio thread plays for it is
+//! wrapped in a `CaptureTap` that keeps a copy of the samples. Once all of
+//! them have played to the end, the audio is cached under the message, engine,
+//! voice and speed, so asking again plays it straight from memory instead of
+//! synthesizing it a second time. Playback that is stopped part way through
+//! isn't cached.
+
+use once_cell::sync::Lazy;
+use rodio::buffer::SamplesBuffer;
+use rodio::Source;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Replays kept in memory (a minute of 24kHz speech is under 3MB)
+const CACHE_SIZE: usize = 8;
+
+/// How much slower "repeat slower" speaks
+pub const SLOWER: f32 = 0.75;
+
+/// A stretch of played audio in the format it was played in
+#[derive(Debug, Clone)]
+pub struct Segment {
+    channels: u16,
+    sample_rate: u32,
+    samples: Vec<i16>,
+}
+
+impl Segment {
+    pub fn source(&self) -> SamplesBuffer<i16> {
+        SamplesBuffer::new(self.channels, self.sample_rate, self.samples.clone())
+    }
+}
+
+pub type CapturedAudio = Arc<Vec<Segment>>;
+
+// Most recently used first
+static CACHE: Lazy<Mutex<VecDeque<(String, CapturedAudio)>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
+
+/// Cache key for a message spoken with a given voice
+pub fn cache_key(message_id: i64, engine: &str, voice: &str, speed: f32, agent: &str) -> String {
+    format!("{}|{}|{}|{:.2}|{}", message_id, engine, voice, speed, agent)
+}
+
+/// Cached audio for a key, if it was played to the end before
+pub fn cached(key: &str) -> Option<CapturedAudio> {
+    let mut cache = CACHE.lock().unwrap();
+    let index = cache.iter().position(|(k, _)| k == key)?;
+    let entry = cache.remove(index)?;
+    let audio = entry.1.clone();
+    cache.push_front(entry);
+    Some(audio)
+}
+
+fn store(key: String, audio: Vec<Segment>) {
+    let mut cache = CACHE.lock().unwrap();
+    cache.retain(|(k, _)| *k != key);
+    cache.push_front((key, Arc::new(audio)));
+    cache.truncate(CACHE_SIZE);
+}
+
+#[derive(Default)]
+struct CaptureState {
+    // One slot per tapped source, filled in when it has played to the end
+    segments: Vec<Option<Segment>>,
+    // No more sources will be added
+    closed: bool,
+    // A source was dropped before it finished, or the audio is already cached
+    done: bool,
+}
+
+/// Audio being captured for one replay
+#[derive(Clone)]
+pub struct Capture {
+    key: String,
+    state: Arc<Mutex<CaptureState>>,
+}
+
+impl Capture {
+    pub fn begin(key: String) -> Self {
+        Self {
+            key,
+            state: Arc::default(),
+        }
+    }
+
+    /// Wrap a source so its samples are captured as the next segment
+    pub fn tap<S: Source<Item = i16>>(&self, source: S) -> CaptureTap<S> {
+        let mut state = self.state.lock().unwrap();
+        state.segments.push(None);
+        CaptureTap {
+            channels: source.channels(),
+            sample_rate: source.sample_rate(),
+            source,
+            samples: Vec::new(),
+            index: state.segments.len() - 1,
+            capture: self.clone(),
+            finished: false,
+        }
+    }
+
+    /// No more sources belong to this replay; it's cached once they've played
+    pub fn end(self) {
+        self.state.lock().unwrap().closed = true;
+        self.finish_if_complete();
+    }
+
+    /// Playback was stopped: whatever is still to come isn't the whole message
+    pub fn abandon(self) {
+        self.state.lock().unwrap().done = true;
+    }
+
+    fn finish_if_complete(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.done || !state.closed || state.segments.iter().any(Option::is_none) {
+            return;
+        }
+        state.done = true;
+        // Engines that play by themselves (`say`) leave nothing to cache
+        if !state.segments.is_empty() {
+            store(self.key.clone(), state.segments.drain(..).flatten().collect());
+        }
+    }
+}
+
+/// Passes a source through unchanged while keeping a copy for the cache
+pub struct CaptureTap<S> {
+    source: S,
+    channels: u16,
+    sample_rate: u32,
+    samples: Vec<i16>,
+    index: usize,
+    capture: Capture,
+    finished: bool,
+}
+
+impl<S: Source<Item = i16>> Iterator for CaptureTap<S> {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        match self.source.next() {
+            Some(sample) => {
+                self.samples.push(sample);
+                Some(sample)
+            }
+            None => {
+                if !self.finished {
+                    self.finished = true;
+                    let segment = Segment {
+                        channels: self.channels,
+                        sample_rate: self.sample_rate,
+                        samples: std::mem::take(&mut self.samples),
+                    };
+                    self.capture.state.lock().unwrap().segments[self.index] = Some(segment);
+                    self.capture.finish_if_complete();
+                }
+                None
+            }
+        }
+    }
+}
+
+impl<S: Source<Item = i16>> Source for CaptureTap<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
+}
+
+// Cleared from the sink (playback stopped) before the end
+impl<S> Drop for CaptureTap<S> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.capture.state.lock().unwrap().done = true;
+        }
+    }
+}
@@ -0,0 +1,487 @@
+//! Sentence segmentation for streaming TTS
+//!
+//! Text arrives from OpenCode as small deltas. `SentenceBuffer` accumulates them
+//! and hands back speakable chunks as soon as a chunk boundary is certain, so
+//! TTS can start on the first sentence while the rest is still streaming.
+//!
+//! Boundaries are sentence-ending punctuation followed by whitespace, list items,
+//! headings and paragraph breaks. Periods inside abbreviations, initials,
+//! decimals, version numbers, file paths and inline code don't end a sentence,
+//! and fenced code blocks are dropped entirely since they can't be spoken.
+
+// Common abbreviations that end with a period but aren't sentence endings
+const ABBREVIATIONS: &[&str] = &[
+    "dr", "mr", "mrs", "ms", "prof", "sr", "jr",
+    "vs", "etc", "inc", "ltd", "corp",
+    "st", "ave", "blvd", "rd",
+    "jan", "feb", "mar", "apr", "jun", "jul", "aug", "sep", "sept", "oct", "nov", "dec",
+    "mon", "tue", "wed", "thu", "fri", "sat", "sun",
+    "e.g", "i.e", "cf", "al", "no", "vol", "pg", "approx", "fig", "ref", "min", "max",
+];
+
+// Minimum sentence length to emit (avoids choppy single-word sentences)
+const MIN_SENTENCE_LENGTH: usize = 10;
+
+// Split overlong run-on sentences at a clause boundary past this length
+const MAX_CHUNK_LENGTH: usize = 300;
+
+// Characters that may trail sentence punctuation and belong to the sentence
+const CLOSERS: &[char] = &['"', '\'', ')', ']', '*', '_', '\u{201D}', '\u{2019}'];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LineKind {
+    Prose,    // ordinary text, may wrap onto the next line
+    ListItem, // "- item", "* item", "1. item" - spoken as its own chunk
+    Heading,  // "# Title" - spoken as its own chunk
+    Fence,    // ``` or ~~~ opening/closing a code block
+    Code,     // a line inside a code block
+}
+
+/// Accumulates streamed text and emits speakable chunks
+#[derive(Debug, Default)]
+pub struct SentenceBuffer {
+    // Prose waiting for a sentence boundary
+    pending: String,
+    // Where lines of prose start in `pending`, for telling list numbers ("2.")
+    // from numbers ending a sentence
+    line_starts: Vec<usize>,
+    // Start of the current line, held back until we know what kind of line it is
+    line: String,
+    // Kind of the current line, once known
+    line_kind: Option<LineKind>,
+    // Inside a ``` code block
+    in_code_fence: bool,
+}
+
+impl SentenceBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a text delta, returning any chunks that are now complete
+    pub fn push_delta(&mut self, delta: &str) -> Vec<String> {
+        let mut chunks = Vec::new();
+
+        for ch in delta.chars() {
+            if ch == '\n' {
+                self.end_line(&mut chunks);
+                continue;
+            }
+
+            match self.line_kind {
+                Some(LineKind::Prose) | Some(LineKind::ListItem) | Some(LineKind::Heading) => {
+                    self.pending.push(ch);
+                }
+                Some(LineKind::Fence) | Some(LineKind::Code) => {}
+                None => {
+                    self.line.push(ch);
+                    self.classify_line(&mut chunks);
+                }
+            }
+        }
+
+        self.extract_sentences(&mut chunks);
+        chunks
+    }
+
+    /// Emit whatever is left (call when the stream ends)
+    pub fn flush(&mut self) -> Vec<String> {
+        let mut chunks = Vec::new();
+
+        // An unclassified line at the end of the stream is just prose
+        if self.line_kind.is_none() && !self.in_code_fence && !is_rule(&self.line) {
+            self.pending.push(' ');
+            self.pending.push_str(&self.line);
+        }
+        self.extract_sentences(&mut chunks);
+        self.emit_pending(&mut chunks);
+
+        self.clear();
+        chunks
+    }
+
+    /// Discard everything without emitting (for interrupts)
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    // Decide what the current line is once enough of it has arrived
+    fn classify_line(&mut self, chunks: &mut Vec<String>) {
+        let line = std::mem::take(&mut self.line);
+        let trimmed = line.trim_start();
+
+        if self.in_code_fence {
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                self.line_kind = Some(LineKind::Fence);
+                self.in_code_fence = false;
+            } else if !could_become_fence(trimmed) {
+                self.line_kind = Some(LineKind::Code);
+            } else {
+                self.line = line;
+            }
+            return;
+        }
+
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            // A code block interrupts whatever was being said
+            self.emit_pending(chunks);
+            self.line_kind = Some(LineKind::Fence);
+            self.in_code_fence = true;
+            return;
+        }
+
+        let Some((kind, content_start)) = classify_prefix(trimmed) else {
+            self.line = line; // need more characters
+            return;
+        };
+
+        if kind != LineKind::Prose {
+            // List items and headings start a new chunk
+            self.emit_pending(chunks);
+        } else if !self.pending.is_empty() && !self.pending.ends_with(' ') {
+            // Soft-wrapped prose continues the previous line
+            self.pending.push(' ');
+        }
+
+        if kind == LineKind::Prose {
+            self.line_starts.push(self.pending.len());
+        }
+        self.pending.push_str(trimmed[content_start..].trim_start());
+        self.line_kind = Some(kind);
+    }
+
+    // Handle a newline
+    fn end_line(&mut self, chunks: &mut Vec<String>) {
+        match self.line_kind {
+            Some(LineKind::ListItem) | Some(LineKind::Heading) => {
+                self.extract_sentences(chunks);
+                self.emit_pending(chunks);
+            }
+            Some(LineKind::Prose) => {
+                self.pending.push(' ');
+            }
+            Some(LineKind::Fence) | Some(LineKind::Code) => {}
+            None => {
+                if self.in_code_fence {
+                    // Blank line inside a code block
+                } else if self.line.trim().is_empty() || is_rule(&self.line) {
+                    // Blank line or horizontal rule - paragraph break
+                    self.extract_sentences(chunks);
+                    self.emit_pending(chunks);
+                } else {
+                    // Short line that never got classified ("42", "--") - plain prose
+                    if !self.pending.is_empty() && !self.pending.ends_with(' ') {
+                        self.pending.push(' ');
+                    }
+                    self.line_starts.push(self.pending.len());
+                    self.pending.push_str(self.line.trim());
+                    self.pending.push(' ');
+                }
+            }
+        }
+
+        self.line.clear();
+        self.line_kind = None;
+    }
+
+    // Emit every complete sentence in the pending text
+    fn extract_sentences(&mut self, chunks: &mut Vec<String>) {
+        while let Some(end) = find_sentence_end(&self.pending, &self.line_starts) {
+            let sentence = self.take_until(end);
+            push_chunk(chunks, &sentence);
+        }
+
+        // Run-on sentence with no end in sight - break at a clause boundary
+        if self.pending.chars().count() > MAX_CHUNK_LENGTH {
+            if let Some(end) = find_clause_break(&self.pending) {
+                let clause = self.take_until(end);
+                push_chunk(chunks, &clause);
+            }
+        }
+    }
+
+    // Take the pending text before `end`, dropping the whitespace after it
+    fn take_until(&mut self, end: usize) -> String {
+        let total = self.pending.len();
+        let rest = self.pending.split_off(end);
+        let rest = rest.trim_start();
+        let removed = total - rest.len();
+        let taken = std::mem::replace(&mut self.pending, rest.to_string());
+        self.line_starts = self
+            .line_starts
+            .iter()
+            .filter_map(|start| start.checked_sub(removed))
+            .collect();
+        taken
+    }
+
+    // Emit all pending text as one chunk
+    fn emit_pending(&mut self, chunks: &mut Vec<String>) {
+        let text = std::mem::take(&mut self.pending);
+        self.line_starts.clear();
+        push_chunk(chunks, &text);
+    }
+}
+
+// Normalize whitespace and add a chunk if there's anything to say
+fn push_chunk(chunks: &mut Vec<String>, text: &str) {
+    let chunk = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if chunk.chars().any(|c| c.is_alphanumeric()) {
+        chunks.push(chunk);
+    }
+}
+
+// Could this partial line still turn out to be a ``` fence?
+fn could_become_fence(trimmed: &str) -> bool {
+    trimmed.is_empty() || "```".starts_with(trimmed) || "~~~".starts_with(trimmed)
+}
+
+// A line made only of -, *, _ or = (at least three) is a horizontal rule
+fn is_rule(line: &str) -> bool {
+    let trimmed: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    trimmed.len() >= 3 && trimmed.chars().all(|c| matches!(c, '-' | '*' | '_' | '='))
+}
+
+// Classify a line by its prefix, returning the kind and where its content starts
+// Returns None while the prefix is still ambiguous
+fn classify_prefix(trimmed: &str) -> Option<(LineKind, usize)> {
+    let mut chars = trimmed.char_indices();
+    let (_, first) = chars.next()?;
+
+    if could_become_fence(trimmed) || is_rule_prefix(trimmed) {
+        return None;
+    }
+
+    match first {
+        '-' | '*' | '+' => match chars.next() {
+            Some((i, ' ')) => Some((LineKind::ListItem, i + 1)),
+            _ => Some((LineKind::Prose, 0)),
+        },
+        '#' => {
+            let hashes = trimmed.chars().take_while(|&c| c == '#').count();
+            match trimmed[hashes..].chars().next() {
+                None => None,
+                Some(' ') if hashes <= 6 => Some((LineKind::Heading, hashes + 1)),
+                Some(_) => Some((LineKind::Prose, 0)),
+            }
+        }
+        '>' => Some((LineKind::Prose, 1)),
+        c if c.is_ascii_digit() => {
+            let digits = trimmed.chars().take_while(|c| c.is_ascii_digit()).count();
+            let mut rest = trimmed[digits..].chars();
+            match (rest.next(), rest.next()) {
+                (None, _) => None,
+                (Some('.') | Some(')'), None) if digits <= 3 => None,
+                (Some('.') | Some(')'), Some(' ')) if digits <= 3 => {
+                    Some((LineKind::ListItem, digits + 2))
+                }
+                _ => Some((LineKind::Prose, 0)),
+            }
+        }
+        _ => Some((LineKind::Prose, 0)),
+    }
+}
+
+// "-", "--", "**" etc. might still become a horizontal rule
+fn is_rule_prefix(trimmed: &str) -> bool {
+    let mut chars = trimmed.chars();
+    match chars.next() {
+        Some(c @ ('-' | '*' | '_' | '=')) => chars.all(|x| x == c),
+        _ => false,
+    }
+}
+
+// Find the byte index just past the first complete sentence. `line_starts`
+// are where lines begin in `text`.
+fn find_sentence_end(text: &str, line_starts: &[usize]) -> Option<usize> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut in_code = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let (_, c) = chars[i];
+
+        if c == '`' {
+            in_code = !in_code;
+            i += 1;
+            continue;
+        }
+        if in_code || !matches!(c, '.' | '!' | '?' | '\u{2026}') {
+            i += 1;
+            continue;
+        }
+
+        // Take the whole punctuation run ("?!", "...") and any closing quotes
+        let start = i;
+        while i < chars.len() && matches!(chars[i].1, '.' | '!' | '?' | '\u{2026}') {
+            i += 1;
+        }
+        let punct: String = chars[start..i].iter().map(|(_, c)| c).collect();
+        while i < chars.len() && CLOSERS.contains(&chars[i].1) {
+            i += 1;
+        }
+
+        // Must be followed by whitespace; "3.14", "v1.2.3" and "main.rs" never are.
+        // At the end of the text we can't tell yet - wait for more.
+        match chars.get(i) {
+            Some((_, next)) if next.is_whitespace() => {}
+            _ => continue,
+        }
+
+        let end = chars[i].0;
+        let next_word = text[end..].trim_start().chars().next();
+
+        let is_end = match next_word {
+            // Nothing after the whitespace yet - the next word decides
+            None if punct == "." || is_ellipsis(&punct) => return None,
+            None => true,
+            // "Wait... what" trails off; "Done... Next" starts a new sentence
+            Some(next) if is_ellipsis(&punct) => next.is_uppercase(),
+            Some(next) if punct == "." => {
+                let word = word_before(&text[..chars[start].0]);
+                let at_line_start = line_starts.contains(&(chars[start].0 - word.len()));
+                !is_abbreviation(word, at_line_start) && !next.is_lowercase()
+            }
+            Some(_) => true,
+        };
+
+        if is_end && text[..end].trim().chars().count() >= MIN_SENTENCE_LENGTH {
+            return Some(end);
+        }
+    }
+
+    None
+}
+
+fn is_ellipsis(punct: &str) -> bool {
+    punct.starts_with("..") || punct.contains('\u{2026}')
+}
+
+// The token right before a period, without surrounding brackets or quotes
+fn word_before(text: &str) -> &str {
+    let start = text
+        .rfind(|c: char| c.is_whitespace() || matches!(c, '(' | '[' | '"' | '\''))
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    &text[start..]
+}
+
+// Abbreviations, initials ("John F. Kennedy") and list numbers ("1." starting
+// a line; elsewhere "in 2024." ends a sentence)
+fn is_abbreviation(word: &str, at_line_start: bool) -> bool {
+    let lower = word.to_lowercase();
+    let lower = lower.trim_end_matches('.');
+
+    ABBREVIATIONS.contains(&lower)
+        || (lower.chars().count() == 1 && lower.chars().all(|c| c.is_alphabetic()))
+        || (at_line_start && !lower.is_empty() && lower.chars().all(|c| c.is_ascii_digit()))
+}
+
+// Last clause boundary (", ", "; ", ": ", " - ") that leaves a reasonable chunk
+fn find_clause_break(text: &str) -> Option<usize> {
+    let limit = text
+        .char_indices()
+        .nth(MAX_CHUNK_LENGTH)
+        .map(|(i, _)| i)
+        .unwrap_or(text.len());
+
+    [", ", "; ", ": ", " - "]
+        .iter()
+        .filter_map(|sep| text[..limit].rfind(sep).map(|i| i + sep.len()))
+        .filter(|&i| text[..i].trim().chars().count() >= MIN_SENTENCE_LENGTH)
+        .max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Feed `text` in one delta and flush
+    fn chunks(text: &str) -> Vec<String> {
+        chunks_of(&[text])
+    }
+
+    fn chunks_of(deltas: &[&str]) -> Vec<String> {
+        let mut buffer = SentenceBuffer::new();
+        let mut chunks: Vec<String> = deltas.iter().flat_map(|delta| buffer.push_delta(delta)).collect();
+        chunks.extend(buffer.flush());
+        chunks
+    }
+
+    #[test]
+    fn splits_sentences() {
+        assert_eq!(
+            chunks("The build passed. All tests are green! Shall I commit?"),
+            ["The build passed.", "All tests are green!", "Shall I commit?"]
+        );
+    }
+
+    #[test]
+    fn abbreviations_and_initials_dont_end_sentences() {
+        assert_eq!(
+            chunks("Ask Dr. Smith about it, e.g. tomorrow. John F. Kennedy said so."),
+            ["Ask Dr. Smith about it, e.g. tomorrow.", "John F. Kennedy said so."]
+        );
+    }
+
+    #[test]
+    fn decimals_versions_and_paths_dont_end_sentences() {
+        assert_eq!(
+            chunks("Pi is 3.14 here. Upgrade to v1.2.3 first. Then open main.rs and look."),
+            ["Pi is 3.14 here.", "Upgrade to v1.2.3 first.", "Then open main.rs and look."]
+        );
+    }
+
+    #[test]
+    fn numbers_end_sentences_unless_they_start_a_line() {
+        assert_eq!(
+            chunks("It was released in 2024. Since then it grew."),
+            ["It was released in 2024.", "Since then it grew."]
+        );
+        assert_eq!(
+            chunks("Steps to follow\n2.\nRun the installer now."),
+            ["Steps to follow 2. Run the installer now."]
+        );
+    }
+
+    #[test]
+    fn ellipses_trail_off_unless_a_sentence_follows() {
+        assert_eq!(chunks("Wait... what happened here?"), ["Wait... what happened here?"]);
+        assert_eq!(
+            chunks("Loading the data... Done with that now."),
+            ["Loading the data...", "Done with that now."]
+        );
+    }
+
+    #[test]
+    fn code_fences_are_dropped() {
+        assert_eq!(
+            chunks("Here is the fix:\n```rust\nfn main() { println!(\"Hi.\"); }\n```\nThat should do it."),
+            ["Here is the fix:", "That should do it."]
+        );
+    }
+
+    #[test]
+    fn list_items_are_chunks_of_their_own() {
+        assert_eq!(
+            chunks("Next steps:\n- Update the lockfile\n1. Run the tests\n2) Tag the release\n# Done"),
+            ["Next steps:", "Update the lockfile", "Run the tests", "Tag the release", "Done"]
+        );
+    }
+
+    #[test]
+    fn deltas_split_mid_word_are_joined() {
+        assert_eq!(
+            chunks_of(&["The fi", "rst sente", "nce ends. The sec", "ond one fol", "lows."]),
+            ["The first sentence ends.", "The second one follows."]
+        );
+    }
+
+    #[test]
+    fn sentences_are_emitted_before_the_stream_ends() {
+        let mut buffer = SentenceBuffer::new();
+        assert!(buffer.push_delta("This one is done.").is_empty());
+        assert_eq!(buffer.push_delta(" And this"), ["This one is done."]);
+        assert_eq!(buffer.flush(), ["And this"]);
+    }
+}
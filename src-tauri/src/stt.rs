@@ -0,0 +1,463 @@
+//! Speech-to-text for recordings made by the app
+//!
+//! Audio comes from the microphone (`mic`) or a WAV file, which is how the
+//! pipeline is tested without a mic, and is handed to one of three backends:
+//!
+//! - `whisper`: a GGML whisper model run in-process on the CPU (whisper.cpp).
+//!   The model is loaded on first use and kept until the model setting
+//!   changes. Builds without the `local-stt` feature report that it is
+//!   unavailable instead of failing to link.
+//! - `openai`: an OpenAI-compatible `/v1/audio/transcriptions` server
+//! - `command`: a user-supplied program that is given a WAV file and prints
+//!   the transcript
+
+use crate::http::{self, Endpoint};
+use crate::mic::Recording;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+
+/// Whisper expects 16kHz mono
+pub const SAMPLE_RATE: u32 = 16000;
+
+// whisper.cpp skips input shorter than a second, so short clips are padded
+const MIN_SAMPLES: usize = SAMPLE_RATE as usize * 11 / 10;
+
+// How long a custom STT command may run before it is killed
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(60);
+
+// Speech-to-text settings (set from frontend settings)
+static CONFIG: Lazy<Mutex<SttConfig>> = Lazy::new(|| Mutex::new(SttConfig::default()));
+
+#[derive(Debug, Clone)]
+pub struct SttConfig {
+    /// "whisper", "openai" or "command"
+    pub engine: String,
+    /// Path to a GGML model, or a bare name like "base.en" looked up in
+    /// ~/.local/share/whisper-models
+    pub whisper_model: String,
+    /// ISO 639-1 code, or "auto" to detect it
+    pub language: String,
+    pub openai_base_url: String,
+    pub openai_model: String,
+    pub openai_api_key: Option<String>,
+    /// Program and arguments; `{input}` is replaced with the WAV file path and
+    /// `{language}` with the language. The transcript is read from stdout.
+    pub command_template: String,
+}
+
+impl Default for SttConfig {
+    fn default() -> Self {
+        Self {
+            engine: "whisper".to_string(),
+            whisper_model: "base.en".to_string(),
+            language: "en".to_string(),
+            openai_base_url: "https://api.openai.com".to_string(),
+            openai_model: "whisper-1".to_string(),
+            openai_api_key: None,
+            command_template: String::new(),
+        }
+    }
+}
+
+/// Result of a transcription
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Transcript {
+    pub text: String,
+    /// Length of the audio that was transcribed
+    pub audio_secs: f32,
+    pub elapsed_ms: u64,
+    /// When the speech started, for audio captured here
+    #[serde(skip)]
+    pub started_at: Option<Instant>,
+}
+
+/// Whether the app records and transcribes for this engine itself (the others,
+/// SuperWhisper and dictation, capture audio on their own)
+pub fn is_backend_engine(engine: &str) -> bool {
+    matches!(engine, "whisper" | "openai" | "command")
+}
+
+/// Update the speech-to-text settings
+pub fn set_config(config: SttConfig) {
+    *CONFIG.lock().unwrap() = config;
+}
+
+/// Directory bare model names are resolved in
+pub fn models_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|d| d.join("whisper-models"))
+}
+
+/// Path of the configured model ("base.en" -> .../whisper-models/ggml-base.en.bin)
+pub fn model_path() -> Result<PathBuf, String> {
+    let model = CONFIG.lock().unwrap().whisper_model.clone();
+    let model = model.trim();
+    if model.is_empty() {
+        return Err("No whisper model configured".to_string());
+    }
+
+    let path = if model.contains('/') || model.ends_with(".bin") {
+        PathBuf::from(model)
+    } else {
+        models_dir()
+            .ok_or("Could not determine the whisper models directory")?
+            .join(format!("ggml-{}.bin", model))
+    };
+
+    if path.exists() {
+        Ok(path)
+    } else {
+        Err(format!(
+            "Whisper model not found at {} (download one from https://huggingface.co/ggerganov/whisper.cpp)",
+            path.display()
+        ))
+    }
+}
+
+/// Transcribe a recording with the configured backend
+pub async fn transcribe(recording: Recording) -> Result<Transcript, String> {
+    let start = std::time::Instant::now();
+    let config = CONFIG.lock().unwrap().clone();
+
+    let audio_secs = recording.duration_secs();
+    let samples = whisper_input(&recording);
+
+    let text = match config.engine.as_str() {
+        "openai" => transcribe_openai(&config, &samples).await?,
+        "command" => transcribe_command(&config, &samples).await?,
+        _ => {
+            let model = model_path()?;
+            let language = config.language.clone();
+            let text = tokio::task::spawn_blocking(move || run_whisper(&model, &samples, &language))
+                .await
+                .map_err(|e| format!("Transcription task failed: {}", e))??;
+            clean_transcript(&text)
+        }
+    };
+
+    let transcript = Transcript {
+        text: text.trim().to_string(),
+        audio_secs,
+        elapsed_ms: start.elapsed().as_millis() as u64,
+        started_at: None,
+    };
+    eprintln!(
+        "[STT] {} transcribed {:.1}s of audio in {}ms: {}",
+        config.engine, transcript.audio_secs, transcript.elapsed_ms, transcript.text
+    );
+    Ok(transcript)
+}
+
+/// Transcribe a clip with a local whisper model whatever engine is configured
+/// (wake word spotting must not send audio off the machine). Blocks.
+pub fn transcribe_local(recording: &Recording, model: &Path, language: &str) -> Result<String, String> {
+    let text = run_whisper(model, &whisper_input(recording), language)?;
+    Ok(clean_transcript(&text))
+}
+
+// 16kHz audio, padded to the length whisper needs
+fn whisper_input(recording: &Recording) -> Vec<f32> {
+    let mut samples = resample(&recording.samples, recording.sample_rate, SAMPLE_RATE);
+    if samples.len() < MIN_SAMPLES {
+        samples.resize(MIN_SAMPLES, 0.0);
+    }
+    samples
+}
+
+// Send the audio to an OpenAI-compatible /v1/audio/transcriptions endpoint
+async fn transcribe_openai(config: &SttConfig, samples: &[f32]) -> Result<String, String> {
+    let url = format!("{}/v1/audio/transcriptions", config.openai_base_url.trim_end_matches('/'));
+
+    let file = reqwest::multipart::Part::bytes(encode_wav(samples)?)
+        .file_name("speech.wav")
+        .mime_str("audio/wav")
+        .map_err(|e| e.to_string())?;
+    let mut form = reqwest::multipart::Form::new()
+        .text("model", config.openai_model.clone())
+        .text("response_format", "json")
+        .part("file", file);
+    if !config.language.is_empty() && config.language != "auto" {
+        form = form.text("language", config.language.clone());
+    }
+
+    let mut request = http::client().post(&url).multipart(form);
+    if let Some(key) = config.openai_api_key.as_deref().filter(|k| !k.is_empty()) {
+        request = request.bearer_auth(key);
+    }
+
+    let response = http::send(Endpoint::Transcription, request)
+        .await
+        .map_err(|e| format!("Transcription server unavailable ({}): {}", url, e))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("Transcription server returned {}: {}", status, body));
+    }
+
+    let body: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid transcription response: {}", e))?;
+    body["text"]
+        .as_str()
+        .map(|text| text.to_string())
+        .ok_or_else(|| "Transcription response has no text".to_string())
+}
+
+// Run the user's STT command on a temporary WAV file
+// Like the custom TTS command, the template is split into arguments before
+// substitution and run without a shell.
+async fn transcribe_command(config: &SttConfig, samples: &[f32]) -> Result<String, String> {
+    if config.command_template.trim().is_empty() {
+        return Err("No STT command configured".to_string());
+    }
+
+    let input = std::env::temp_dir().join(format!(
+        "opencode-talk-stt-{}-{}.wav",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos()
+    ));
+    tokio::fs::write(&input, encode_wav(samples)?)
+        .await
+        .map_err(|e| format!("Failed to write audio: {}", e))?;
+
+    let result = run_command(&config.command_template, &input, &config.language).await;
+    let _ = tokio::fs::remove_file(&input).await;
+    result
+}
+
+async fn run_command(template: &str, input: &Path, language: &str) -> Result<String, String> {
+    let words = shlex::split(template).ok_or("STT command has unbalanced quotes")?;
+    let input = input.display().to_string();
+    let args: Vec<String> = words
+        .iter()
+        .map(|word| word.replace("{input}", &input).replace("{language}", language))
+        .collect();
+    let (program, args) = args.split_first().ok_or("STT command is empty")?;
+
+    let child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", program, e))?;
+
+    let output = tokio::time::timeout(COMMAND_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| format!("{} timed out after {:?}", program, COMMAND_TIMEOUT))?
+        .map_err(|e| format!("Failed to wait for {}: {}", program, e))?;
+
+    if !output.status.success() {
+        return Err(format!("{} failed: {}", program, String::from_utf8_lossy(&output.stderr)));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// 16kHz mono 16-bit WAV, the format every transcription service accepts
+fn encode_wav(samples: &[f32]) -> Result<Vec<u8>, String> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: SAMPLE_RATE,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut buffer = Cursor::new(Vec::new());
+    let mut writer = hound::WavWriter::new(&mut buffer, spec).map_err(|e| e.to_string())?;
+    for sample in samples {
+        writer
+            .write_sample((sample.clamp(-1.0, 1.0) * 32767.0) as i16)
+            .map_err(|e| e.to_string())?;
+    }
+    writer.finalize().map_err(|e| e.to_string())?;
+    Ok(buffer.into_inner())
+}
+
+/// Read a WAV file as a mono recording
+pub fn read_wav(path: &Path) -> Result<Recording, String> {
+    let mut reader = hound::WavReader::open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let spec = reader.spec();
+    let channels = spec.channels.max(1) as usize;
+
+    let interleaved: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader
+            .samples::<f32>()
+            .collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?,
+        hound::SampleFormat::Int => {
+            let scale = (1i64 << (spec.bits_per_sample.max(1) - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|s| s.map(|s| s as f32 / scale))
+                .collect::<Result<_, _>>()
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+        }
+    };
+
+    let samples = interleaved
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect();
+
+    Ok(Recording {
+        samples,
+        sample_rate: spec.sample_rate,
+    })
+}
+
+/// Linear resampling (good enough for speech recognition)
+pub fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    if from_rate == to_rate || from_rate == 0 || samples.is_empty() {
+        return samples.to_vec();
+    }
+
+    let ratio = from_rate as f64 / to_rate as f64;
+    let len = (samples.len() as f64 / ratio) as usize;
+    (0..len)
+        .map(|i| {
+            let pos = i as f64 * ratio;
+            let index = pos as usize;
+            let frac = (pos - index as f64) as f32;
+            let a = samples[index];
+            let b = samples.get(index + 1).copied().unwrap_or(a);
+            a + (b - a) * frac
+        })
+        .collect()
+}
+
+// Sounds whisper transcribes as a bracketed, parenthesised or starred tag
+// instead of words ("[BLANK_AUDIO]", "(music)", "*laughs*")
+const NOISE_TAGS: &[&str] = &[
+    "blank audio",
+    "silence",
+    "no speech",
+    "inaudible",
+    "music",
+    "music playing",
+    "applause",
+    "laughs",
+    "laughing",
+    "laughter",
+    "coughs",
+    "coughing",
+    "sighs",
+    "breathing",
+    "clears throat",
+    "noise",
+    "background noise",
+    "static",
+    "beep",
+    "typing",
+    "keyboard clicking",
+    "wind blowing",
+];
+
+// Whisper marks silence and noise with tags that shouldn't be sent as a
+// prompt. Only known tags go, so a spoken aside in brackets survives.
+fn clean_transcript(text: &str) -> String {
+    static TAG_RE: Lazy<regex::Regex> =
+        Lazy::new(|| regex::Regex::new(r"\[([^\]]{1,30})\]|\(([^)]{1,30})\)|\*([^*]{1,30})\*").unwrap());
+    TAG_RE
+        .replace_all(text, |c: &regex::Captures| {
+            let tag = c.iter().skip(1).flatten().next().map_or("", |m| m.as_str());
+            let tag = tag.trim().replace('_', " ").to_lowercase();
+            if NOISE_TAGS.contains(&tag.as_str()) {
+                " ".to_string()
+            } else {
+                c[0].to_string()
+            }
+        })
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(feature = "local-stt")]
+fn run_whisper(model: &Path, samples: &[f32], language: &str) -> Result<String, String> {
+    use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+    // Loaded model, kept warm between transcriptions
+    static CONTEXT: Lazy<Mutex<Option<(PathBuf, WhisperContext)>>> = Lazy::new(|| Mutex::new(None));
+
+    let mut context = CONTEXT.lock().unwrap();
+    if context.as_ref().map(|(path, _)| path.as_path()) != Some(model) {
+        let path = model.to_str().ok_or("Whisper model path is not valid UTF-8")?;
+        eprintln!("[STT] Loading whisper model {}", path);
+        let ctx = WhisperContext::new_with_params(path, WhisperContextParameters::default())
+            .map_err(|e| format!("Failed to load whisper model: {}", e))?;
+        *context = Some((model.to_path_buf(), ctx));
+    }
+    let Some((_, ctx)) = context.as_ref() else {
+        return Err("Whisper model not loaded".to_string());
+    };
+
+    let mut state = ctx
+        .create_state()
+        .map_err(|e| format!("Failed to create whisper state: {}", e))?;
+
+    let threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4).min(8);
+    let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+    params.set_n_threads(threads as i32);
+    params.set_language(Some(if language.is_empty() { "auto" } else { language }));
+    params.set_translate(false);
+    params.set_no_context(true);
+    params.set_suppress_blank(true);
+    params.set_print_special(false);
+    params.set_print_progress(false);
+    params.set_print_realtime(false);
+    params.set_print_timestamps(false);
+
+    state
+        .full(params, samples)
+        .map_err(|e| format!("Whisper failed: {}", e))?;
+
+    let segments = state
+        .full_n_segments()
+        .map_err(|e| format!("Whisper failed: {}", e))?;
+    let mut text = String::new();
+    for segment in 0..segments {
+        if let Ok(segment_text) = state.full_get_segment_text_lossy(segment) {
+            text.push_str(&segment_text);
+        }
+    }
+
+    Ok(text)
+}
+
+#[cfg(not(feature = "local-stt"))]
+fn run_whisper(_model: &Path, _samples: &[f32], _language: &str) -> Result<String, String> {
+    Err("This build doesn't include local speech recognition (the local-stt feature is off)".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_noise_tags_are_removed() {
+        assert_eq!(clean_transcript("[BLANK_AUDIO]"), "");
+        assert_eq!(clean_transcript("Hello (music) there *laughs* ok"), "Hello there ok");
+        assert_eq!(clean_transcript("[ Music ] done [wind blowing]"), "done");
+    }
+
+    #[test]
+    fn spoken_brackets_and_emphasis_are_kept() {
+        assert_eq!(
+            clean_transcript("Use the (new) parser, *not* the old one"),
+            "Use the (new) parser, *not* the old one"
+        );
+        assert_eq!(clean_transcript("Check [the docs] (see above)"), "Check [the docs] (see above)");
+    }
+}
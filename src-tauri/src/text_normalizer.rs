@@ -0,0 +1,556 @@
+//! Markdown-to-speech normalization
+//!
+//! Assistant replies are markdown full of things TTS engines read literally:
+//! backticks, URLs, `snake_case` identifiers, long paths, "200ms". Every piece
+//! of text passes through `normalize_for_speech` before it reaches an engine.
+//! The markdown is parsed properly, code and tables are replaced with short
+//! descriptions, and the remaining prose is rewritten into something that
+//! sounds natural when read aloud.
+
+use once_cell::sync::Lazy;
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+use regex::{Captures, Regex};
+
+// Inline code longer than this is described rather than read
+const MAX_SPOKEN_CODE_LENGTH: usize = 40;
+
+static URL_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b(?:https?|ftp)://[^\s<>()\[\]]+[^\s<>()\[\].,;:!?]").unwrap());
+
+// Paths with at least one slash: ./src/main.rs, ~/notes, /etc/hosts, src/lib/utils.ts
+static PATH_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?:~|\.{1,2})?/?(?:[\w.@-]+/)+[\w.@-]*|~/[\w.@-]*[\w@-]").unwrap()
+});
+
+// Bare file names with a known extension: main.rs, sentence_buffer.rs, package.json
+static FILE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\b([\w-]+)\.(rs|ts|tsx|js|jsx|mjs|py|go|rb|java|kt|swift|c|h|cpp|hpp|cs|php|json|toml|yaml|yml|md|txt|sh|css|scss|html|lock|sql|env|xml|csv|log)\b").unwrap()
+});
+
+// snake_case / SCREAMING_SNAKE and lowerCamelCase identifiers
+static SNAKE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b[A-Za-z][A-Za-z0-9]*(?:_[A-Za-z0-9]+)+\b").unwrap());
+static CAMEL_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b[a-z]{2,}[a-z0-9]*(?:[A-Z][a-z0-9]+)+\b").unwrap());
+
+// v1.2.3 / 1.2.3 version numbers
+static VERSION_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b(v)?(\d+(?:\.\d+){2,})\b").unwrap());
+
+// Numbers with units: 200ms, 3.5 GB, 10k, 2x, 50%. Seconds and minutes need
+// their longer forms, since "the 1990s" and "5m" (metres, millions) are more
+// often something else.
+static UNIT_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\b(\d+(?:\.\d+)?)(?:(%)|\s?(ms|KB|MB|GB|TB|KiB|MiB|GiB|kb|mb|gb|tb|kHz|MHz|GHz|Hz|fps|px|secs?|mins?|hrs?)\b|(h|k|K|x)\b)").unwrap()
+});
+
+// 1-5, 10-20 (but not dates like 2024-01-01)
+static RANGE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b(\d{1,4})-(\d{1,4})\b(-\d)?").unwrap());
+
+static SPACE_BEFORE_PUNCT_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s+([.,;:!?])").unwrap());
+static REPEATED_PUNCT_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"([.,;:])(?:\s*[.,;:])+").unwrap());
+
+// Abbreviations and symbols that read badly
+const REPLACEMENTS: &[(&str, &str)] = &[
+    ("e.g.", "for example"),
+    ("i.e.", "that is"),
+    ("etc.", "et cetera."),
+    ("vs.", "versus"),
+    (" vs ", " versus "),
+    (" -> ", " to "),
+    (" => ", " to "),
+    (" → ", " to "),
+    (" & ", " and "),
+    ("w/o ", "without "),
+    ("w/ ", "with "),
+];
+
+/// Turn markdown into plain text suitable for any TTS engine
+pub fn normalize_for_speech(markdown: &str) -> String {
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let mut out = String::new();
+
+    // Code block being skipped: (language, line count)
+    let mut code_block: Option<(String, usize)> = None;
+    // Table being skipped: body row count
+    let mut table_rows: Option<usize> = None;
+    // Image alt text being collected
+    let mut image_alt: Option<String> = None;
+    // Adjacent text events, verbalized together (the parser splits text at
+    // characters like `~` that might start markup)
+    let mut prose = String::new();
+
+    for event in Parser::new_ext(markdown, options) {
+        if code_block.is_none() && table_rows.is_none() && image_alt.is_none() {
+            if let Event::Text(text) = &event {
+                prose.push_str(text);
+                continue;
+            }
+            if !prose.is_empty() {
+                out.push_str(&verbalize_prose(&prose));
+                prose.clear();
+            }
+        }
+
+        if let Some((language, lines)) = code_block.as_mut() {
+            match event {
+                Event::Text(code) => *lines += code.lines().count(),
+                Event::End(TagEnd::CodeBlock) => {
+                    let description = describe_code_block(language, *lines);
+                    end_clause(&mut out);
+                    out.push_str(&description);
+                    end_clause(&mut out);
+                    code_block = None;
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        if let Some(rows) = table_rows.as_mut() {
+            match event {
+                Event::End(TagEnd::TableRow) => *rows += 1,
+                Event::End(TagEnd::Table) => {
+                    let description = match *rows {
+                        1 => "There's a table with one row.".to_string(),
+                        n => format!("There's a table with {} rows.", n),
+                    };
+                    end_clause(&mut out);
+                    out.push_str(&description);
+                    out.push(' ');
+                    table_rows = None;
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        if let Some(alt) = image_alt.as_mut() {
+            match event {
+                Event::Text(text) | Event::Code(text) => alt.push_str(&text),
+                Event::End(TagEnd::Image) => {
+                    let alt = alt.trim();
+                    if alt.is_empty() {
+                        out.push_str(" an image ");
+                    } else {
+                        out.push_str(&format!(" an image of {} ", verbalize_prose(alt)));
+                    }
+                    image_alt = None;
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        match event {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => info.split_whitespace().next().unwrap_or("").to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                code_block = Some((language, 0));
+            }
+            Event::Start(Tag::Table(_)) => table_rows = Some(0),
+            Event::Start(Tag::Image { .. }) => image_alt = Some(String::new()),
+            Event::Code(code) => out.push_str(&verbalize_code(&code)),
+            Event::SoftBreak | Event::HardBreak => out.push(' '),
+            Event::TaskListMarker(done) => out.push_str(if done { "Done: " } else { "To do: " }),
+            Event::End(TagEnd::Paragraph)
+            | Event::End(TagEnd::Heading(_))
+            | Event::End(TagEnd::Item)
+            | Event::Rule => end_clause(&mut out),
+            // Raw HTML, footnotes, math etc. aren't worth speaking
+            _ => {}
+        }
+    }
+
+    out.push_str(&verbalize_prose(&prose));
+    tidy(&out)
+}
+
+// Make sure the text so far ends a sentence before something new starts
+fn end_clause(out: &mut String) {
+    let trimmed_len = out.trim_end().len();
+    out.truncate(trimmed_len);
+    if let Some(last) = out.chars().last() {
+        if !matches!(last, '.' | '!' | '?' | ':' | ';' | ',') {
+            out.push('.');
+        }
+        out.push(' ');
+    }
+}
+
+// "a Rust code block", "a 12-line code block"
+fn describe_code_block(language: &str, lines: usize) -> String {
+    let language = match language.to_lowercase().as_str() {
+        "" | "text" | "txt" | "plaintext" => String::new(),
+        "rs" | "rust" => "Rust ".to_string(),
+        "ts" | "typescript" => "TypeScript ".to_string(),
+        "tsx" | "jsx" => "React ".to_string(),
+        "js" | "javascript" => "JavaScript ".to_string(),
+        "py" | "python" => "Python ".to_string(),
+        "sh" | "bash" | "zsh" | "shell" | "console" => "shell ".to_string(),
+        "json" => "JSON ".to_string(),
+        "toml" => "TOML ".to_string(),
+        "yaml" | "yml" => "YAML ".to_string(),
+        "diff" | "patch" => "diff ".to_string(),
+        "mermaid" | "plantuml" | "dot" => return "There's a diagram in the output.".to_string(),
+        other => format!("{} ", other),
+    };
+
+    match lines {
+        0 | 1 => format!("There's a short {}code snippet in the output.", language),
+        n => format!("There's a {}-line {}code block in the output.", n, language),
+    }
+}
+
+/// Rewrite prose so identifiers, paths, URLs and numbers read naturally
+fn verbalize_prose(text: &str) -> String {
+    let text = URL_RE.replace_all(text, |c: &Captures| describe_url(&c[0]));
+    let text = PATH_RE.replace_all(&text, |c: &Captures| {
+        let path = &c[0];
+        if looks_like_path(path) {
+            describe_path(path)
+        } else {
+            path.to_string()
+        }
+    });
+    let text = FILE_RE.replace_all(&text, |c: &Captures| {
+        format!("{} dot {}", split_identifier(&c[1]), &c[2])
+    });
+    let text = SNAKE_RE.replace_all(&text, |c: &Captures| split_identifier(&c[0]));
+    let text = CAMEL_RE.replace_all(&text, |c: &Captures| split_identifier(&c[0]));
+    let text = VERSION_RE.replace_all(&text, |c: &Captures| {
+        let spoken = c[2].replace('.', " point ");
+        if c.get(1).is_some() {
+            format!("version {}", spoken)
+        } else {
+            spoken
+        }
+    });
+    let text = UNIT_RE.replace_all(&text, |c: &Captures| {
+        let unit = c.get(2).or(c.get(3)).or(c.get(4)).map(|m| m.as_str()).unwrap_or("");
+        format!("{} {}", &c[1], spoken_unit(unit, &c[1]))
+    });
+    let text = RANGE_RE.replace_all(&text, |c: &Captures| {
+        if c.get(3).is_some() {
+            c[0].to_string() // a date, leave it alone
+        } else {
+            format!("{} to {}", &c[1], &c[2])
+        }
+    });
+
+    let mut text = text.into_owned();
+    for (from, to) in REPLACEMENTS {
+        text = text.replace(from, to);
+    }
+    if let Some(rest) = text.strip_prefix("~") {
+        if rest.starts_with(|c: char| c.is_ascii_digit()) {
+            text = format!("about {}", rest);
+        }
+    }
+    text.replace(" ~", " about ")
+}
+
+/// Inline code: read short things, describe long ones
+fn verbalize_code(code: &str) -> String {
+    let code = code.trim();
+
+    if URL_RE.is_match(code) && !code.contains(' ') {
+        return describe_url(code);
+    }
+    if looks_like_path(code) {
+        return describe_path(code);
+    }
+    if let Some(c) = FILE_RE.captures(code).filter(|c| c[0].len() == code.len()) {
+        return format!("{} dot {}", split_identifier(&c[1]), &c[2]);
+    }
+
+    // A single identifier or path expression: foo_bar, getUserId(), Foo::new, self.items
+    let is_expression = !code.is_empty()
+        && code
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | ':' | '.' | '(' | ')' | '-' | '!' | '&' | '*'));
+    if is_expression {
+        return code
+            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .filter(|part| !part.is_empty())
+            .map(split_identifier)
+            .collect::<Vec<_>>()
+            .join(" ");
+    }
+
+    if code.chars().count() <= MAX_SPOKEN_CODE_LENGTH && code.split_whitespace().count() <= 5 {
+        // A short command: "cargo test --release" -> "cargo test release"
+        return code
+            .split_whitespace()
+            .map(|word| word.trim_start_matches('-'))
+            .map(|word| if word.contains('/') { describe_path(word) } else { word.to_string() })
+            .collect::<Vec<_>>()
+            .join(" ");
+    }
+
+    "a code snippet".to_string()
+}
+
+// Slash-separated tokens that are really paths rather than "and/or" or "I/O"
+fn looks_like_path(token: &str) -> bool {
+    if !token.contains('/') || token.contains("://") {
+        return false;
+    }
+    if token.starts_with('/') || token.starts_with("./") || token.starts_with("../") || token.starts_with("~/") {
+        return token.len() > 1;
+    }
+
+    // Relative paths need a file extension or a trailing slash ("w/" is "with")
+    let last = token.trim_end_matches('/').rsplit('/').next().unwrap_or("");
+    (token.ends_with('/') && last.len() > 1) || last.rsplit_once('.').is_some_and(|(name, ext)| {
+        !name.is_empty() && !ext.is_empty() && ext.len() <= 6 && ext.chars().all(|c| c.is_ascii_alphanumeric())
+    })
+}
+
+// "src/components/Settings.tsx" -> "settings dot tsx", "src/lib/" -> "the lib folder"
+fn describe_path(path: &str) -> String {
+    let is_dir = path.ends_with('/');
+    let name = path.trim_end_matches('/').rsplit('/').next().unwrap_or(path);
+    let name = name.trim_start_matches('.');
+
+    if name.is_empty() || name == "~" {
+        return "your home folder".to_string();
+    }
+    if is_dir {
+        return format!("the {} folder", split_identifier(name));
+    }
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => format!("{} dot {}", split_identifier(stem), ext),
+        _ => split_identifier(name),
+    }
+}
+
+// "https://www.github.com/foo/bar" -> "a link to github.com"
+fn describe_url(url: &str) -> String {
+    let without_scheme = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+    let host = without_scheme.split(['/', '?', '#']).next().unwrap_or(without_scheme);
+    let host = host.split('@').next_back().unwrap_or(host);
+    let host = host.strip_prefix("www.").unwrap_or(host);
+
+    match host.split(':').next() {
+        Some("localhost") | Some("127.0.0.1") => "a local link".to_string(),
+        Some(host) if !host.is_empty() => format!("a link to {}", host),
+        _ => "a link".to_string(),
+    }
+}
+
+/// Split an identifier into words: "get_user_id" / "getUserId" -> "get user id",
+/// "HTTPServer" -> "HTTP server"
+pub fn split_identifier(identifier: &str) -> String {
+    let mut words: Vec<String> = Vec::new();
+    let chars: Vec<char> = identifier.chars().collect();
+    let mut current = String::new();
+
+    for (i, &c) in chars.iter().enumerate() {
+        if c == '_' || c == '-' {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+
+        let prev = i.checked_sub(1).map(|p| chars[p]);
+        let next = chars.get(i + 1).copied();
+        let boundary = c.is_uppercase()
+            && !current.is_empty()
+            && (prev.is_some_and(|p| p.is_lowercase() || p.is_ascii_digit())
+                || (prev.is_some_and(|p| p.is_uppercase()) && next.is_some_and(|n| n.is_lowercase())));
+
+        if boundary {
+            words.push(std::mem::take(&mut current));
+        }
+        current.push(c);
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+
+    // SCREAMING_SNAKE constants read as ordinary words
+    if identifier.chars().all(|c| !c.is_lowercase()) && words.len() > 1 {
+        return words.join(" ").to_lowercase();
+    }
+
+    words
+        .into_iter()
+        .map(|word| {
+            // Keep short acronyms ("ID", "HTTP") so they're spelled out,
+            // but don't shout SCREAMING_SNAKE words
+            let is_acronym = word.chars().all(|c| c.is_uppercase() || c.is_ascii_digit());
+            if is_acronym && word.chars().count() <= 4 {
+                word
+            } else {
+                word.to_lowercase()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn spoken_unit(unit: &str, number: &str) -> &'static str {
+    let singular = number == "1";
+    match unit {
+        "%" => "percent",
+        "ms" => if singular { "millisecond" } else { "milliseconds" },
+        "sec" | "secs" => if singular { "second" } else { "seconds" },
+        "min" | "mins" => if singular { "minute" } else { "minutes" },
+        "h" | "hr" | "hrs" => if singular { "hour" } else { "hours" },
+        "KB" | "kb" => "kilobytes",
+        "MB" | "mb" => "megabytes",
+        "GB" | "gb" => "gigabytes",
+        "TB" | "tb" => "terabytes",
+        "KiB" => "kibibytes",
+        "MiB" => "mebibytes",
+        "GiB" => "gibibytes",
+        "Hz" => "hertz",
+        "kHz" => "kilohertz",
+        "MHz" => "megahertz",
+        "GHz" => "gigahertz",
+        "fps" => "frames per second",
+        "px" => "pixels",
+        "k" | "K" => "thousand",
+        "x" => "times",
+        _ => "",
+    }
+}
+
+// Collapse whitespace and fix punctuation left over from the rewrites
+fn tidy(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let text = SPACE_BEFORE_PUNCT_RE.replace_all(&text, "$1");
+    let text = REPEATED_PUNCT_RE.replace_all(&text, "$1");
+    text.trim_start_matches(|c: char| matches!(c, '.' | ',' | ';' | ':') || c.is_whitespace())
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn speak(markdown: &str) -> String {
+        normalize_for_speech(markdown)
+    }
+
+    #[test]
+    fn urls_become_their_host() {
+        assert_eq!(
+            speak("See https://www.github.com/foo/bar for details."),
+            "See a link to github.com for details."
+        );
+        assert_eq!(
+            speak("Open http://localhost:3000/api now."),
+            "Open a local link now."
+        );
+    }
+
+    #[test]
+    fn paths_become_their_file_or_folder() {
+        assert_eq!(
+            speak("Edit ./src/main.rs and src/lib/utils.ts in src/lib/ today."),
+            "Edit main dot rs and utils dot ts in the lib folder today."
+        );
+        assert_eq!(speak("It's in ~/notes."), "It's in notes.");
+        assert_eq!(
+            speak("Both and/or and I/O stay."),
+            "Both and/or and I/O stay."
+        );
+        assert_eq!(
+            speak("Works w/ tests and w/o docs."),
+            "Works with tests and without docs."
+        );
+    }
+
+    #[test]
+    fn identifiers_are_split_into_words() {
+        assert_eq!(
+            speak("Update sentence_buffer.rs and package.json."),
+            "Update sentence buffer dot rs and package dot json."
+        );
+        assert_eq!(
+            speak("Call get_user_id, MAX_RETRIES and getUserId."),
+            "Call get user id, max retries and get user id."
+        );
+        assert_eq!(split_identifier("HTTPServer"), "HTTP server");
+    }
+
+    #[test]
+    fn versions_are_read_point_by_point() {
+        assert_eq!(
+            speak("Upgrade to v1.2.3 or 2.0.1."),
+            "Upgrade to version 1 point 2 point 3 or 2 point 0 point 1."
+        );
+    }
+
+    #[test]
+    fn units_are_spelled_out() {
+        assert_eq!(
+            speak("It took 200ms and 3.5 GB, 10k rows, 2x faster, 50% done, 1 sec, 5 mins, 24h."),
+            "It took 200 milliseconds and 3.5 gigabytes, 10 thousand rows, 2 times faster, 50 percent done, \
+             1 second, 5 minutes, 24 hours."
+        );
+    }
+
+    #[test]
+    fn decades_and_bare_letters_are_not_units() {
+        assert_eq!(
+            speak("Back in the 1990s it was 5m long, and the 80s too."),
+            "Back in the 1990s it was 5m long, and the 80s too."
+        );
+    }
+
+    #[test]
+    fn ranges_are_read_but_dates_are_not() {
+        assert_eq!(
+            speak("Pick 1-5 items, not 2024-01-01."),
+            "Pick 1 to 5 items, not 2024-01-01."
+        );
+    }
+
+    #[test]
+    fn abbreviations_and_symbols_are_replaced() {
+        assert_eq!(
+            speak("Use e.g. this vs. that, i.e. fast -> slow & more."),
+            "Use for example this versus that, that is fast to slow and more."
+        );
+        assert_eq!(
+            speak("~5 minutes, takes ~10 seconds."),
+            "about 5 minutes, takes about 10 seconds."
+        );
+    }
+
+    #[test]
+    fn inline_code_is_read_or_described() {
+        assert_eq!(
+            speak("Run `cargo test --release`, `foo_bar()`, `src/main.rs` and `https://example.com/x`."),
+            "Run cargo test release, foo bar, main dot rs and a link to example.com."
+        );
+        assert_eq!(
+            speak("Use `some very long code expression with many words in it that goes on`."),
+            "Use a code snippet."
+        );
+    }
+
+    #[test]
+    fn blocks_are_described() {
+        assert_eq!(
+            speak("Here:\n\n```rust\nfn main() {}\nfn x() {}\n```\n\nDone"),
+            "Here: There's a 2-line Rust code block in the output. Done."
+        );
+        assert_eq!(
+            speak("A table:\n\n| a | b |\n|---|---|\n| 1 | 2 |\n| 3 | 4 |\n"),
+            "A table: There's a table with 2 rows."
+        );
+        assert_eq!(
+            speak("![a cat](cat.png) and ![](x.png)"),
+            "an image of a cat and an image."
+        );
+        assert_eq!(
+            speak("- [x] write tests\n- [ ] ship it"),
+            "Done: write tests. To do: ship it."
+        );
+        assert_eq!(speak("# Heading\nText"), "Heading. Text.");
+    }
+}
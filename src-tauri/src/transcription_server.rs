@@ -4,7 +4,7 @@
 //! We receive the text and emit it as a Tauri event to the frontend.
 
 use std::thread;
-use tauri::{AppHandle, Emitter};
+use tauri::AppHandle;
 use tiny_http::{Server, Response, Method, Header};
 use serde::{Deserialize, Serialize};
 
@@ -80,14 +80,11 @@ pub fn start_server(app_handle: AppHandle) {
             println!("Received transcription: {}", text);
 
             // Emit the transcription event to the frontend
-            let payload = TranscriptionPayload {
-                text: text.clone(),
-                confidence: None,
+            let transcript = crate::stt::Transcript {
+                text,
+                ..Default::default()
             };
-            
-            if let Err(e) = app.emit("transcription", &payload) {
-                eprintln!("Failed to emit transcription event: {}", e);
-            }
+            crate::emit_transcription(&app, &transcript, "superwhisper");
 
             // Respond with success
             let response = Response::from_string("OK")
@@ -0,0 +1,500 @@
+//! Tray icon and menu
+//!
+//! The state icons are drawn at startup from the app icon embedded in the
+//! binary, so nothing has to be found on disk at runtime (the bundle layout
+//! differs between macOS, Windows and the Linux packages). Each state tints the
+//! icon and adds a badge dot in the panel's colours; Processing spins a small
+//! arc in the badge instead.
+//!
+//! The tooltip shows the state and the current session. Linux tray hosts don't
+//! show tooltips, so there the icon is the only indicator.
+//!
+//! The menu is rebuilt whenever what it shows changes (the OpenCode server's
+//! status and sessions, which are polled, and the voice settings) rather than
+//! edited in place, which not every Linux tray host picks up.
+
+use crate::{http, SharedState, VoiceState};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{
+    image::Image,
+    menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu},
+    tray::{MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent},
+    AppHandle, Manager, Wry,
+};
+
+const TRAY_ID: &str = "main-tray";
+
+// Large enough for HiDPI menu bars; the OS scales it down
+const BASE_ICON: &[u8] = include_bytes!("../icons/128x128.png");
+
+// Same colours as the floating panel's state button (Tailwind 500s)
+const LISTENING_COLOR: [u8; 3] = [0xef, 0x44, 0x44];
+const PROCESSING_COLOR: [u8; 3] = [0xea, 0xb3, 0x08];
+const SPEAKING_COLOR: [u8; 3] = [0x22, 0xc5, 0x5e];
+
+// How far the icon is pulled towards the state colour
+const TINT: f32 = 0.35;
+
+const SPINNER_FRAMES: usize = 8;
+const SPINNER_INTERVAL: Duration = Duration::from_millis(150);
+
+// Bumped on every state change so a running spinner knows to stop
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+const SERVER_POLL_INTERVAL: Duration = Duration::from_secs(15);
+const RECENT_SESSIONS: usize = 8;
+
+// Engines offered in the menu, as in the settings' engine picker
+const TTS_ENGINES: [(&str, &str); 7] = [
+    ("kokoro", "Kokoro"),
+    ("piper", "Piper"),
+    ("edge", "Edge"),
+    ("macos", "macOS"),
+    ("espeak", "espeak-ng"),
+    ("command", "Custom command"),
+    ("openai", "OpenAI-compatible"),
+];
+
+/// How an engine is named in the menu and in spoken confirmations
+pub fn engine_label(engine: &str) -> &str {
+    TTS_ENGINES
+        .iter()
+        .find(|(id, _)| *id == engine)
+        .map_or(engine, |(_, label)| *label)
+}
+
+/// Voices offered for each engine (the same lists as the settings window)
+pub fn engine_voices(engine: &str) -> &'static [&'static str] {
+    match engine {
+        "macos" => &[
+            "Samantha", "Alex", "Victoria", "Tom", "Karen", "Daniel", "Moira", "Tessa", "Veena", "Fiona",
+            "Allison", "Ava",
+        ],
+        "kokoro" => &[
+            "af_heart", "af_bella", "af_nicole", "af_sarah", "af_sky", "am_adam", "am_michael", "bf_emma",
+            "bf_isabella", "bm_george", "bm_lewis",
+        ],
+        "piper" => &[
+            "en_US-amy-medium",
+            "en_US-arctic-medium",
+            "en_US-danny-low",
+            "en_US-kathleen-low",
+            "en_US-lessac-medium",
+            "en_US-libritts-high",
+            "en_US-ryan-medium",
+        ],
+        "espeak" => &["en-us", "en-gb", "de", "es", "fr"],
+        "openai" => &["alloy", "echo", "fable", "onyx", "nova", "shimmer"],
+        _ => &[],
+    }
+}
+
+/// Something picked from the tray menu that the app has to act on
+#[derive(Debug, Clone)]
+pub enum TrayAction {
+    SelectSession { id: String, title: Option<String> },
+    SetEngine(String),
+    SetVoice(String),
+    ToggleMute,
+    StopSpeaking,
+    RepeatLastAnswer,
+}
+
+/// An OpenCode session, as listed in the menu
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RecentSession {
+    pub id: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    time: SessionTime,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+struct SessionTime {
+    #[serde(default)]
+    updated: f64,
+}
+
+// What the OpenCode server last told us; None until the first poll
+static SERVER: Mutex<Option<(bool, Vec<RecentSession>)>> = Mutex::new(None);
+
+// Everything the menu shows, so it is only rebuilt when something changed
+#[derive(Clone, PartialEq)]
+struct MenuSnapshot {
+    server: Option<(bool, Vec<RecentSession>)>,
+    current_session: Option<String>,
+    engine: String,
+    voice: String,
+    muted: bool,
+}
+
+static SHOWN_MENU: Mutex<Option<MenuSnapshot>> = Mutex::new(None);
+
+struct StateIcons {
+    width: u32,
+    height: u32,
+    idle: Vec<u8>,
+    listening: Vec<u8>,
+    speaking: Vec<u8>,
+    processing: Vec<Vec<u8>>,
+}
+
+impl StateIcons {
+    fn image(&self, rgba: &[u8]) -> Image<'static> {
+        Image::new_owned(rgba.to_vec(), self.width, self.height)
+    }
+}
+
+static ICONS: Lazy<Option<StateIcons>> = Lazy::new(|| match Image::from_bytes(BASE_ICON) {
+    Ok(base) => Some(render_icons(&base)),
+    Err(e) => {
+        eprintln!("[TRAY] Failed to decode the tray icon: {}", e);
+        None
+    }
+});
+
+fn render_icons(base: &Image) -> StateIcons {
+    let (width, height) = (base.width(), base.height());
+    let variant = |color: [u8; 3], badge: &dyn Fn(f32, f32) -> f32| {
+        let mut rgba = base.rgba().to_vec();
+        tint(&mut rgba, color);
+        draw_badge(&mut rgba, width, height, color, badge);
+        rgba
+    };
+
+    let dot = |_: f32, _: f32| 1.0;
+    let processing = (0..SPINNER_FRAMES)
+        .map(|frame| {
+            let head = frame as f32 / SPINNER_FRAMES as f32 * std::f32::consts::TAU;
+            variant(PROCESSING_COLOR, &move |radius, angle| spinner(head, radius, angle))
+        })
+        .collect();
+
+    StateIcons {
+        width,
+        height,
+        idle: base.rgba().to_vec(),
+        listening: variant(LISTENING_COLOR, &dot),
+        speaking: variant(SPEAKING_COLOR, &dot),
+        processing,
+    }
+}
+
+fn tint(rgba: &mut [u8], color: [u8; 3]) {
+    for pixel in rgba.chunks_exact_mut(4) {
+        for (channel, target) in pixel.iter_mut().zip(color) {
+            *channel = (*channel as f32 + (target as f32 - *channel as f32) * TINT).round() as u8;
+        }
+    }
+}
+
+// Spinner badge: a ring with a bright head at `head` radians fading into a
+// tail behind it
+fn spinner(head: f32, radius: f32, angle: f32) -> f32 {
+    if radius < 0.5 {
+        return 0.0;
+    }
+    let behind = (head - angle).rem_euclid(std::f32::consts::TAU) / std::f32::consts::TAU;
+    (1.0 - behind).powi(2)
+}
+
+// Draw a badge in the bottom-right corner, outlined in white so it stands out
+// on both light and dark menu bars. `shade(radius, angle)` gives the opacity of
+// the colour at a point, radius being 0 at the centre and 1 at the edge.
+fn draw_badge(rgba: &mut [u8], width: u32, height: u32, color: [u8; 3], shade: &dyn Fn(f32, f32) -> f32) {
+    let size = width.min(height) as f32;
+    let (cx, cy) = (width as f32 - size * 0.24, height as f32 - size * 0.24);
+    let radius = size * 0.2;
+    let outline = size * 0.05;
+
+    for y in 0..height {
+        for x in 0..width {
+            let (dx, dy) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+            let distance = (dx * dx + dy * dy).sqrt();
+            // Antialiased coverage of the outline disc and the inner badge
+            let outer = (radius + outline - distance + 0.5).clamp(0.0, 1.0);
+            if outer == 0.0 {
+                continue;
+            }
+            let inner = (radius - distance + 0.5).clamp(0.0, 1.0) * shade(distance / radius, dy.atan2(dx));
+
+            let i = ((y * width + x) * 4) as usize;
+            let pixel = &mut rgba[i..i + 4];
+            for (channel, target) in pixel.iter_mut().zip(color) {
+                let painted = 255.0 + (target as f32 - 255.0) * inner;
+                *channel = (*channel as f32 + (painted - *channel as f32) * outer).round() as u8;
+            }
+            pixel[3] = (pixel[3] as f32 + (255.0 - pixel[3] as f32) * outer).round() as u8;
+        }
+    }
+}
+
+fn state_label(state: &VoiceState) -> &'static str {
+    match state {
+        VoiceState::Idle => "Idle",
+        VoiceState::Listening => "Listening",
+        VoiceState::Processing => "Thinking",
+        VoiceState::Speaking => "Speaking",
+    }
+}
+
+/// Show a voice state (and the current session) on the tray icon
+pub fn show_state(app: &AppHandle, state: &VoiceState, session_title: Option<&str>) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+
+    let mut tooltip = format!("OpenCode Talk: {}", state_label(state));
+    if let Some(title) = session_title.filter(|t| !t.is_empty()) {
+        tooltip.push_str(&format!("\n{}", title));
+    }
+    let _ = tray.set_tooltip(Some(tooltip));
+
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    let Some(icons) = ICONS.as_ref() else {
+        return;
+    };
+    match state {
+        VoiceState::Idle => set_icon(&tray, icons.image(&icons.idle)),
+        VoiceState::Listening => set_icon(&tray, icons.image(&icons.listening)),
+        VoiceState::Speaking => set_icon(&tray, icons.image(&icons.speaking)),
+        VoiceState::Processing => {
+            std::thread::spawn(move || {
+                for frame in icons.processing.iter().cycle() {
+                    if GENERATION.load(Ordering::SeqCst) != generation {
+                        break;
+                    }
+                    set_icon(&tray, icons.image(frame));
+                    std::thread::sleep(SPINNER_INTERVAL);
+                }
+            });
+        }
+    }
+}
+
+fn set_icon(tray: &TrayIcon, icon: Image<'static>) {
+    if let Err(e) = tray.set_icon(Some(icon)) {
+        eprintln!("[TRAY] Failed to set the tray icon: {}", e);
+    }
+}
+
+fn snapshot(app: &AppHandle) -> MenuSnapshot {
+    let state = app.state::<SharedState>();
+    let app_state = state.lock().unwrap();
+    MenuSnapshot {
+        server: SERVER.lock().unwrap().clone(),
+        current_session: app_state.current_session_id.clone(),
+        engine: app_state.settings.tts_engine.clone(),
+        voice: app_state.settings.tts_voice.clone(),
+        muted: app_state.settings.voice_muted,
+    }
+}
+
+fn build_menu(app: &AppHandle, snapshot: &MenuSnapshot) -> tauri::Result<Menu<Wry>> {
+    let menu = Menu::new(app)?;
+
+    let status = match &snapshot.server {
+        None => "OpenCode: checking...",
+        Some((true, _)) => "OpenCode: connected",
+        Some((false, _)) => "OpenCode: not reachable",
+    };
+    menu.append(&MenuItem::with_id(app, "status", status, false, None::<&str>)?)?;
+    menu.append(&PredefinedMenuItem::separator(app)?)?;
+    menu.append(&MenuItem::with_id(app, "show", "Show Panel", true, None::<&str>)?)?;
+    menu.append(&MenuItem::with_id(app, "settings", "Settings...", true, None::<&str>)?)?;
+    menu.append(&PredefinedMenuItem::separator(app)?)?;
+
+    let sessions = Submenu::with_id(app, "sessions", "Recent Sessions", true)?;
+    let recent = snapshot.server.as_ref().map(|(_, sessions)| sessions.as_slice()).unwrap_or_default();
+    for session in recent {
+        let title = session.title.as_deref().filter(|t| !t.is_empty()).unwrap_or("Untitled session");
+        let current = snapshot.current_session.as_deref() == Some(session.id.as_str());
+        let id = format!("session:{}", session.id);
+        sessions.append(&CheckMenuItem::with_id(app, id, title, true, current, None::<&str>)?)?;
+    }
+    if recent.is_empty() {
+        sessions.append(&MenuItem::with_id(app, "no-sessions", "No sessions", false, None::<&str>)?)?;
+    }
+    menu.append(&sessions)?;
+
+    let engines = Submenu::with_id(app, "engines", "Voice Engine", true)?;
+    for (engine, label) in TTS_ENGINES {
+        let id = format!("engine:{}", engine);
+        engines.append(&CheckMenuItem::with_id(app, id, label, true, snapshot.engine == engine, None::<&str>)?)?;
+    }
+    menu.append(&engines)?;
+
+    // The current voice is listed even when it isn't one of the presets
+    let voices = Submenu::with_id(app, "voices", "Voice", true)?;
+    let mut names: Vec<&str> = engine_voices(&snapshot.engine).to_vec();
+    if !snapshot.voice.is_empty() && !names.contains(&snapshot.voice.as_str()) {
+        names.insert(0, &snapshot.voice);
+    }
+    for name in &names {
+        let id = format!("voice:{}", name);
+        voices.append(&CheckMenuItem::with_id(app, id, name, true, snapshot.voice == *name, None::<&str>)?)?;
+    }
+    if names.is_empty() {
+        voices.append(&MenuItem::with_id(app, "no-voices", "Set in Settings", false, None::<&str>)?)?;
+    }
+    menu.append(&voices)?;
+
+    menu.append(&CheckMenuItem::with_id(app, "mute", "Mute Voice", true, snapshot.muted, None::<&str>)?)?;
+    menu.append(&PredefinedMenuItem::separator(app)?)?;
+    menu.append(&MenuItem::with_id(app, "stop", "Stop Speaking", true, None::<&str>)?)?;
+    menu.append(&MenuItem::with_id(app, "repeat", "Repeat Last Answer", true, None::<&str>)?)?;
+    menu.append(&PredefinedMenuItem::separator(app)?)?;
+    menu.append(&MenuItem::with_id(app, "quit", "Quit OpenCode Talk", true, None::<&str>)?)?;
+
+    Ok(menu)
+}
+
+/// Rebuild the tray menu if anything it shows has changed
+pub fn refresh_menu(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    let snapshot = snapshot(app);
+    let mut shown = SHOWN_MENU.lock().unwrap();
+    if shown.as_ref() == Some(&snapshot) {
+        return;
+    }
+
+    match build_menu(app, &snapshot).and_then(|menu| tray.set_menu(Some(menu))) {
+        Ok(()) => *shown = Some(snapshot),
+        Err(e) => eprintln!("[TRAY] Failed to update the menu: {}", e),
+    }
+}
+
+// Ask the OpenCode server whether it's up and for its latest sessions
+async fn poll_server(server_url: &str) -> (bool, Vec<RecentSession>) {
+    let base = server_url.trim_end_matches('/');
+    let healthy = http::send(http::Endpoint::Health, http::client().get(format!("{}/global/health", base)))
+        .await
+        .is_ok_and(|response| response.status().is_success());
+    if !healthy {
+        return (false, Vec::new());
+    }
+
+    let request = http::client().get(format!("{}/session", base));
+    let mut sessions = match http::send(http::Endpoint::OpenCode, request).await {
+        Ok(response) => response.json::<Vec<RecentSession>>().await.unwrap_or_else(|e| {
+            eprintln!("[TRAY] Unexpected session list: {}", e);
+            Vec::new()
+        }),
+        Err(e) => {
+            eprintln!("[TRAY] Failed to list sessions: {}", e);
+            Vec::new()
+        }
+    };
+    sessions.sort_by(|a, b| b.time.updated.total_cmp(&a.time.updated));
+    sessions.truncate(RECENT_SESSIONS);
+    (true, sessions)
+}
+
+// Keep the server status and recent sessions in the menu up to date
+fn start_server_poll(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let server_url = app.state::<SharedState>().lock().unwrap().settings.server_url.clone();
+            let status = poll_server(&server_url).await;
+            *SERVER.lock().unwrap() = Some(status);
+            refresh_menu(&app);
+            tokio::time::sleep(SERVER_POLL_INTERVAL).await;
+        }
+    });
+}
+
+// Turn a menu item id into an action (None for the ones handled here)
+fn menu_action(id: &str) -> Option<TrayAction> {
+    if let Some(session) = id.strip_prefix("session:") {
+        let title = SERVER
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|(_, sessions)| sessions.iter().find(|s| s.id == session).cloned())
+            .and_then(|s| s.title);
+        return Some(TrayAction::SelectSession { id: session.to_string(), title });
+    }
+    if let Some(engine) = id.strip_prefix("engine:") {
+        return Some(TrayAction::SetEngine(engine.to_string()));
+    }
+    if let Some(voice) = id.strip_prefix("voice:") {
+        return Some(TrayAction::SetVoice(voice.to_string()));
+    }
+    match id {
+        "mute" => Some(TrayAction::ToggleMute),
+        "stop" => Some(TrayAction::StopSpeaking),
+        "repeat" => Some(TrayAction::RepeatLastAnswer),
+        _ => None,
+    }
+}
+
+fn show_window(app: &AppHandle, label: &str) {
+    if let Some(window) = app.get_webview_window(label) {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// Create the tray icon and its menu, and start polling the OpenCode server
+pub fn setup(
+    app: &AppHandle,
+    on_action: impl Fn(&AppHandle, TrayAction) + Send + Sync + 'static,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let snapshot = snapshot(app);
+    let menu = build_menu(app, &snapshot)?;
+
+    let icon = match ICONS.as_ref() {
+        Some(icons) => icons.image(&icons.idle),
+        None => app.default_window_icon().unwrap().clone(),
+    };
+
+    let _tray = TrayIconBuilder::with_id(TRAY_ID)
+        .icon(icon)
+        .tooltip(format!("OpenCode Talk: {}", state_label(&VoiceState::Idle)))
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                let app = tray.app_handle();
+                if let Some(window) = app.get_webview_window("main") {
+                    if window.is_visible().unwrap_or(false) {
+                        let _ = window.hide();
+                    } else {
+                        let _ = window.show();
+                        let _ = window.set_focus();
+                    }
+                }
+            }
+        })
+        .on_menu_event(move |app, event| match event.id.as_ref() {
+            "show" => show_window(app, "main"),
+            "settings" => show_window(app, "settings"),
+            "quit" => app.exit(0),
+            id => {
+                if let Some(action) = menu_action(id) {
+                    on_action(app, action);
+                }
+                // Check items toggle themselves when clicked; redraw from the
+                // real state in case the action didn't change it
+                *SHOWN_MENU.lock().unwrap() = None;
+                refresh_menu(app);
+            }
+        })
+        .build(app)?;
+    *SHOWN_MENU.lock().unwrap() = Some(snapshot);
+
+    start_server_poll(app.clone());
+    Ok(())
+}
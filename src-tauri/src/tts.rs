@@ -3,16 +3,32 @@ use std::collections::HashSet;
 use std::fs::File;
 use std::io::BufReader;
 use std::sync::mpsc as std_mpsc;
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 use tokio::process::Command;
-use tokio::sync::{Mutex, mpsc, watch};
+use tokio::sync::{Mutex, mpsc, oneshot, watch};
 use std::sync::Arc;
 use once_cell::sync::Lazy;
-use rodio::{Decoder, OutputStream, Sink};
+use rodio::{Decoder, OutputStream, Sink, Source};
+use crate::echo::ReferenceTap;
+use crate::http::{self, Endpoint};
+use crate::pcm_stream::{pcm_stream, PcmStreamSource};
+use crate::language;
+use crate::lexicon;
+use crate::prosody::{self, Span, TextSpan};
+use crate::replay::{self, Capture, CapturedAudio};
+use crate::text_normalizer;
+use crate::voice_profile::{self, Role, Voice};
 
 // Commands sent to the dedicated audio playback thread
 enum AudioCommand {
     Play(String),  // file path to play
+    Stream(PcmStreamSource),  // PCM that is still arriving from the network
+    Pause(Duration),  // silence between prosody spans
+    Replay(CapturedAudio),  // audio cached from an earlier replay
+    BeginCapture(String),  // keep what's played from here on under a replay cache key
+    EndCapture,    // everything for the replay has been queued
+    Mark(oneshot::Sender<()>),  // signal once what's queued before it has played
     Stop,          // stop current playback and clear queue
     Shutdown,      // exit the audio thread
 }
@@ -21,10 +37,28 @@ enum AudioCommand {
 static AUDIO_TX: Lazy<std::sync::Mutex<Option<std_mpsc::Sender<AudioCommand>>>> = 
     Lazy::new(|| std::sync::Mutex::new(None));
 
-// Audio queue for streaming TTS playback - takes generation tasks
-static GENERATION_QUEUE: Lazy<Arc<Mutex<Option<mpsc::Sender<GenerationTask>>>>> = 
+// Audio queue for streaming TTS playback - takes generation tasks. Unbounded,
+// so no sentence is dropped when synthesis falls behind a fast response.
+static GENERATION_QUEUE: Lazy<Arc<Mutex<Option<mpsc::UnboundedSender<GenerationTask>>>>> = 
     Lazy::new(|| Arc::new(Mutex::new(None)));
 
+// Whether audio is coming out of the speakers, so speech input can ignore it
+// (see `is_playing`): the sink is non-empty, or an engine that plays by itself
+// (`say`) is running. Only the sink's output is tapped as an echo reference.
+static SINK_PLAYING: AtomicBool = AtomicBool::new(false);
+static DIRECT_PLAYBACK: AtomicUsize = AtomicUsize::new(0);
+static PLAYBACK_ENDED: Lazy<std::sync::Mutex<Option<Instant>>> = Lazy::new(|| std::sync::Mutex::new(None));
+static DIRECT_PLAYBACK_ENDED: Lazy<std::sync::Mutex<Option<Instant>>> = Lazy::new(|| std::sync::Mutex::new(None));
+
+// The room keeps echoing briefly after playback stops
+const PLAYBACK_TAIL: Duration = Duration::from_millis(300);
+
+// Voice muted from the settings or the tray: nothing new is spoken
+static MUTED: AtomicBool = AtomicBool::new(false);
+
+// Playback volume (f32 bits), applied to the sink; `say` plays at its own volume
+static VOLUME: AtomicU32 = AtomicU32::new(1.0f32.to_bits());
+
 // Stop signal using watch channel for proper synchronization
 static STOP_SIGNAL: Lazy<Arc<Mutex<Option<watch::Sender<bool>>>>> = 
     Lazy::new(|| Arc::new(Mutex::new(None)));
@@ -33,12 +67,68 @@ static STOP_SIGNAL: Lazy<Arc<Mutex<Option<watch::Sender<bool>>>>> =
 static PENDING_TEMP_FILES: Lazy<Arc<Mutex<HashSet<String>>>> = 
     Lazy::new(|| Arc::new(Mutex::new(HashSet::new())));
 
+// User-configured command for the "command" engine (set from frontend settings)
+static CUSTOM_COMMAND: Lazy<std::sync::Mutex<CustomCommand>> = 
+    Lazy::new(|| std::sync::Mutex::new(CustomCommand::default()));
+
+// How long a custom TTS command may run before it is killed
+const CUSTOM_COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A user-supplied TTS command line
+/// 
+/// The template may use `{text}`, `{voice}`, `{speed}` and `{output}` placeholders.
+/// Without `{text}` the text is written to stdin; without `{output}` the audio is
+/// read from stdout.
+#[derive(Debug, Clone, Default)]
+pub struct CustomCommand {
+    pub template: String,
+    pub output_format: String,
+}
+
+// Server settings for the "openai" engine (set from frontend settings)
+static OPENAI_CONFIG: Lazy<std::sync::Mutex<OpenAiSpeechConfig>> = 
+    Lazy::new(|| std::sync::Mutex::new(OpenAiSpeechConfig::default()));
+
+// Local Kokoro server started by the app (see scripts/kokoro_server.py)
+const KOKORO_URL: &str = "http://127.0.0.1:7892";
+
+// OpenAI's "pcm" response format is 24kHz mono signed 16-bit little-endian
+const OPENAI_PCM_SAMPLE_RATE: u32 = 24000;
+
+/// Connection settings for an OpenAI-compatible `/v1/audio/speech` server
+/// (OpenAI itself, Kokoro-FastAPI, openedai-speech, LocalAI, ...)
+#[derive(Debug, Clone)]
+pub struct OpenAiSpeechConfig {
+    pub base_url: String,
+    pub model: String,
+    pub api_key: Option<String>,
+}
+
+impl Default for OpenAiSpeechConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "https://api.openai.com".to_string(),
+            model: "tts-1".to_string(),
+            api_key: None,
+        }
+    }
+}
+
+// Result of a generation step: a finished file, or audio already streaming to the sink
+enum GeneratedAudio {
+    File(String),
+    Streamed,
+}
+
 #[derive(Debug)]
 struct GenerationTask {
     text: String,
     voice: String,
     speed: f32,
     engine: String,
+    agent: String,     // OpenCode agent the sentence came from
+    role: Role,        // Kind of message, for the voice profile
+    language: String,  // ISO 639-1, filled in when the sentence is routed
 }
 
 /// Initialize the audio player background tasks
@@ -51,7 +141,7 @@ struct GenerationTask {
 /// Using rodio with a persistent Sink eliminates the ~1.3s overhead per sentence
 /// that afplay subprocess spawning caused.
 pub async fn init_audio_player() {
-    let (gen_tx, mut gen_rx) = mpsc::channel::<GenerationTask>(64);
+    let (gen_tx, mut gen_rx) = mpsc::unbounded_channel::<GenerationTask>();
     let (stop_tx, stop_rx) = watch::channel(false);
     let (audio_tx, audio_rx) = std_mpsc::channel::<AudioCommand>();
     
@@ -96,6 +186,8 @@ pub async fn init_audio_player() {
         
         // Track pending files for cleanup
         let mut pending_files: Vec<String> = Vec::new();
+        // Replay whose audio is being kept for the cache
+        let mut capture: Option<Capture> = None;
         
         loop {
             // Use recv_timeout so we can periodically check if sink is empty for cleanup
@@ -105,7 +197,7 @@ pub async fn init_audio_player() {
                         Ok(file) => {
                             match Decoder::new(BufReader::new(file)) {
                                 Ok(source) => {
-                                    sink.append(source);
+                                    append_source(&sink, capture.as_ref(), source);
                                     pending_files.push(path);
                                 }
                                 Err(e) => {
@@ -119,7 +211,43 @@ pub async fn init_audio_player() {
                         }
                     }
                 }
+                Ok(AudioCommand::Stream(source)) => {
+                    append_source(&sink, capture.as_ref(), source);
+                }
+                Ok(AudioCommand::Pause(duration)) => {
+                    let silence = rodio::source::Zero::<i16>::new(1, 24000).take_duration(duration);
+                    match capture {
+                        Some(ref capture) => sink.append(capture.tap(silence)),
+                        None => sink.append(silence),
+                    }
+                }
+                Ok(AudioCommand::Replay(audio)) => {
+                    for segment in audio.iter() {
+                        sink.append(ReferenceTap::new(segment.source()));
+                    }
+                }
+                Ok(AudioCommand::BeginCapture(key)) => {
+                    if let Some(previous) = capture.replace(Capture::begin(key)) {
+                        previous.abandon();
+                    }
+                }
+                Ok(AudioCommand::EndCapture) => {
+                    if let Some(capture) = capture.take() {
+                        capture.end();
+                    }
+                }
+                Ok(AudioCommand::Mark(done)) => {
+                    let done = std::sync::Mutex::new(Some(done));
+                    sink.append(rodio::source::EmptyCallback::<i16>::new(Box::new(move || {
+                        if let Some(done) = done.lock().unwrap().take() {
+                            let _ = done.send(());
+                        }
+                    })));
+                }
                 Ok(AudioCommand::Stop) => {
+                    if let Some(capture) = capture.take() {
+                        capture.abandon();
+                    }
                     sink.clear();
                     // Clean up all pending files
                     for f in pending_files.drain(..) {
@@ -146,8 +274,12 @@ pub async fn init_audio_player() {
                     break;
                 }
             }
+            
+            sink.set_volume(volume());
+            set_sink_playing(!sink.empty());
         }
         
+        set_sink_playing(false);
         eprintln!("[TTS-AUDIO] Audio thread shutting down");
     });
     
@@ -186,45 +318,40 @@ pub async fn init_audio_player() {
                         continue;
                     }
                     
+                    // Markdown and code don't read well aloud - every engine gets plain speech text
+                    let spans = prepare_for_speech(&task.text);
+                    if spans.is_empty() {
+                        continue;
+                    }
+                    
+                    // Apply the agent's and role's voice profile, then route the
+                    // sentence to the voice configured for its language
+                    let profile = resolve_voice(&task.engine, &task.voice, task.speed, &task.agent, task.role);
+                    let route = language::route(&prosody::plain_text(&spans), &profile.engine, &profile.voice);
+                    let task = GenerationTask {
+                        engine: route.engine,
+                        voice: route.voice,
+                        speed: profile.speed,
+                        language: route.language,
+                        ..task
+                    };
+                    
                     let gen_start = std::time::Instant::now();
                     eprintln!("[TTS-GEN] Starting generation for: {}...", &task.text.chars().take(30).collect::<String>());
                     
-                    // Generate the audio based on engine
-                    let audio_result = match task.engine.as_str() {
-                        "piper" => generate_piper_audio(&task.text, &task.voice, task.speed).await,
-                        "kokoro" => generate_kokoro_audio_fast(&task.text, &task.voice, task.speed).await,
-                        _ => generate_kokoro_audio_fast(&task.text, &task.voice, task.speed).await,
+                    let queued = if task.engine == "espeak" {
+                        // espeak-ng understands the prosody markup natively (as SSML)
+                        let audio_result = generate_espeak_audio(&spans, &task.voice, task.speed).await.map(GeneratedAudio::File);
+                        queue_generated_audio(audio_result, &gen_stop_rx).await
+                    } else {
+                        generate_spans(&task, &spans, &gen_stop_rx).await
                     };
                     
                     eprintln!("[TTS-GEN] Generation took: {:?}", gen_start.elapsed());
                     
-                    match audio_result {
-                        Ok(file_path) => {
-                            // Check stop signal again before queueing for playback
-                            if *gen_stop_rx.borrow() {
-                                let _ = tokio::fs::remove_file(&file_path).await;
-                                let mut pending = PENDING_TEMP_FILES.lock().await;
-                                pending.remove(&file_path);
-                                continue;
-                            }
-                            
-                            // Track the temp file
-                            {
-                                let mut pending = PENDING_TEMP_FILES.lock().await;
-                                pending.insert(file_path.clone());
-                            }
-                            
-                            // Send to audio thread for playback
-                            if let Some(ref tx) = *AUDIO_TX.lock().unwrap() {
-                                if tx.send(AudioCommand::Play(file_path)).is_err() {
-                                    eprintln!("[TTS-GEN] Audio thread disconnected");
-                                    break;
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            eprintln!("[TTS-GEN] Error generating audio: {}", e);
-                        }
+                    if !queued {
+                        eprintln!("[TTS-GEN] Audio thread disconnected");
+                        break;
                     }
                 }
             }
@@ -232,22 +359,142 @@ pub async fn init_audio_player() {
     });
 }
 
+// Queue a source on the sink, tapped for the echo reference and, during a
+// replay, for the cache
+fn append_source<S>(sink: &Sink, capture: Option<&Capture>, source: S)
+where
+    S: Source<Item = i16> + Send + 'static,
+{
+    match capture {
+        Some(capture) => sink.append(ReferenceTap::new(capture.tap(source))),
+        None => sink.append(ReferenceTap::new(source)),
+    }
+}
+
+/// Synthesize a sentence span by span, queueing silence for pauses
+/// Used for engines without their own prosody markup.
+/// Returns false if the audio thread has gone away.
+async fn generate_spans(task: &GenerationTask, spans: &[Span], stop_rx: &watch::Receiver<bool>) -> bool {
+    for span in spans {
+        if *stop_rx.borrow() {
+            break;
+        }
+        
+        let queued = match span {
+            Span::Pause(duration) => match *AUDIO_TX.lock().unwrap() {
+                Some(ref tx) => tx.send(AudioCommand::Pause(*duration)).is_ok(),
+                None => true,
+            },
+            Span::Text(text_span) => {
+                let audio_result = generate_span_audio(task, text_span, stop_rx).await;
+                queue_generated_audio(audio_result, stop_rx).await
+            }
+        };
+        if !queued {
+            return false;
+        }
+    }
+    
+    true
+}
+
+/// Generate audio for one span, falling back to espeak-ng if the engine fails
+async fn generate_span_audio(
+    task: &GenerationTask,
+    span: &TextSpan,
+    stop_rx: &watch::Receiver<bool>,
+) -> Result<GeneratedAudio, String> {
+    let text = span.spoken_text();
+    let voice = task.voice.as_str();
+    let speed = task.speed * span.effective_rate();
+    let lang_code = language::kokoro_lang_code(&task.language, voice);
+    
+    let audio_result = match task.engine.as_str() {
+        "piper" => generate_piper_audio(&text, voice, speed).await.map(GeneratedAudio::File),
+        "kokoro" => generate_kokoro_audio_streamed(&text, voice, speed, lang_code, stop_rx).await,
+        "command" => generate_custom_command_audio(&text, voice, speed).await.map(GeneratedAudio::File),
+        "openai" => stream_openai_speech(&text, voice, speed, Some(stop_rx)).await.map(|_| GeneratedAudio::Streamed),
+        _ => generate_kokoro_audio_streamed(&text, voice, speed, lang_code, stop_rx).await,
+    };
+    
+    // espeak-ng is the last resort when every other engine has failed
+    match audio_result {
+        Err(e) => {
+            eprintln!("[TTS-GEN] {} failed ({}), falling back to espeak-ng", task.engine, e);
+            let fallback_voice = language::espeak_voice(&task.language);
+            generate_espeak_audio(&[Span::Text(span.clone())], fallback_voice, task.speed).await.map(GeneratedAudio::File)
+        }
+        other => other,
+    }
+}
+
+/// Hand generated audio to the audio thread
+/// Returns false if the audio thread has gone away
+async fn queue_generated_audio(audio_result: Result<GeneratedAudio, String>, stop_rx: &watch::Receiver<bool>) -> bool {
+    match audio_result {
+        Ok(GeneratedAudio::Streamed) => true,
+        Ok(GeneratedAudio::File(file_path)) => {
+            // Check stop signal again before queueing for playback
+            if *stop_rx.borrow() {
+                let _ = tokio::fs::remove_file(&file_path).await;
+                let mut pending = PENDING_TEMP_FILES.lock().await;
+                pending.remove(&file_path);
+                return true;
+            }
+            
+            // Track the temp file
+            {
+                let mut pending = PENDING_TEMP_FILES.lock().await;
+                pending.insert(file_path.clone());
+            }
+            
+            // Send to audio thread for playback
+            match *AUDIO_TX.lock().unwrap() {
+                Some(ref tx) => tx.send(AudioCommand::Play(file_path)).is_ok(),
+                None => true,
+            }
+        }
+        Err(e) => {
+            eprintln!("[TTS-GEN] Error generating audio: {}", e);
+            true
+        }
+    }
+}
+
+/// Unique temp file path for generated audio
+fn temp_audio_path(engine: &str, extension: &str) -> String {
+    format!("/tmp/opencode-talk-{}-{}-{}.{}", 
+        engine,
+        std::process::id(), 
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+        extension
+    )
+}
+
 /// Queue a sentence for TTS generation and playback
 /// Returns immediately - generation happens in order in background
-pub async fn speak_sentence(text: &str, voice: &str, speed: f32, engine: &str) -> Result<(), String> {
+pub async fn speak_sentence(text: &str, voice: &str, speed: f32, engine: &str, agent: &str, role: Role) -> Result<(), String> {
+    if is_muted() {
+        return Ok(());
+    }
+    
     let task = GenerationTask {
         text: text.to_string(),
         voice: voice.to_string(),
         speed,
         engine: engine.to_string(),
+        agent: agent.to_string(),
+        role,
+        language: String::new(),
     };
     
     // Queue the task - this returns immediately
     let queue = GENERATION_QUEUE.lock().await;
     if let Some(ref tx) = *queue {
-        tx.send(task)
-            .await
-            .map_err(|e| format!("Failed to queue task: {}", e))?;
+        tx.send(task).map_err(|e| format!("Failed to queue task: {}", e))?;
     } else {
         return Err("Audio player not initialized".to_string());
     }
@@ -255,30 +502,55 @@ pub async fn speak_sentence(text: &str, voice: &str, speed: f32, engine: &str) -
     Ok(())
 }
 
+/// Queue a sentence from synchronous code (e.g. a non-async command)
+/// Unlike `speak_sentence` this never waits, so calls are queued in exactly the
+/// order they are made. Must not be called from inside the async runtime.
+pub fn queue_sentence(text: &str, voice: &str, speed: f32, engine: &str, agent: &str, role: Role) -> Result<(), String> {
+    if is_muted() {
+        return Ok(());
+    }
+    
+    let task = GenerationTask {
+        text: text.to_string(),
+        voice: voice.to_string(),
+        speed,
+        engine: engine.to_string(),
+        agent: agent.to_string(),
+        role,
+        language: String::new(),
+    };
+    
+    let queue = GENERATION_QUEUE.blocking_lock();
+    match *queue {
+        Some(ref tx) => tx.send(task).map_err(|e| format!("Failed to queue task: {}", e)),
+        None => Err("Audio player not initialized".to_string()),
+    }
+}
+
 /// Generate audio file using Piper TTS, returns the file path
 /// Fast local TTS - typically 0.5-1 second per sentence
 async fn generate_piper_audio(text: &str, voice: &str, speed: f32) -> Result<String, String> {
-    let temp_file = format!("/tmp/opencode-talk-piper-{}-{}.wav", 
-        std::process::id(), 
-        std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_nanos()
-    );
+    let temp_file = temp_audio_path("piper", "wav");
     
     // Piper path
     let piper_path = dirs::home_dir()
         .map(|h| h.join("Library/Python/3.9/bin/piper"))
         .unwrap_or_else(|| std::path::PathBuf::from("piper"));
     
-    // Default voice model path if not specified
+    // Voice models live in ~/.local/share/piper-voices; a voice can be a full
+    // .onnx path or a model name from there (e.g. "de_DE-thorsten-high")
+    let voices_dir = dirs::home_dir()
+        .map(|h| h.join(".local/share/piper-voices"))
+        .unwrap_or_default();
+    let named_model = voices_dir.join(format!("{}.onnx", voice));
     let model_path = if voice.ends_with(".onnx") {
         voice.to_string()
+    } else if !voice.is_empty() && !voice.contains('/') && named_model.exists() {
+        named_model.to_string_lossy().to_string()
     } else {
         // Use the lessac-high voice by default (best quality)
-        dirs::home_dir()
-            .map(|h| h.join(".local/share/piper-voices/en_US-lessac-high.onnx"))
-            .unwrap_or_else(|| std::path::PathBuf::from("en_US-lessac-high.onnx"))
+        voices_dir
+            .join("en_US-lessac-high.onnx")
             .to_string_lossy()
             .to_string()
     };
@@ -316,47 +588,303 @@ async fn generate_piper_audio(text: &str, voice: &str, speed: f32) -> Result<Str
     Ok(temp_file)
 }
 
-/// Generate audio using Kokoro server (fast - model stays warm)
-async fn generate_kokoro_audio_fast(text: &str, voice: &str, speed: f32) -> Result<String, String> {
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
-        .build()
-        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
-    
-    let response = client
-        .post("http://127.0.0.1:7892/tts")
+/// Generate audio file using espeak-ng, returns the file path
+/// Robotic but available on virtually every Linux box - used as the last-resort voice.
+/// The spans are passed as SSML, so pauses, emphasis, rate and spell-out are native.
+async fn generate_espeak_audio(spans: &[Span], voice: &str, speed: f32) -> Result<String, String> {
+    let temp_file = temp_audio_path("espeak", "wav");
+    
+    let output = espeak_command(voice, speed)
+        .args(["-m", "-w", &temp_file, "--", &prosody::to_ssml(spans)])
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run espeak-ng: {}", e))?;
+    
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("espeak-ng failed: {}", stderr));
+    }
+    
+    Ok(temp_file)
+}
+
+/// Build an espeak-ng command with voice and rate applied
+/// Falls back to plain `espeak` if espeak-ng isn't on the PATH
+fn espeak_command(voice: &str, speed: f32) -> Command {
+    let binary = *ESPEAK_BINARY;
+    
+    // espeak voices look like "en-us" or "de"; anything else (e.g. a Kokoro
+    // voice left over from another engine) gets the default English voice
+    let voice = if !voice.is_empty()
+        && voice.len() <= 12
+        && voice.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '+')
+    {
+        voice
+    } else {
+        "en-us"
+    };
+    
+    // Convert speed to words per minute (espeak default is 175 wpm)
+    let rate = (175.0 * speed).clamp(80.0, 450.0) as u32;
+    
+    let mut cmd = Command::new(binary);
+    cmd.args(["-v", voice, "-s", &rate.to_string()]);
+    cmd
+}
+
+// Looked up once, since `which` blocks and espeak commands are built in async code
+static ESPEAK_BINARY: Lazy<&str> = Lazy::new(|| if which_sync("espeak-ng") { "espeak-ng" } else { "espeak" });
+
+/// Check if a command exists in PATH (blocking)
+fn which_sync(command: &str) -> bool {
+    std::process::Command::new("which")
+        .arg(command)
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+/// Set the command template used by the "command" engine
+pub fn set_custom_command(template: &str, output_format: &str) {
+    let mut command = CUSTOM_COMMAND.lock().unwrap();
+    command.template = template.to_string();
+    command.output_format = output_format.to_string();
+}
+
+/// Generate audio file by running the user's custom command, returns the file path
+/// 
+/// The template is split into arguments before placeholders are substituted and
+/// the program is run directly (no shell), so the spoken text can never be
+/// interpreted as shell syntax.
+async fn generate_custom_command_audio(text: &str, voice: &str, speed: f32) -> Result<String, String> {
+    let command = CUSTOM_COMMAND.lock().unwrap().clone();
+    if command.template.trim().is_empty() {
+        return Err("No custom TTS command configured".to_string());
+    }
+    
+    let extension = match command.output_format.as_str() {
+        "mp3" => "mp3",
+        _ => "wav",
+    };
+    let temp_file = temp_audio_path("command", extension);
+    
+    let words = shlex::split(&command.template)
+        .ok_or("Custom TTS command has unbalanced quotes")?;
+    let text_in_args = command.template.contains("{text}");
+    let output_in_args = command.template.contains("{output}");
+    let speed_str = speed.to_string();
+    
+    let args: Vec<String> = words
+        .iter()
+        .map(|word| {
+            word.replace("{voice}", voice)
+                .replace("{speed}", &speed_str)
+                .replace("{output}", &temp_file)
+                .replace("{text}", text)
+        })
+        .collect();
+    let (program, args) = args.split_first().ok_or("Custom TTS command is empty")?;
+    
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(if text_in_args { Stdio::null() } else { Stdio::piped() })
+        .stdout(if output_in_args { Stdio::null() } else { Stdio::piped() })
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", program, e))?;
+    
+    // Feed the text on stdin when the template doesn't take it as an argument,
+    // while reading the output so neither pipe can fill up and stall. A command
+    // that never reads its input runs into the timeout too.
+    let stdin = child.stdin.take();
+    let feed = async {
+        if let Some(mut stdin) = stdin {
+            use tokio::io::AsyncWriteExt;
+            stdin.write_all(text.as_bytes()).await?;
+        } // Dropping stdin closes it to signal EOF
+        Ok::<(), std::io::Error>(())
+    };
+    let (fed, output) = tokio::time::timeout(CUSTOM_COMMAND_TIMEOUT, async {
+        tokio::join!(feed, child.wait_with_output())
+    })
+    .await
+    .map_err(|_| format!("{} timed out after {:?}", program, CUSTOM_COMMAND_TIMEOUT))?;
+    fed.map_err(|e| format!("Failed to write to {}: {}", program, e))?;
+    let output = output.map_err(|e| format!("Failed to wait for {}: {}", program, e))?;
+    
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let _ = tokio::fs::remove_file(&temp_file).await;
+        return Err(format!("{} failed: {}", program, stderr));
+    }
+    
+    // Audio on stdout - write it to the temp file for the audio thread
+    if !output_in_args {
+        if output.stdout.is_empty() {
+            return Err(format!("{} produced no audio", program));
+        }
+        tokio::fs::write(&temp_file, &output.stdout).await
+            .map_err(|e| format!("Failed to write audio: {}", e))?;
+    }
+    
+    Ok(temp_file)
+}
+
+/// Check whether the Kokoro server is up and has its model loaded
+pub async fn kokoro_health() -> bool {
+    let request = http::client().get(format!("{}/health", KOKORO_URL));
+    match http::send(Endpoint::Health, request).await {
+        Ok(response) if response.status().is_success() => response
+            .json::<serde_json::Value>()
+            .await
+            .map(|v| v["model_loaded"].as_bool().unwrap_or(true))
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// Set the server used by the "openai" engine
+pub fn set_openai_config(config: OpenAiSpeechConfig) {
+    *OPENAI_CONFIG.lock().unwrap() = config;
+}
+
+/// Synthesize via an OpenAI-compatible `/v1/audio/speech` endpoint
+/// 
+/// Requests raw PCM and forwards each chunk to the audio thread as it arrives,
+/// so playback starts before the server has finished generating. Returns once
+/// the whole response has been received (or playback was stopped).
+async fn stream_openai_speech(
+    text: &str,
+    voice: &str,
+    speed: f32,
+    stop_rx: Option<&watch::Receiver<bool>>,
+) -> Result<(), String> {
+    let config = OPENAI_CONFIG.lock().unwrap().clone();
+    let url = format!("{}/v1/audio/speech", config.base_url.trim_end_matches('/'));
+    
+    let mut request = http::client()
+        .post(&url)
         .json(&serde_json::json!({
-            "text": text,
+            "model": config.model,
+            "input": text,
             "voice": voice,
-            "speed": speed
-        }))
-        .send()
-        .await;
-    
-    // If Kokoro server fails, fall back to Piper
-    let response = match response {
-        Ok(r) if r.status().is_success() => r,
-        Ok(r) => {
-            eprintln!("Kokoro server returned {}, falling back to Piper", r.status());
-            return generate_piper_audio(text, voice, speed).await;
+            "speed": speed,
+            "response_format": "pcm"
+        }));
+    if let Some(key) = config.api_key.as_deref().filter(|k| !k.is_empty()) {
+        request = request.bearer_auth(key);
+    }
+    
+    let response = http::send(Endpoint::Speech, request)
+        .await
+        .map_err(|e| format!("Speech server unavailable ({}): {}", url, e))?;
+    
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("Speech server returned {}: {}", status, body));
+    }
+    
+    play_pcm_response(response, OPENAI_PCM_SAMPLE_RATE, stop_rx).await
+}
+
+/// Play a response body of 16-bit mono PCM while it is still downloading
+/// 
+/// The source is handed to the audio thread first and fed as chunks arrive.
+/// Returns once the whole body has been received or playback was stopped.
+async fn play_pcm_response(
+    mut response: reqwest::Response,
+    sample_rate: u32,
+    stop_rx: Option<&watch::Receiver<bool>>,
+) -> Result<(), String> {
+    let (mut writer, source) = pcm_stream(sample_rate, 1);
+    if let Some(ref tx) = *AUDIO_TX.lock().unwrap() {
+        tx.send(AudioCommand::Stream(source))
+            .map_err(|_| "Audio thread disconnected".to_string())?;
+    } else {
+        return Err("Audio player not initialized".to_string());
+    }
+    
+    loop {
+        // Once playback has started a broken stream just ends the sentence early;
+        // falling back to another engine would repeat what was already heard
+        let chunk = match response.chunk().await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break,
+            Err(e) => {
+                eprintln!("[TTS-GEN] Speech stream interrupted: {}", e);
+                break;
+            }
+        };
+        if stop_rx.is_some_and(|rx| *rx.borrow()) {
+            break;
         }
-        Err(e) => {
-            eprintln!("Kokoro server unavailable ({}), falling back to Piper", e);
-            return generate_piper_audio(text, voice, speed).await;
+        // The sink dropped our source - playback was stopped
+        if !writer.write(&chunk) {
+            break;
         }
-    };
+    }
     
-    let result: serde_json::Value = response
-        .json()
+    Ok(())
+}
+
+/// Generate audio using Kokoro server (fast - model stays warm)
+/// 
+/// Uses the server's streaming endpoint so playback starts with the first
+/// synthesized chunk instead of after the whole sentence. Falls back to Piper
+/// when the server is unavailable.
+async fn generate_kokoro_audio_streamed(
+    text: &str,
+    voice: &str,
+    speed: f32,
+    lang_code: &str,
+    stop_rx: &watch::Receiver<bool>,
+) -> Result<GeneratedAudio, String> {
+    match stream_kokoro_audio(text, voice, speed, lang_code, stop_rx).await {
+        Ok(()) => Ok(GeneratedAudio::Streamed),
+        Err(e) => {
+            eprintln!("{}, falling back to Piper", e);
+            generate_piper_audio(text, voice, speed).await.map(GeneratedAudio::File)
+        }
+    }
+}
+
+/// Stream PCM from the Kokoro server's `/tts/stream` endpoint into the audio thread
+async fn stream_kokoro_audio(
+    text: &str,
+    voice: &str,
+    speed: f32,
+    lang_code: &str,
+    stop_rx: &watch::Receiver<bool>,
+) -> Result<(), String> {
+    let request = http::client()
+        .post(format!("{}/tts/stream", KOKORO_URL))
+        .json(&serde_json::json!({
+            "text": text,
+            "voice": voice,
+            "speed": speed,
+            "lang_code": lang_code
+        }));
+    let response = http::send(Endpoint::Kokoro, request)
         .await
-        .map_err(|e| format!("Failed to parse Kokoro response: {}", e))?;
+        .map_err(|e| format!("Kokoro server unavailable ({})", e))?;
+    
+    if !response.status().is_success() {
+        return Err(format!("Kokoro server returned {}", response.status()));
+    }
     
-    let file_path = result["file"]
-        .as_str()
-        .ok_or("Missing file path in response")?
-        .to_string();
+    // The server announces its sample rate; Kokoro itself always uses 24kHz
+    let sample_rate = response
+        .headers()
+        .get("X-Sample-Rate")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(24000);
     
-    Ok(file_path)
+    play_pcm_response(response, sample_rate, Some(stop_rx)).await
 }
 
 /// Clear the audio queue and stop current playback
@@ -396,20 +924,215 @@ pub async fn clear_audio_queue() -> Result<(), String> {
     Ok(())
 }
 
+/// Turn assistant markdown into what is handed to an engine: normalized for
+/// speech, with the pronunciation lexicon applied, split into prosody spans
+pub fn prepare_for_speech(text: &str) -> Vec<Span> {
+    let protected = prosody::protect(text);
+    let prepared = lexicon::apply(&text_normalizer::normalize_for_speech(&protected.text));
+    protected.into_spans(&prepared)
+}
+
+/// Apply the voice profile for an agent and message role to the selected voice
+fn resolve_voice(engine: &str, voice: &str, speed: f32, agent: &str, role: Role) -> Voice {
+    let base = Voice {
+        engine: engine.to_string(),
+        voice: voice.to_string(),
+        speed,
+    };
+    voice_profile::resolve(base, agent, role)
+}
+
+/// Whether TTS audio is playing (or has just stopped), so microphone input
+/// can be ignored instead of transcribing the assistant's own voice
+pub fn is_playing() -> bool {
+    SINK_PLAYING.load(Ordering::Relaxed) || DIRECT_PLAYBACK.load(Ordering::Relaxed) > 0 || ended_recently(&PLAYBACK_ENDED)
+}
+
+/// Wait until what has been queued so far has played (or was stopped), and
+/// return when the room should be quiet again
+pub async fn played_out() -> Instant {
+    let (done_tx, done_rx) = oneshot::channel();
+    let sent = match *AUDIO_TX.lock().unwrap() {
+        Some(ref tx) => tx.send(AudioCommand::Mark(done_tx)).is_ok(),
+        None => false,
+    };
+    // A cleared sink drops the sender, which ends the wait too
+    if sent {
+        let _ = done_rx.await;
+    }
+    Instant::now() + PLAYBACK_TAIL
+}
+
+/// Whether audio is playing that `echo` has no reference for, so input has
+/// to be ignored outright rather than echo-suppressed
+pub fn is_playing_unreferenced() -> bool {
+    DIRECT_PLAYBACK.load(Ordering::Relaxed) > 0 || ended_recently(&DIRECT_PLAYBACK_ENDED)
+}
+
+/// Mute or unmute the voice (already queued audio is left to the caller)
+pub fn set_muted(muted: bool) {
+    MUTED.store(muted, Ordering::Relaxed);
+}
+
+pub fn is_muted() -> bool {
+    MUTED.load(Ordering::Relaxed)
+}
+
+/// Set the playback volume (1.0 is as synthesized)
+pub fn set_volume(volume: f32) {
+    VOLUME.store(volume.max(0.0).to_bits(), Ordering::Relaxed);
+}
+
+pub fn volume() -> f32 {
+    f32::from_bits(VOLUME.load(Ordering::Relaxed))
+}
+
+fn ended_recently(ended: &std::sync::Mutex<Option<Instant>>) -> bool {
+    ended
+        .lock()
+        .unwrap()
+        .is_some_and(|ended| ended.elapsed() < PLAYBACK_TAIL)
+}
+
+fn set_sink_playing(playing: bool) {
+    if SINK_PLAYING.swap(playing, Ordering::Relaxed) && !playing {
+        *PLAYBACK_ENDED.lock().unwrap() = Some(Instant::now());
+    }
+}
+
+// Marks an engine that plays by itself as playing for as long as it's alive
+struct DirectPlayback;
+
+impl DirectPlayback {
+    fn start() -> Self {
+        DIRECT_PLAYBACK.fetch_add(1, Ordering::Relaxed);
+        DirectPlayback
+    }
+}
+
+impl Drop for DirectPlayback {
+    fn drop(&mut self) {
+        DIRECT_PLAYBACK.fetch_sub(1, Ordering::Relaxed);
+        let now = Some(Instant::now());
+        *PLAYBACK_ENDED.lock().unwrap() = now;
+        *DIRECT_PLAYBACK_ENDED.lock().unwrap() = now;
+    }
+}
+
 /// Speak text using the specified TTS engine
-pub async fn speak(text: &str, engine: &str, voice: &str, speed: f32) -> Result<(), String> {
+pub async fn speak(text: &str, engine: &str, voice: &str, speed: f32, agent: &str, role: Role) -> Result<(), String> {
     // Stop any currently playing audio first
     stop_speaking().await?;
     
+    if is_muted() {
+        return Ok(());
+    }
+    
+    speak_text(text, engine, voice, speed, agent, role).await
+}
+
+/// Speak a past assistant message again with the given voice, from the
+/// replay cache when it has been played to the end with it before
+pub async fn replay(key: String, text: &str, engine: &str, voice: &str, speed: f32, agent: &str) -> Result<(), String> {
+    // Anything still being generated for the current answer would follow it
+    clear_audio_queue().await?;
+    
+    if is_muted() {
+        return Ok(());
+    }
+    
+    if let Some(audio) = replay::cached(&key) {
+        eprintln!("[TTS] Replaying from cache");
+        return match *AUDIO_TX.lock().unwrap() {
+            Some(ref tx) => tx.send(AudioCommand::Replay(audio)).map_err(|_| "Audio thread disconnected".to_string()),
+            None => Err("Audio player not initialized".to_string()),
+        };
+    }
+    
+    if let Some(ref tx) = *AUDIO_TX.lock().unwrap() {
+        let _ = tx.send(AudioCommand::BeginCapture(key));
+    }
+    let result = speak_text(text, engine, voice, speed, agent, Role::Assistant).await;
+    if let Some(ref tx) = *AUDIO_TX.lock().unwrap() {
+        let _ = tx.send(AudioCommand::EndCapture);
+    }
+    result
+}
+
+async fn speak_text(text: &str, engine: &str, voice: &str, speed: f32, agent: &str, role: Role) -> Result<(), String> {
+    let spans = prepare_for_speech(text);
+    if spans.is_empty() {
+        return Ok(());
+    }
+    
+    // Use the agent's and role's voice, then the one configured for the text's language
+    let profile = resolve_voice(engine, voice, speed, agent, role);
+    let route = language::route(&prosody::plain_text(&spans), &profile.engine, &profile.voice);
+    let (engine, voice, speed) = (route.engine.as_str(), route.voice.as_str(), profile.speed);
+    
+    // `say` and espeak-ng have their own prosody markup; the rest are segmented
+    // and fall back to espeak-ng span by span
     match engine {
-        "macos" => speak_macos(text, voice, speed).await,
-        "piper" => speak_piper(text, voice, speed).await,
-        "kokoro" => speak_kokoro(text, voice, speed).await,
-        "edge" => speak_edge(text, voice, speed).await,
+        "macos" => match speak_macos(&spans, voice, speed).await {
+            // espeak-ng is the last resort when `say` isn't usable
+            Err(e) => {
+                eprintln!("[TTS] {} failed ({}), falling back to espeak-ng", engine, e);
+                speak_espeak(&spans, language::espeak_voice(&route.language), speed).await.map_err(|_| e)
+            }
+            ok => ok,
+        },
+        "espeak" => speak_espeak(&spans, voice, speed).await,
+        "piper" | "kokoro" | "edge" | "command" | "openai" => {
+            speak_segmented(&spans, engine, voice, &route.language, speed).await
+        }
         _ => Err(format!("Unknown TTS engine: {}", engine)),
     }
 }
 
+/// Speak span by span on an engine without prosody markup, with silence
+/// queued on the audio thread for pauses. A span the engine fails on is
+/// spoken with espeak-ng instead, so what was already queued isn't repeated.
+/// 
+/// edge-tts escapes its input, so its SSML support can't be used from here.
+async fn speak_segmented(
+    spans: &[Span],
+    engine: &str,
+    voice: &str,
+    language: &str,
+    speed: f32,
+) -> Result<(), String> {
+    for span in spans {
+        let text_span = match span {
+            Span::Pause(duration) => {
+                if let Some(ref tx) = *AUDIO_TX.lock().unwrap() {
+                    let _ = tx.send(AudioCommand::Pause(*duration));
+                }
+                continue;
+            }
+            Span::Text(text_span) => text_span,
+        };
+        
+        let text = text_span.spoken_text();
+        let span_speed = speed * text_span.effective_rate();
+        let result = match engine {
+            "piper" => speak_piper(&text, voice, span_speed).await,
+            "kokoro" => speak_kokoro(&text, voice, span_speed, language::kokoro_lang_code(language, voice)).await,
+            "edge" => speak_edge(&text, voice, span_speed).await,
+            "command" => speak_custom_command(&text, voice, span_speed).await,
+            "openai" => stream_openai_speech(&text, voice, span_speed, None).await,
+            _ => return Err(format!("Unknown TTS engine: {}", engine)),
+        };
+        if let Err(e) = result {
+            eprintln!("[TTS] {} failed ({}), falling back to espeak-ng", engine, e);
+            speak_espeak(std::slice::from_ref(span), language::espeak_voice(language), speed)
+                .await
+                .map_err(|_| e)?;
+        }
+    }
+    
+    Ok(())
+}
+
 /// Stop any currently playing TTS
 pub async fn stop_speaking() -> Result<(), String> {
     // Tell audio thread to stop playback
@@ -429,12 +1152,14 @@ pub fn shutdown_audio_player() {
 }
 
 /// Speak using macOS built-in `say` command
-async fn speak_macos(text: &str, voice: &str, speed: f32) -> Result<(), String> {
+async fn speak_macos(spans: &[Span], voice: &str, speed: f32) -> Result<(), String> {
     // Convert speed to words per minute (default is ~175 wpm)
     let rate = (175.0 * speed) as u32;
+    let text = prosody::to_say_markup(spans, rate);
     
+    let _playing = DirectPlayback::start();
     let output = Command::new("say")
-        .args(["-v", voice, "-r", &rate.to_string(), text])
+        .args(["-v", voice, "-r", &rate.to_string(), &text])
         .stdout(Stdio::null())
         .stderr(Stdio::null())
         .output()
@@ -462,7 +1187,7 @@ async fn speak_piper(text: &str, voice: &str, speed: f32) -> Result<(), String>
     }
     
     // Create a temp file for the audio output
-    let temp_file = format!("/tmp/opencode-talk-{}.wav", std::process::id());
+    let temp_file = temp_audio_path("piper", "wav");
     
     // Generate audio with piper
     let mut piper_output = Command::new("piper")
@@ -499,7 +1224,7 @@ async fn speak_piper(text: &str, voice: &str, speed: f32) -> Result<(), String>
 /// Speak using Edge TTS (Microsoft neural voices)
 async fn speak_edge(text: &str, voice: &str, speed: f32) -> Result<(), String> {
     // Create a temp file for the audio output
-    let temp_file = format!("/tmp/opencode-talk-{}.mp3", std::process::id());
+    let temp_file = temp_audio_path("edge", "mp3");
     
     // edge-tts path (installed via pip)
     let edge_tts_path = dirs::home_dir()
@@ -540,10 +1265,34 @@ async fn speak_edge(text: &str, voice: &str, speed: f32) -> Result<(), String> {
     Ok(())
 }
 
+/// Speak using espeak-ng (zero-setup fallback, plays through rodio)
+async fn speak_espeak(spans: &[Span], voice: &str, speed: f32) -> Result<(), String> {
+    let temp_file = generate_espeak_audio(spans, voice, speed).await?;
+    
+    // Play using rodio via audio thread
+    if let Some(ref tx) = *AUDIO_TX.lock().unwrap() {
+        let _ = tx.send(AudioCommand::Play(temp_file));
+    }
+    
+    Ok(())
+}
+
+/// Speak using the user's custom command (plays through rodio)
+async fn speak_custom_command(text: &str, voice: &str, speed: f32) -> Result<(), String> {
+    let temp_file = generate_custom_command_audio(text, voice, speed).await?;
+    
+    // Play using rodio via audio thread
+    if let Some(ref tx) = *AUDIO_TX.lock().unwrap() {
+        let _ = tx.send(AudioCommand::Play(temp_file));
+    }
+    
+    Ok(())
+}
+
 /// Speak using Kokoro TTS (via Python)
-async fn speak_kokoro(text: &str, voice: &str, speed: f32) -> Result<(), String> {
+async fn speak_kokoro(text: &str, voice: &str, speed: f32, lang_code: &str) -> Result<(), String> {
     // Create a temp file for the audio output
-    let temp_file = format!("/tmp/opencode-talk-{}.wav", std::process::id());
+    let temp_file = temp_audio_path("kokoro", "wav");
     
     // Python script to run Kokoro
     const KOKORO_SCRIPT: &str = r#"
@@ -552,11 +1301,11 @@ try:
     from kokoro import KPipeline
     import soundfile as sf
     
-    pipeline = KPipeline(lang_code='a')
     text = sys.argv[1]
     voice = sys.argv[2]
     speed = float(sys.argv[3])
     output_file = sys.argv[4]
+    pipeline = KPipeline(lang_code=sys.argv[5])
     
     generator = pipeline(text, voice=voice, speed=speed)
     
@@ -587,7 +1336,7 @@ except Exception as e:
     
     // Run the Python script
     let output = Command::new("python3")
-        .args([&script_file, text, voice, &speed.to_string(), &temp_file])
+        .args([&script_file, text, voice, &speed.to_string(), &temp_file, lang_code])
         .output()
         .await
         .map_err(|e| format!("Failed to run Kokoro: {}", e))?;
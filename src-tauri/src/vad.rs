@@ -0,0 +1,315 @@
+//! Voice activity detection for continuous (hands-free) listening
+//!
+//! An energy-based detector splits a live or file-fed stream into utterances.
+//! Audio is cut into short frames and a frame counts as speech when it is
+//! clearly louder than the background noise floor, which is tracked as the
+//! stream goes so a fan or a noisy room doesn't count as talking. An utterance
+//! starts at the first speech frame (plus a little audio from before it, so the
+//! first syllable isn't clipped) and ends after a configurable stretch of
+//! silence. Utterances with too little speech in them (coughs, clicks) are
+//! dropped. With echo suppression on, frames explained by our own playback
+//! (see `echo`) don't count as speech either.
+
+use crate::echo;
+use crate::mic::{MicCapture, MicLevel, Recording};
+use std::collections::VecDeque;
+use std::sync::mpsc as std_mpsc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+const FRAME_MS: u32 = 30;
+
+// Audio kept from before speech starts
+const PRE_ROLL_MS: u32 = 300;
+
+// Speech must be this much louder than the noise floor (about 10dB)...
+const SPEECH_RATIO: f32 = 3.0;
+// ...and louder than this in absolute terms
+const MIN_SPEECH_RMS: f32 = 0.01;
+
+// The noise floor follows quieter frames quickly and louder ones slowly, so a
+// steady new noise source is absorbed within a few seconds
+const NOISE_FALL: f32 = 0.1;
+const NOISE_RISE: f32 = 0.005;
+const INITIAL_NOISE_FLOOR: f32 = 0.003;
+
+#[derive(Debug, Clone)]
+pub struct VadConfig {
+    /// Silence that ends an utterance
+    pub silence_timeout: Duration,
+    /// Utterances with less speech than this are dropped
+    pub min_speech: Duration,
+    /// Longer utterances are cut here
+    pub max_utterance: Duration,
+    /// Ignore the echo of TTS playback in live input (`push_captured`)
+    pub echo_suppression: bool,
+}
+
+impl Default for VadConfig {
+    fn default() -> Self {
+        Self {
+            silence_timeout: Duration::from_millis(800),
+            min_speech: Duration::from_millis(300),
+            max_utterance: Duration::from_secs(30),
+            echo_suppression: false,
+        }
+    }
+}
+
+/// One stretch of speech
+#[derive(Debug, Clone)]
+pub struct Utterance {
+    pub recording: Recording,
+    /// Position in the stream, in seconds since it started
+    pub start_secs: f32,
+    pub end_secs: f32,
+    /// When speech was first heard (live input only), not counting the pre-roll
+    pub started_at: Option<Instant>,
+}
+
+// The utterance being collected
+struct Active {
+    samples: Vec<f32>,
+    start: u64,
+    started_at: Option<Instant>,
+    speech_samples: usize,
+    silence_samples: usize,
+}
+
+/// Streaming utterance segmenter
+pub struct Vad {
+    sample_rate: u32,
+    frame_len: usize,
+    silence_timeout: usize,
+    min_speech: usize,
+    max_utterance: usize,
+    pre_roll_len: usize,
+    echo_suppression: bool,
+    frame: Vec<f32>,
+    // Samples consumed so far
+    position: u64,
+    noise_floor: f32,
+    pre_roll: VecDeque<f32>,
+    active: Option<Active>,
+}
+
+impl Vad {
+    pub fn new(config: &VadConfig, sample_rate: u32) -> Self {
+        let samples = |d: Duration| (d.as_secs_f64() * sample_rate as f64) as usize;
+        let frame_len = (sample_rate * FRAME_MS / 1000).max(1) as usize;
+        Self {
+            sample_rate,
+            frame_len,
+            silence_timeout: samples(config.silence_timeout),
+            min_speech: samples(config.min_speech),
+            max_utterance: samples(config.max_utterance).max(frame_len),
+            pre_roll_len: (sample_rate * PRE_ROLL_MS / 1000) as usize,
+            echo_suppression: config.echo_suppression,
+            frame: Vec::new(),
+            position: 0,
+            noise_floor: INITIAL_NOISE_FLOOR,
+            pre_roll: VecDeque::new(),
+            active: None,
+        }
+    }
+
+    /// Feed mono samples, returning any utterances they complete
+    pub fn push(&mut self, samples: &[f32]) -> Vec<Utterance> {
+        self.push_frames(samples, None)
+    }
+
+    /// Feed live samples, the last of which was captured at `captured_at`, so
+    /// frames can be lined up with playback for echo suppression
+    pub fn push_captured(&mut self, samples: &[f32], captured_at: Instant) -> Vec<Utterance> {
+        self.push_frames(samples, Some(captured_at))
+    }
+
+    fn push_frames(&mut self, samples: &[f32], captured_at: Option<Instant>) -> Vec<Utterance> {
+        let mut finished = Vec::new();
+        for (i, &sample) in samples.iter().enumerate() {
+            self.frame.push(sample);
+            if self.frame.len() == self.frame_len {
+                let frame_end = captured_at.map(|at| {
+                    let after = (samples.len() - 1 - i) as f64 / self.sample_rate as f64;
+                    at.checked_sub(Duration::from_secs_f64(after)).unwrap_or(at)
+                });
+                finished.extend(self.process_frame(frame_end));
+            }
+        }
+        finished
+    }
+
+    /// End of the stream: return the utterance in progress, if it has enough speech
+    pub fn finish(&mut self) -> Option<Utterance> {
+        let frame = std::mem::take(&mut self.frame);
+        if let Some(active) = self.active.as_mut() {
+            active.samples.extend_from_slice(&frame);
+        }
+        self.position += frame.len() as u64;
+        self.end_utterance()
+    }
+
+    /// Drop any partial utterance (e.g. while our own speech is playing)
+    /// The noise floor is kept.
+    pub fn reset(&mut self) {
+        self.position += self.frame.len() as u64;
+        self.frame.clear();
+        self.pre_roll.clear();
+        self.active = None;
+    }
+
+    /// Account for samples that were skipped without being fed in, so
+    /// utterance times stay aligned with the stream
+    pub fn skip(&mut self, samples: usize) {
+        self.position += samples as u64;
+    }
+
+    fn process_frame(&mut self, frame_end: Option<Instant>) -> Option<Utterance> {
+        let frame = std::mem::take(&mut self.frame);
+        let rms = (frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32).sqrt();
+        let loud = rms > MIN_SPEECH_RMS && rms > self.noise_floor * SPEECH_RATIO;
+        let frame_start = self.position;
+        self.position += frame.len() as u64;
+
+        let length = Duration::from_secs_f64(frame.len() as f64 / self.sample_rate as f64);
+        let frame_start_at = frame_end.map(|end| end.checked_sub(length).unwrap_or(end));
+        let is_echo = match (frame_start_at, frame_end) {
+            (Some(start), Some(end)) if loud && self.echo_suppression => echo::is_echo(rms, start, end),
+            _ => false,
+        };
+        let is_speech = loud && !is_echo;
+
+        // Track the noise floor from everything that isn't speech (or our own voice)
+        if !loud {
+            let rate = if rms < self.noise_floor { NOISE_FALL } else { NOISE_RISE };
+            self.noise_floor += (rms - self.noise_floor) * rate;
+        }
+
+        let Some(active) = self.active.as_mut() else {
+            if is_speech {
+                let mut samples: Vec<f32> = self.pre_roll.drain(..).collect();
+                let start = frame_start - samples.len() as u64;
+                samples.extend_from_slice(&frame);
+                self.active = Some(Active {
+                    samples,
+                    start,
+                    started_at: frame_start_at,
+                    speech_samples: frame.len(),
+                    silence_samples: 0,
+                });
+            } else {
+                self.pre_roll.extend(frame.iter());
+                let excess = self.pre_roll.len().saturating_sub(self.pre_roll_len);
+                self.pre_roll.drain(..excess);
+            }
+            return None;
+        };
+
+        active.samples.extend_from_slice(&frame);
+        if is_speech {
+            active.speech_samples += frame.len();
+            active.silence_samples = 0;
+        } else {
+            active.silence_samples += frame.len();
+        }
+
+        let done = active.silence_samples >= self.silence_timeout || active.samples.len() >= self.max_utterance;
+        if done {
+            self.end_utterance()
+        } else {
+            None
+        }
+    }
+
+    fn end_utterance(&mut self) -> Option<Utterance> {
+        let active = self.active.take()?;
+        if active.speech_samples < self.min_speech {
+            return None;
+        }
+
+        let end = active.start + active.samples.len() as u64;
+        Some(Utterance {
+            start_secs: active.start as f32 / self.sample_rate as f32,
+            end_secs: end as f32 / self.sample_rate as f32,
+            started_at: active.started_at,
+            recording: Recording {
+                samples: active.samples,
+                sample_rate: self.sample_rate,
+            },
+        })
+    }
+}
+
+/// Split a whole recording into utterances
+pub fn segment(recording: &Recording, config: &VadConfig) -> Vec<Utterance> {
+    let mut vad = Vad::new(config, recording.sample_rate);
+    let mut utterances = vad.push(&recording.samples);
+    utterances.extend(vad.finish());
+    utterances
+}
+
+/// Hands-free listening: the microphone runs continuously and every finished
+/// utterance is handed to `on_utterance`
+///
+/// Input is ignored whenever `is_muted` returns true (while our own speech is
+/// playing without an echo reference), and a partial utterance is dropped
+/// rather than completed from what the microphone picked up of the playback.
+pub struct ContinuousListener {
+    capture: MicCapture,
+    worker: JoinHandle<()>,
+}
+
+impl ContinuousListener {
+    pub fn start(
+        device_name: &str,
+        config: VadConfig,
+        is_muted: impl Fn() -> bool + Send + 'static,
+        on_level: impl Fn(MicLevel) + Send + 'static,
+        mut on_utterance: impl FnMut(Utterance) + Send + 'static,
+    ) -> Result<Self, String> {
+        let (chunk_tx, chunk_rx) = std_mpsc::channel::<(Instant, Vec<f32>)>();
+        let capture = MicCapture::stream(device_name, on_level, move |chunk| {
+            let _ = chunk_tx.send((Instant::now(), chunk.to_vec()));
+        })?;
+        let sample_rate = capture.sample_rate();
+
+        let worker = thread::spawn(move || {
+            let mut vad = Vad::new(&config, sample_rate);
+            let mut muted = false;
+
+            // Ends when the capture stops and drops the sender
+            for (captured_at, chunk) in chunk_rx {
+                if is_muted() {
+                    if !muted {
+                        eprintln!("[VAD] Paused while speech is playing");
+                        muted = true;
+                    }
+                    vad.reset();
+                    vad.skip(chunk.len());
+                    continue;
+                }
+                if muted {
+                    eprintln!("[VAD] Resumed");
+                    muted = false;
+                }
+
+                for utterance in vad.push_captured(&chunk, captured_at) {
+                    eprintln!("[VAD] Utterance {:.1}s - {:.1}s", utterance.start_secs, utterance.end_secs);
+                    on_utterance(utterance);
+                }
+            }
+
+            if let Some(utterance) = vad.finish() {
+                on_utterance(utterance);
+            }
+        });
+
+        Ok(Self { capture, worker })
+    }
+
+    /// Stop listening; an utterance in progress is still delivered
+    pub fn stop(self) {
+        let _ = self.capture.stop();
+        let _ = self.worker.join();
+    }
+}
@@ -0,0 +1,146 @@
+//! Voice profiles per OpenCode agent and message role
+//!
+//! A profile overrides any of engine, voice and speed. The agent's profile is
+//! applied over the main voice settings and the role's profile over that, so
+//! "tool summaries are faster" holds for every agent while each agent keeps
+//! its own voice. Empty fields inherit.
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+// Profiles from the frontend settings
+static PROFILES: Lazy<Mutex<VoiceProfiles>> = Lazy::new(|| Mutex::new(VoiceProfiles::default()));
+
+/// What kind of message is being spoken
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// The assistant's own reply
+    #[default]
+    Assistant,
+    /// Narration of a tool call or its result
+    Tool,
+    /// Notices from the app itself ("Starting a new conversation")
+    System,
+    Error,
+}
+
+/// Overrides for one agent or role (empty fields inherit)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VoiceProfile {
+    pub engine: String,
+    pub voice: String,
+    pub speed: Option<f32>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct VoiceProfiles {
+    pub agents: HashMap<String, VoiceProfile>,
+    pub roles: HashMap<Role, VoiceProfile>,
+}
+
+/// A fully resolved voice
+#[derive(Debug, Clone, PartialEq)]
+pub struct Voice {
+    pub engine: String,
+    pub voice: String,
+    pub speed: f32,
+}
+
+impl Voice {
+    fn apply(&mut self, profile: &VoiceProfile) {
+        // A voice only makes sense for the engine it belongs to, so switching
+        // engine without naming a voice falls back to that engine's default
+        if !profile.engine.is_empty() && profile.engine != self.engine {
+            self.engine = profile.engine.clone();
+            self.voice = default_voice(&self.engine).to_string();
+        }
+        if !profile.voice.is_empty() {
+            self.voice = profile.voice.clone();
+        }
+        if let Some(speed) = profile.speed.filter(|s| s.is_finite() && *s > 0.0) {
+            self.speed = speed;
+        }
+    }
+}
+
+/// The voice an engine speaks with when none is named
+pub fn default_voice(engine: &str) -> &'static str {
+    match engine {
+        "edge" => "en-US-AriaNeural",
+        // Piper uses its default model for voices it doesn't have
+        _ => crate::tray::engine_voices(engine).first().copied().unwrap_or_default(),
+    }
+}
+
+/// Replace the agent and role profiles
+pub fn set_profiles(profiles: VoiceProfiles) {
+    *PROFILES.lock().unwrap() = profiles;
+}
+
+/// Apply the agent's and then the role's profile to the main voice
+pub fn resolve(base: Voice, agent: &str, role: Role) -> Voice {
+    let profiles = PROFILES.lock().unwrap();
+    let mut voice = base;
+
+    if let Some(profile) = profiles.agents.get(agent) {
+        voice.apply(profile);
+    }
+    if let Some(profile) = profiles.roles.get(&role) {
+        voice.apply(profile);
+    }
+
+    voice
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(engine: &str, voice: &str, speed: Option<f32>) -> VoiceProfile {
+        VoiceProfile {
+            engine: engine.to_string(),
+            voice: voice.to_string(),
+            speed,
+        }
+    }
+
+    #[test]
+    fn profiles_apply_agent_then_role() {
+        set_profiles(VoiceProfiles {
+            agents: HashMap::from([
+                ("build".to_string(), profile("kokoro", "", None)),
+                ("plan".to_string(), profile("openai", "nova", Some(0.9))),
+                ("docs".to_string(), profile("", "Alex", None)),
+            ]),
+            roles: HashMap::from([(Role::Tool, profile("", "", Some(1.4)))]),
+        });
+        let base = Voice {
+            engine: "macos".to_string(),
+            voice: "Samantha".to_string(),
+            speed: 1.0,
+        };
+        let resolved = |agent: &str, role: Role| {
+            let voice = resolve(base.clone(), agent, role);
+            (voice.engine, voice.voice, voice.speed)
+        };
+
+        // Switching engine without a voice uses that engine's default
+        assert_eq!(resolved("build", Role::Assistant), ("kokoro".into(), "af_heart".into(), 1.0));
+        assert_eq!(resolved("plan", Role::Assistant), ("openai".into(), "nova".into(), 0.9));
+        assert_eq!(resolved("plan", Role::Tool), ("openai".into(), "nova".into(), 1.4));
+        // A voice on its own keeps the engine
+        assert_eq!(resolved("docs", Role::Assistant), ("macos".into(), "Alex".into(), 1.0));
+        assert_eq!(resolved("other", Role::Assistant), ("macos".into(), "Samantha".into(), 1.0));
+    }
+
+    #[test]
+    fn every_engine_has_a_default_voice() {
+        for engine in ["macos", "kokoro", "piper", "espeak", "openai", "edge"] {
+            assert!(!default_voice(engine).is_empty(), "{}", engine);
+        }
+    }
+}
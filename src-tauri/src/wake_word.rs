@@ -0,0 +1,279 @@
+//! Wake word ("hey opencode") spotting for hands-free activation
+//!
+//! The microphone stream is cut into utterances by the VAD and each short one
+//! is transcribed with the local whisper model, so nothing leaves the machine
+//! until the wake word has been heard. The transcript is matched against the
+//! phrase letter by letter, ignoring spacing and punctuation ("Hey, Open
+//! Code!" matches), with the sensitivity setting deciding how many letters may
+//! differ. Whatever follows the phrase in the same breath is the command;
+//! otherwise the next utterance is.
+//!
+//! `cargo run --bin wake_word_test` runs recorded WAV files through the
+//! detector.
+
+use crate::mic::{MicLevel, Recording};
+use crate::stt;
+use crate::vad::{self, ContinuousListener, Utterance, VadConfig};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self as std_mpsc, RecvTimeoutError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+// Longer utterances are conversation, not someone addressing us, and aren't
+// worth transcribing just to look for the phrase
+const MAX_WAKE_UTTERANCE_SECS: f32 = 6.0;
+
+// The phrase may be preceded by a couple of words ("okay, hey opencode")
+const MAX_LEADING_WORDS: usize = 2;
+
+// How long to wait for the command after a bare wake word
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(8);
+
+#[derive(Debug, Clone)]
+pub struct WakeWordConfig {
+    pub phrase: String,
+    /// 0.0 (exact match only) to 1.0 (loose: more activations, more false alarms)
+    pub sensitivity: f32,
+}
+
+impl Default for WakeWordConfig {
+    fn default() -> Self {
+        Self {
+            phrase: "hey opencode".to_string(),
+            sensitivity: 0.5,
+        }
+    }
+}
+
+impl WakeWordConfig {
+    // Minimum similarity for a match
+    fn threshold(&self) -> f32 {
+        0.9 - 0.3 * self.sensitivity.clamp(0.0, 1.0)
+    }
+}
+
+/// Where the phrase was found in a transcript
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhraseMatch {
+    /// Similarity to the phrase (1.0 = exact)
+    pub score: f32,
+    /// Text after the phrase
+    pub command: String,
+}
+
+/// A wake word heard in an utterance
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Detection {
+    pub score: f32,
+    pub transcript: String,
+    pub command: String,
+    pub start_secs: f32,
+    pub end_secs: f32,
+}
+
+/// Look for the wake phrase near the start of a transcript
+pub fn match_phrase(transcript: &str, config: &WakeWordConfig) -> Option<PhraseMatch> {
+    let phrase: String = words(&config.phrase).map(|(_, word)| word).collect();
+    let phrase_words = words(&config.phrase).count();
+    if phrase.is_empty() {
+        return None;
+    }
+
+    let spans: Vec<(usize, String)> = words(transcript).collect();
+    let mut best: Option<(f32, usize)> = None;
+    for start in 0..spans.len().min(MAX_LEADING_WORDS + 1) {
+        // Recognizers split and join words freely ("open code", "opencode")
+        for len in 1..=phrase_words + 2 {
+            let Some(candidate) = spans.get(start..start + len) else {
+                break;
+            };
+            let candidate: String = candidate.iter().map(|(_, word)| word.as_str()).collect();
+            let score = similarity(&candidate, &phrase);
+            if best.is_none_or(|(best_score, _)| score > best_score) {
+                best = Some((score, start + len - 1));
+            }
+        }
+    }
+
+    let (score, last_word) = best.filter(|(score, _)| *score >= config.threshold())?;
+    let command_start = spans[last_word].0;
+    let command = transcript[command_start..]
+        .trim_start_matches(|c: char| !c.is_alphanumeric())
+        .trim()
+        .to_string();
+    Some(PhraseMatch { score, command })
+}
+
+// Lowercased alphanumeric words with the byte offset where each one ends
+fn words(text: &str) -> impl Iterator<Item = (usize, String)> + '_ {
+    static WORD_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"[\p{Alphabetic}\p{Nd}]+").unwrap());
+    WORD_RE
+        .find_iter(text)
+        .map(|word| (word.end(), word.as_str().to_lowercase()))
+}
+
+// 1.0 minus the edit distance relative to the longer string
+fn similarity(a: &str, b: &str) -> f32 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    1.0 - previous[b.len()] as f32 / longest as f32
+}
+
+/// Transcribes utterances locally and checks them for the wake word
+pub struct Spotter {
+    config: WakeWordConfig,
+    model: PathBuf,
+    language: String,
+}
+
+impl Spotter {
+    /// `model` is a GGML whisper model file
+    pub fn new(config: WakeWordConfig, model: PathBuf, language: &str) -> Self {
+        Self {
+            config,
+            model,
+            language: language.to_string(),
+        }
+    }
+
+    pub fn config(&self) -> &WakeWordConfig {
+        &self.config
+    }
+
+    /// Run every utterance in a WAV file through the detector
+    pub fn scan_wav(&self, path: &Path) -> Result<Vec<Detection>, String> {
+        let recording = stt::read_wav(path)?;
+        let mut detections = Vec::new();
+        for utterance in vad::segment(&recording, &VadConfig::default()) {
+            detections.extend(self.check(&utterance)?);
+        }
+        Ok(detections)
+    }
+
+    pub(crate) fn check(&self, utterance: &Utterance) -> Result<Option<Detection>, String> {
+        if utterance.recording.duration_secs() > MAX_WAKE_UTTERANCE_SECS {
+            return Ok(None);
+        }
+
+        let transcript = self.transcribe(&utterance.recording)?;
+        Ok(match_phrase(&transcript, &self.config).map(|found| Detection {
+            score: found.score,
+            transcript,
+            command: found.command,
+            start_secs: utterance.start_secs,
+            end_secs: utterance.end_secs,
+        }))
+    }
+
+    fn transcribe(&self, recording: &Recording) -> Result<String, String> {
+        stt::transcribe_local(recording, &self.model, &self.language)
+    }
+}
+
+/// What the wake word listener heard
+pub enum WakeEvent {
+    /// The wake word; a command utterance follows unless it times out
+    Woke(Detection),
+    /// Speech addressed to us, to be transcribed (may start with the phrase)
+    Command(Utterance),
+    /// Woken, but nothing was said
+    TimedOut,
+}
+
+/// Listens in the background until the wake word is heard, then hands over
+/// the command that follows it
+pub struct WakeWordListener {
+    listener: ContinuousListener,
+    worker: JoinHandle<()>,
+}
+
+impl WakeWordListener {
+    pub fn start(
+        device_name: &str,
+        spotter: Spotter,
+        vad_config: VadConfig,
+        is_muted: impl Fn() -> bool + Send + 'static,
+        on_level: impl Fn(MicLevel) + Send + 'static,
+        mut on_event: impl FnMut(WakeEvent) + Send + 'static,
+    ) -> Result<Self, String> {
+        let (utterance_tx, utterance_rx) = std_mpsc::channel::<Utterance>();
+        let listener = ContinuousListener::start(device_name, vad_config, is_muted, on_level, move |utterance| {
+            let _ = utterance_tx.send(utterance);
+        })?;
+        eprintln!("[WAKE] Listening for {:?}", spotter.config().phrase);
+
+        let worker = thread::spawn(move || {
+            // Set after a bare wake word while waiting for the command
+            let mut awake_until: Option<Instant> = None;
+
+            loop {
+                let next = match awake_until {
+                    Some(deadline) => utterance_rx.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+                    None => utterance_rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                };
+
+                let utterance = match next {
+                    Ok(utterance) => utterance,
+                    Err(RecvTimeoutError::Timeout) => {
+                        eprintln!("[WAKE] No command after the wake word");
+                        awake_until = None;
+                        on_event(WakeEvent::TimedOut);
+                        continue;
+                    }
+                    Err(RecvTimeoutError::Disconnected) => break,
+                };
+
+                if awake_until.take().is_some() {
+                    on_event(WakeEvent::Command(utterance));
+                    continue;
+                }
+
+                match spotter.check(&utterance) {
+                    Ok(Some(detection)) => {
+                        eprintln!(
+                            "[WAKE] Heard {:?} (score {:.2})",
+                            detection.transcript, detection.score
+                        );
+                        let has_command = !detection.command.is_empty();
+                        on_event(WakeEvent::Woke(detection));
+                        if has_command {
+                            on_event(WakeEvent::Command(utterance));
+                        } else {
+                            awake_until = Some(Instant::now() + COMMAND_TIMEOUT);
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(e) => eprintln!("[WAKE] Detection failed: {}", e),
+                }
+            }
+        });
+
+        Ok(Self { listener, worker })
+    }
+
+    pub fn stop(self) {
+        // Stopping the VAD closes the utterance channel, which ends the worker
+        self.listener.stop();
+        let _ = self.worker.join();
+    }
+}
@@ -17,19 +17,8 @@ export function ConfirmDialog({ confirmation }: ConfirmDialogProps) {
     await handleConfirmation(false);
   }, []);
   
-  // Determine severity for styling
-  const getSeverity = (): 'low' | 'medium' | 'high' => {
-    const desc = confirmation.description.toLowerCase();
-    if (desc.includes('delete') || desc.includes('remove') || desc.includes('force')) {
-      return 'high';
-    }
-    if (desc.includes('write') || desc.includes('modify') || desc.includes('change')) {
-      return 'medium';
-    }
-    return 'low';
-  };
-  
-  const severity = getSeverity();
+  // Assessed by the backend from the tool and its arguments
+  const severity = confirmation.severity;
   
   const severityStyles = {
     low: {
@@ -88,11 +77,17 @@ export function ConfirmDialog({ confirmation }: ConfirmDialogProps) {
           <p className="text-sm font-mono text-gray-800 dark:text-gray-200 break-all">
             {confirmation.description}
           </p>
+          {confirmation.details && (
+            <p className="mt-2 text-xs font-mono text-gray-600 dark:text-gray-400 whitespace-pre-wrap break-all max-h-24 overflow-y-auto">
+              {confirmation.details}
+            </p>
+          )}
         </div>
         
         {/* Voice instruction */}
         <p className="text-xs text-gray-500 dark:text-gray-400 mb-3">
-          Say <strong>"yes"</strong> or <strong>"no"</strong>, or click a button below:
+          Say <strong>"yes"</strong>, <strong>"no"</strong> or <strong>"details"</strong>, or click a button below
+          (denied after {confirmation.timeoutSecs}s without an answer):
         </p>
         
         {/* Buttons */}
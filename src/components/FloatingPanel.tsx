@@ -6,8 +6,10 @@ import { useVoiceInput } from '../hooks/useVoiceInput';
 import { useSettingsStore } from '../stores/settings';
 import { useConversationStore } from '../stores/conversation';
 import { cn, formatRelativeTime, formatHotkey } from '../lib/utils';
+import { isBackendStt } from '../lib/stt';
+import { replayMessage } from '../lib/voice-bridge';
 import { ConfirmDialog } from './ConfirmDialog';
-import { StatusIndicator } from './StatusIndicator';
+import { StatusIndicator, MicLevelMeter } from './StatusIndicator';
 
 export function FloatingPanel() {
   const settings = useSettingsStore();
@@ -100,6 +102,9 @@ export function FloatingPanel() {
             <StatusIndicator state={voiceState} />
           )}
           
+          {/* Input level while the backend is recording */}
+          {voiceState === 'listening' && isBackendStt(settings.sttEngine) && <MicLevelMeter />}
+          
           {/* Connection status */}
           <div className="flex items-center gap-1.5">
             {isConnecting ? (
@@ -215,9 +220,20 @@ export function FloatingPanel() {
                   </p>
                 )}
               </div>
-              <span className="text-xs text-gray-400 dark:text-gray-500 px-2">
-                {formatRelativeTime(message.timestamp)}
-              </span>
+              <div className="flex items-center gap-1 px-2">
+                <span className="text-xs text-gray-400 dark:text-gray-500">
+                  {formatRelativeTime(message.timestamp)}
+                </span>
+                {message.role === 'assistant' && message.historyId !== undefined && (
+                  <button
+                    onClick={() => replayMessage(message.historyId!)}
+                    className="p-0.5 rounded text-gray-400 hover:text-gray-600 dark:text-gray-500 dark:hover:text-gray-300"
+                    title="Replay"
+                  >
+                    <Volume2 className="w-3 h-3" />
+                  </button>
+                )}
+              </div>
             </div>
           ))
         )}
@@ -11,8 +11,9 @@ import {
   Check,
   AlertCircle,
 } from 'lucide-react';
-import { useSettingsStore, type Settings as SettingsType, type SettingsStore } from '../stores/settings';
+import { useSettingsStore, type Settings as SettingsType, type SettingsStore, type LanguageVoice, type SpeechRole, type VoiceProfile } from '../stores/settings';
 import { useVoiceOutput } from '../hooks/useVoiceOutput';
+import { isBackendStt } from '../lib/stt';
 import { cn } from '../lib/utils';
 
 type SettingsTab = 'voice-input' | 'voice-output' | 'opencode' | 'behavior' | 'ui';
@@ -180,57 +181,177 @@ function VoiceInputSettings({ settings }: { settings: SettingsStore }) {
             </label>
             <select
               value={settings.sttEngine}
-              onChange={(e) => settings.setSettings({ sttEngine: e.target.value as 'superwhisper' | 'macos' })}
+              onChange={(e) => settings.setSettings({ sttEngine: e.target.value as SettingsType['sttEngine'] })}
               className="w-full px-3 py-2 bg-white dark:bg-gray-800 border border-gray-300 dark:border-gray-600 rounded-lg text-sm focus:ring-2 focus:ring-blue-500 focus:border-transparent"
             >
               <option value="superwhisper">SuperWhisper (recommended)</option>
+              <option value="whisper">Local Whisper (built in)</option>
+              <option value="openai">OpenAI-compatible server</option>
+              <option value="command">Custom command</option>
               <option value="macos">macOS Dictation (fallback)</option>
             </select>
             <p className="mt-1 text-xs text-gray-500 dark:text-gray-400">
-              SuperWhisper provides better accuracy and runs locally.
+              {settings.sttEngine === 'whisper'
+                ? 'Runs whisper.cpp on your CPU. Works on every platform.'
+                : settings.sttEngine === 'openai'
+                ? 'Sends each recording to a /v1/audio/transcriptions endpoint.'
+                : settings.sttEngine === 'command'
+                ? 'Runs your program on each recording (a WAV file) and reads the transcript from its output.'
+                : 'SuperWhisper provides better accuracy and runs locally.'}
             </p>
           </div>
           
+          {isBackendStt(settings.sttEngine) && <InputDeviceSettings settings={settings} />}
+          {settings.sttEngine === 'whisper' && <WhisperSettings settings={settings} />}
+          {settings.sttEngine === 'openai' && <OpenAiTranscriptionSettings settings={settings} />}
+          {settings.sttEngine === 'command' && (
+            <div>
+              <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-2">
+                STT Command
+              </label>
+              <input
+                type="text"
+                value={settings.sttCommandTemplate}
+                onChange={(e) => settings.setSettings({ sttCommandTemplate: e.target.value })}
+                className="w-full px-3 py-2 bg-white dark:bg-gray-800 border border-gray-300 dark:border-gray-600 rounded-lg text-sm font-mono focus:ring-2 focus:ring-blue-500 focus:border-transparent"
+                placeholder="whisper-cli -nt -l {language} -f {input}"
+              />
+              <p className="mt-1 text-xs text-gray-500 dark:text-gray-400">
+                {'{input}'} is a 16kHz mono WAV file and {'{language}'} the spoken language. The transcript is read from stdout.
+              </p>
+            </div>
+          )}
+          
           <div>
-            <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-2">
-              Push-to-Talk Hotkey
-            </label>
-            <input
-              type="text"
+            <HotkeyField
+              label="Push-to-Talk Hotkey"
+              action="pushToTalk"
               value={settings.pushToTalkHotkey}
-              onChange={(e) => settings.setSettings({ pushToTalkHotkey: e.target.value })}
-              className="w-full px-3 py-2 bg-white dark:bg-gray-800 border border-gray-300 dark:border-gray-600 rounded-lg text-sm font-mono focus:ring-2 focus:ring-blue-500 focus:border-transparent"
               placeholder="Option+Space"
+              onChange={(hotkey) => settings.setSettings({ pushToTalkHotkey: hotkey })}
             />
           </div>
           
           <div>
-            <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-2">
-              Continuous Mode Hotkey
-            </label>
-            <input
-              type="text"
+            <HotkeyField
+              label="Continuous Mode Hotkey"
+              action="continuousMode"
               value={settings.continuousModeHotkey}
-              onChange={(e) => settings.setSettings({ continuousModeHotkey: e.target.value })}
-              className="w-full px-3 py-2 bg-white dark:bg-gray-800 border border-gray-300 dark:border-gray-600 rounded-lg text-sm font-mono focus:ring-2 focus:ring-blue-500 focus:border-transparent"
               placeholder="Option+Shift+Space"
+              onChange={(hotkey) => settings.setSettings({ continuousModeHotkey: hotkey })}
             />
+            <p className="mt-1 text-xs text-gray-500 dark:text-gray-400">
+              Toggles hands-free listening: each pause ends an utterance. Needs a Whisper, OpenAI or command STT engine.
+            </p>
           </div>
           
+          {isBackendStt(settings.sttEngine) && (
+            <div className="grid grid-cols-2 gap-4">
+              <div>
+                <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-2">
+                  Pause to End: {settings.vadSilenceMs}ms
+                </label>
+                <input
+                  type="range"
+                  min="300"
+                  max="3000"
+                  step="100"
+                  value={settings.vadSilenceMs}
+                  onChange={(e) => settings.setSettings({ vadSilenceMs: parseInt(e.target.value) })}
+                  className="w-full"
+                />
+              </div>
+              <div>
+                <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-2">
+                  Minimum Speech: {settings.vadMinSpeechMs}ms
+                </label>
+                <input
+                  type="range"
+                  min="100"
+                  max="1500"
+                  step="50"
+                  value={settings.vadMinSpeechMs}
+                  onChange={(e) => settings.setSettings({ vadMinSpeechMs: parseInt(e.target.value) })}
+                  className="w-full"
+                />
+              </div>
+              <label className="col-span-2 flex items-center gap-3 cursor-pointer">
+                <input
+                  type="checkbox"
+                  checked={settings.echoSuppression}
+                  onChange={(e) => settings.setSettings({ echoSuppression: e.target.checked })}
+                  className="w-4 h-4 rounded border-gray-300 text-blue-500 focus:ring-blue-500"
+                />
+                <span className="text-sm text-gray-700 dark:text-gray-300">
+                  Echo suppression (talk over the assistant when using speakers)
+                </span>
+              </label>
+            </div>
+          )}
+          
+          <WakeWordSettings settings={settings} />
+          
           <div>
-            <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-2">
-              Interrupt Hotkey
-            </label>
-            <input
-              type="text"
+            <HotkeyField
+              label="Interrupt Hotkey"
+              action="interrupt"
               value={settings.interruptHotkey}
-              onChange={(e) => settings.setSettings({ interruptHotkey: e.target.value })}
-              className="w-full px-3 py-2 bg-white dark:bg-gray-800 border border-gray-300 dark:border-gray-600 rounded-lg text-sm font-mono focus:ring-2 focus:ring-blue-500 focus:border-transparent"
               placeholder="Escape"
+              onChange={(hotkey) => settings.setSettings({ interruptHotkey: hotkey })}
             />
           </div>
         </div>
       </div>
+      
+      <VoiceCommandSettings />
+    </div>
+  );
+}
+
+interface VoiceCommandGrammar {
+  intents: { intent: string; phrases: string[] }[];
+  path: string | null;
+  file: string | null;
+  errors: string[];
+}
+
+// Voice commands handled by the app instead of being sent to OpenCode
+function VoiceCommandSettings() {
+  const [grammar, setGrammar] = useState<VoiceCommandGrammar | null>(null);
+  
+  useEffect(() => {
+    invoke<VoiceCommandGrammar>('get_voice_commands').then(setGrammar).catch(() => {});
+  }, []);
+  
+  if (!grammar) return null;
+  
+  return (
+    <div className="mt-6">
+      <h2 className="text-lg font-medium text-gray-900 dark:text-gray-100 mb-4">
+        Voice Commands
+      </h2>
+      
+      <div className="space-y-3">
+        <p className="text-xs text-gray-500">
+          These are carried out right away instead of being sent to OpenCode. Replace the
+          phrases of an intent in an [intents] table in {grammar.path || 'commands.toml in the config directory'};
+          {' {engine}'} and {'{voice}'} stand for an engine or voice name.
+        </p>
+        
+        <div className="text-xs space-y-1">
+          {grammar.intents.filter((intent) => intent.phrases.length > 0).map((intent) => (
+            <p key={intent.intent} className="text-gray-700 dark:text-gray-300">
+              <span className="font-mono">{intent.intent}</span>: {intent.phrases.join(', ')}
+            </p>
+          ))}
+          {grammar.errors.map((error) => (
+            <p key={error} className="flex items-start gap-1 text-red-500">
+              <AlertCircle className="w-3 h-3 mt-0.5 shrink-0" />
+              {error}
+            </p>
+          ))}
+        </div>
+      </div>
     </div>
   );
 }
@@ -270,7 +391,9 @@ function VoiceOutputSettings({
               <option value="kokoro">Kokoro (highest quality, local)</option>
               <option value="piper">Piper (fast, local)</option>
               <option value="macos">macOS (fastest, basic)</option>
-              <option value="openai">OpenAI TTS (cloud)</option>
+              <option value="espeak">espeak-ng (zero setup, robotic)</option>
+              <option value="command">Custom command</option>
+              <option value="openai">OpenAI-compatible server</option>
             </select>
           </div>
           
@@ -309,18 +432,107 @@ function VoiceOutputSettings({
             </div>
           </div>
           
+          <div>
+            <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-2">
+              Volume: {Math.round(settings.ttsVolume * 100)}%
+            </label>
+            <input
+              type="range"
+              min="0.25"
+              max="2.0"
+              step="0.25"
+              value={settings.ttsVolume}
+              onChange={(e) => settings.setSettings({ ttsVolume: parseFloat(e.target.value) })}
+              className="w-full"
+            />
+          </div>
+          
+          <label className="flex items-center gap-3 cursor-pointer">
+            <input
+              type="checkbox"
+              checked={settings.voiceMuted}
+              onChange={(e) => settings.setSettings({ voiceMuted: e.target.checked })}
+              className="w-4 h-4 rounded border-gray-300 text-blue-500 focus:ring-blue-500"
+            />
+            <span className="text-sm text-gray-700 dark:text-gray-300">
+              Mute voice (answers are only shown)
+            </span>
+          </label>
+          
+          {settings.ttsEngine === 'command' && (
+            <div className="space-y-3">
+              <div>
+                <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-2">
+                  Command Template
+                </label>
+                <input
+                  type="text"
+                  value={settings.ttsCommandTemplate}
+                  onChange={(e) => settings.setSettings({ ttsCommandTemplate: e.target.value })}
+                  className="w-full px-3 py-2 bg-white dark:bg-gray-800 border border-gray-300 dark:border-gray-600 rounded-lg text-sm font-mono focus:ring-2 focus:ring-blue-500 focus:border-transparent"
+                  placeholder="mytts --voice {voice} --rate {speed} --out {output} {text}"
+                />
+                <p className="text-xs text-gray-500 mt-1">
+                  Placeholders: {'{text}'}, {'{voice}'}, {'{speed}'}, {'{output}'}. Without {'{text}'} the text is sent on stdin; without {'{output}'} audio is read from stdout.
+                </p>
+              </div>
+              <div>
+                <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-2">
+                  Output Format
+                </label>
+                <select
+                  value={settings.ttsCommandFormat}
+                  onChange={(e) => settings.setSettings({ ttsCommandFormat: e.target.value as SettingsType['ttsCommandFormat'] })}
+                  className="w-full px-3 py-2 bg-white dark:bg-gray-800 border border-gray-300 dark:border-gray-600 rounded-lg text-sm focus:ring-2 focus:ring-blue-500 focus:border-transparent"
+                >
+                  <option value="wav">WAV</option>
+                  <option value="mp3">MP3</option>
+                </select>
+              </div>
+            </div>
+          )}
+          
           {settings.ttsEngine === 'openai' && (
-            <div>
-              <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-2">
-                OpenAI API Key
-              </label>
-              <input
-                type="password"
-                value={settings.openaiApiKey || ''}
-                onChange={(e) => settings.setSettings({ openaiApiKey: e.target.value })}
-                className="w-full px-3 py-2 bg-white dark:bg-gray-800 border border-gray-300 dark:border-gray-600 rounded-lg text-sm focus:ring-2 focus:ring-blue-500 focus:border-transparent"
-                placeholder="sk-..."
-              />
+            <div className="space-y-3">
+              <div>
+                <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-2">
+                  Server URL
+                </label>
+                <input
+                  type="text"
+                  value={settings.openaiBaseUrl}
+                  onChange={(e) => settings.setSettings({ openaiBaseUrl: e.target.value })}
+                  className="w-full px-3 py-2 bg-white dark:bg-gray-800 border border-gray-300 dark:border-gray-600 rounded-lg text-sm focus:ring-2 focus:ring-blue-500 focus:border-transparent"
+                  placeholder="https://api.openai.com"
+                />
+                <p className="text-xs text-gray-500 mt-1">
+                  OpenAI, or a local server with the same API (Kokoro-FastAPI, openedai-speech, LocalAI)
+                </p>
+              </div>
+              <div>
+                <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-2">
+                  Model
+                </label>
+                <input
+                  type="text"
+                  value={settings.openaiModel}
+                  onChange={(e) => settings.setSettings({ openaiModel: e.target.value })}
+                  className="w-full px-3 py-2 bg-white dark:bg-gray-800 border border-gray-300 dark:border-gray-600 rounded-lg text-sm focus:ring-2 focus:ring-blue-500 focus:border-transparent"
+                  placeholder="tts-1"
+                />
+              </div>
+              <div>
+                <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-2">
+                  API Key
+                </label>
+                <input
+                  type="password"
+                  value={settings.openaiApiKey || ''}
+                  onChange={(e) => settings.setSettings({ openaiApiKey: e.target.value })}
+                  className="w-full px-3 py-2 bg-white dark:bg-gray-800 border border-gray-300 dark:border-gray-600 rounded-lg text-sm focus:ring-2 focus:ring-blue-500 focus:border-transparent"
+                  placeholder="sk-... (leave empty for local servers)"
+                />
+              </div>
             </div>
           )}
           
@@ -343,6 +555,611 @@ function VoiceOutputSettings({
           </button>
         </div>
       </div>
+      
+      <LanguageSettings settings={settings} />
+      
+      <VoiceProfileSettings settings={settings} />
+      
+      <PronunciationSettings settings={settings} />
+    </div>
+  );
+}
+
+// Hotkey input, applied when the field loses focus so the backend can check
+// the shortcut before it replaces the old one
+function HotkeyField({
+  label,
+  action,
+  value,
+  placeholder,
+  onChange,
+}: {
+  label: string;
+  action: 'pushToTalk' | 'continuousMode' | 'interrupt' | 'approve' | 'deny';
+  value: string;
+  placeholder: string;
+  onChange: (hotkey: string) => void;
+}) {
+  const [draft, setDraft] = useState(value);
+  const [error, setError] = useState<string | null>(null);
+  
+  useEffect(() => {
+    setDraft(value);
+  }, [value]);
+  
+  const apply = async () => {
+    if (draft === value) return;
+    try {
+      const hotkey = await invoke<string>('rebind_hotkey', { action, hotkey: draft });
+      setError(null);
+      onChange(hotkey);
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+  
+  return (
+    <>
+      <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-2">
+        {label}
+      </label>
+      <input
+        type="text"
+        value={draft}
+        onChange={(e) => setDraft(e.target.value)}
+        onBlur={apply}
+        onKeyDown={(e) => {
+          if (e.key === 'Enter') e.currentTarget.blur();
+        }}
+        className={cn(
+          'w-full px-3 py-2 bg-white dark:bg-gray-800 border rounded-lg text-sm font-mono focus:ring-2 focus:ring-blue-500 focus:border-transparent',
+          error ? 'border-red-400 dark:border-red-500' : 'border-gray-300 dark:border-gray-600'
+        )}
+        placeholder={placeholder}
+      />
+      {error && (
+        <p className="mt-1 text-xs text-red-500">{error}</p>
+      )}
+    </>
+  );
+}
+
+// Hands-free activation by saying the wake word
+function WakeWordSettings({ settings }: { settings: SettingsStore }) {
+  return (
+    <div className="space-y-3">
+      <label className="flex items-center gap-3 cursor-pointer">
+        <input
+          type="checkbox"
+          checked={settings.wakeWordEnabled}
+          onChange={(e) => settings.setSettings({ wakeWordEnabled: e.target.checked })}
+          className="w-4 h-4 rounded border-gray-300 text-blue-500 focus:ring-blue-500"
+        />
+        <span className="text-sm text-gray-700 dark:text-gray-300">
+          Listen for a wake word
+        </span>
+      </label>
+      
+      {settings.wakeWordEnabled && (
+        <>
+          <div>
+            <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-2">
+              Wake Word
+            </label>
+            <input
+              type="text"
+              value={settings.wakeWord}
+              onChange={(e) => settings.setSettings({ wakeWord: e.target.value })}
+              className="w-full px-3 py-2 bg-white dark:bg-gray-800 border border-gray-300 dark:border-gray-600 rounded-lg text-sm focus:ring-2 focus:ring-blue-500 focus:border-transparent"
+              placeholder="hey opencode"
+            />
+          </div>
+          
+          <div>
+            <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-2">
+              Sensitivity: {Math.round(settings.wakeWordSensitivity * 100)}%
+            </label>
+            <input
+              type="range"
+              min="0"
+              max="1"
+              step="0.05"
+              value={settings.wakeWordSensitivity}
+              onChange={(e) => settings.setSettings({ wakeWordSensitivity: parseFloat(e.target.value) })}
+              className="w-full"
+            />
+            <div className="flex justify-between text-xs text-gray-400 mt-1">
+              <span>Exact</span>
+              <span>Loose</span>
+            </div>
+          </div>
+          
+          <p className="text-xs text-gray-500 dark:text-gray-400">
+            Detected on this computer with the local Whisper model ({settings.whisperModel}); audio is only sent elsewhere after the wake word. Say the command in the same breath or right after.
+          </p>
+        </>
+      )}
+    </div>
+  );
+}
+
+// Microphone used by the backend recorder
+function InputDeviceSettings({ settings }: { settings: SettingsStore }) {
+  const [devices, setDevices] = useState<string[]>([]);
+  
+  useEffect(() => {
+    invoke<string[]>('list_input_devices').then(setDevices).catch(() => {});
+  }, []);
+  
+  return (
+    <div>
+      <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-2">
+        Microphone
+      </label>
+      <select
+        value={settings.inputDevice}
+        onChange={(e) => settings.setSettings({ inputDevice: e.target.value })}
+        className="w-full px-3 py-2 bg-white dark:bg-gray-800 border border-gray-300 dark:border-gray-600 rounded-lg text-sm focus:ring-2 focus:ring-blue-500 focus:border-transparent"
+      >
+        <option value="">System default</option>
+        {/* Keep a saved device selectable while it's unplugged */}
+        {settings.inputDevice && !devices.includes(settings.inputDevice) && (
+          <option value={settings.inputDevice}>{settings.inputDevice} (not connected)</option>
+        )}
+        {devices.map((device) => (
+          <option key={device} value={device}>{device}</option>
+        ))}
+      </select>
+    </div>
+  );
+}
+
+// Server for the OpenAI-compatible STT engine
+function OpenAiTranscriptionSettings({ settings }: { settings: SettingsStore }) {
+  return (
+    <>
+      <div>
+        <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-2">
+          Transcription Server
+        </label>
+        <input
+          type="text"
+          value={settings.sttOpenaiBaseUrl}
+          onChange={(e) => settings.setSettings({ sttOpenaiBaseUrl: e.target.value })}
+          className="w-full px-3 py-2 bg-white dark:bg-gray-800 border border-gray-300 dark:border-gray-600 rounded-lg text-sm font-mono focus:ring-2 focus:ring-blue-500 focus:border-transparent"
+          placeholder="https://api.openai.com"
+        />
+      </div>
+      
+      <div>
+        <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-2">
+          Transcription Model
+        </label>
+        <input
+          type="text"
+          value={settings.sttOpenaiModel}
+          onChange={(e) => settings.setSettings({ sttOpenaiModel: e.target.value })}
+          className="w-full px-3 py-2 bg-white dark:bg-gray-800 border border-gray-300 dark:border-gray-600 rounded-lg text-sm font-mono focus:ring-2 focus:ring-blue-500 focus:border-transparent"
+          placeholder="whisper-1"
+        />
+        <p className="mt-1 text-xs text-gray-500 dark:text-gray-400">
+          Local servers like faster-whisper-server work too.
+        </p>
+      </div>
+      
+      <div>
+        <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-2">
+          API Key
+        </label>
+        <input
+          type="password"
+          value={settings.openaiApiKey || ''}
+          onChange={(e) => settings.setSettings({ openaiApiKey: e.target.value })}
+          className="w-full px-3 py-2 bg-white dark:bg-gray-800 border border-gray-300 dark:border-gray-600 rounded-lg text-sm focus:ring-2 focus:ring-blue-500 focus:border-transparent"
+          placeholder="sk-... (leave empty for local servers)"
+        />
+        <p className="mt-1 text-xs text-gray-500 dark:text-gray-400">
+          Shared with the OpenAI-compatible voice output engine.
+        </p>
+      </div>
+    </>
+  );
+}
+
+// Local whisper model and language
+function WhisperSettings({ settings }: { settings: SettingsStore }) {
+  const [modelStatus, setModelStatus] = useState<{ ok: boolean; message: string } | null>(null);
+  
+  useEffect(() => {
+    // update_settings is synced asynchronously, so check after it has landed
+    const timer = setTimeout(() => {
+      invoke<string>('check_whisper_model')
+        .then((path) => setModelStatus({ ok: true, message: path }))
+        .catch((e) => setModelStatus({ ok: false, message: String(e) }));
+    }, 500);
+    return () => clearTimeout(timer);
+  }, [settings.whisperModel]);
+  
+  return (
+    <>
+      <div>
+        <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-2">
+          Whisper Model
+        </label>
+        <input
+          type="text"
+          value={settings.whisperModel}
+          onChange={(e) => settings.setSettings({ whisperModel: e.target.value })}
+          className="w-full px-3 py-2 bg-white dark:bg-gray-800 border border-gray-300 dark:border-gray-600 rounded-lg text-sm font-mono focus:ring-2 focus:ring-blue-500 focus:border-transparent"
+          placeholder="base.en"
+        />
+        {modelStatus && (
+          <p className={cn('mt-1 text-xs', modelStatus.ok ? 'text-gray-500 dark:text-gray-400' : 'text-red-500')}>
+            {modelStatus.message}
+          </p>
+        )}
+      </div>
+      
+      <div>
+        <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-2">
+          Spoken Language
+        </label>
+        <select
+          value={settings.whisperLanguage}
+          onChange={(e) => settings.setSettings({ whisperLanguage: e.target.value })}
+          className="w-full px-3 py-2 bg-white dark:bg-gray-800 border border-gray-300 dark:border-gray-600 rounded-lg text-sm focus:ring-2 focus:ring-blue-500 focus:border-transparent"
+        >
+          <option value="auto">Detect automatically</option>
+          {Object.entries(LANGUAGES).map(([code, name]) => (
+            <option key={code} value={code}>{name}</option>
+          ))}
+        </select>
+        <p className="mt-1 text-xs text-gray-500 dark:text-gray-400">
+          English-only models (".en") only understand English.
+        </p>
+      </div>
+    </>
+  );
+}
+
+// Languages that can be detected and given their own voice
+const LANGUAGES: Record<string, string> = {
+  en: 'English',
+  de: 'German',
+  es: 'Spanish',
+  fr: 'French',
+  it: 'Italian',
+  pt: 'Portuguese',
+  nl: 'Dutch',
+  pl: 'Polish',
+  ru: 'Russian',
+  hi: 'Hindi',
+  ja: 'Japanese',
+  zh: 'Chinese',
+};
+
+// Per-language voices (sentences are routed by detected language)
+function LanguageSettings({ settings }: { settings: SettingsStore }) {
+  const entries = Object.entries(settings.languageVoices);
+  const unused = Object.keys(LANGUAGES).filter(
+    (code) => code !== settings.ttsLanguage && !(code in settings.languageVoices)
+  );
+  
+  const setVoice = (code: string, voice: LanguageVoice) => {
+    settings.setSettings({ languageVoices: { ...settings.languageVoices, [code]: voice } });
+  };
+  
+  const removeVoice = (code: string) => {
+    const { [code]: _, ...rest } = settings.languageVoices;
+    settings.setSettings({ languageVoices: rest });
+  };
+  
+  return (
+    <div>
+      <h2 className="text-lg font-medium text-gray-900 dark:text-gray-100 mb-4">
+        Languages
+      </h2>
+      
+      <div className="space-y-3">
+        <div>
+          <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-2">
+            Main voice language
+          </label>
+          <select
+            value={settings.ttsLanguage}
+            onChange={(e) => settings.setSettings({ ttsLanguage: e.target.value })}
+            className="w-full px-3 py-2 bg-white dark:bg-gray-800 border border-gray-300 dark:border-gray-600 rounded-lg text-sm focus:ring-2 focus:ring-blue-500 focus:border-transparent"
+          >
+            {Object.entries(LANGUAGES).map(([code, name]) => (
+              <option key={code} value={code}>{name}</option>
+            ))}
+          </select>
+          <p className="text-xs text-gray-500 mt-1">
+            Add a voice for another language and each sentence in that language is spoken with it.
+          </p>
+        </div>
+        
+        {entries.map(([code, voice]) => (
+          <div key={code} className="flex gap-2 items-center">
+            <span className="w-24 text-sm text-gray-700 dark:text-gray-300">
+              {LANGUAGES[code] || code}
+            </span>
+            <select
+              value={voice.engine}
+              onChange={(e) => setVoice(code, { ...voice, engine: e.target.value })}
+              className="px-2 py-2 bg-white dark:bg-gray-800 border border-gray-300 dark:border-gray-600 rounded-lg text-sm focus:ring-2 focus:ring-blue-500 focus:border-transparent"
+            >
+              <option value="">Same engine</option>
+              <option value="kokoro">Kokoro</option>
+              <option value="piper">Piper</option>
+              <option value="macos">macOS</option>
+              <option value="espeak">espeak-ng</option>
+              <option value="command">Custom command</option>
+              <option value="openai">OpenAI-compatible</option>
+            </select>
+            <input
+              type="text"
+              value={voice.voice}
+              onChange={(e) => setVoice(code, { ...voice, voice: e.target.value })}
+              className="flex-1 px-3 py-2 bg-white dark:bg-gray-800 border border-gray-300 dark:border-gray-600 rounded-lg text-sm focus:ring-2 focus:ring-blue-500 focus:border-transparent"
+              placeholder={code === 'es' ? 'ef_dora' : code === 'de' ? 'de_DE-thorsten-high' : 'voice'}
+            />
+            <button
+              onClick={() => removeVoice(code)}
+              className="p-2 text-gray-400 hover:text-red-500 transition-colors"
+              title="Remove"
+            >
+              <X className="w-4 h-4" />
+            </button>
+          </div>
+        ))}
+        
+        {unused.length > 0 && (
+          <select
+            value=""
+            onChange={(e) => e.target.value && setVoice(e.target.value, { engine: '', voice: '' })}
+            className="w-full px-3 py-2 bg-white dark:bg-gray-800 border border-gray-300 dark:border-gray-600 rounded-lg text-sm text-gray-500 focus:ring-2 focus:ring-blue-500 focus:border-transparent"
+          >
+            <option value="">Add a language...</option>
+            {unused.map((code) => (
+              <option key={code} value={code}>{LANGUAGES[code]}</option>
+            ))}
+          </select>
+        )}
+      </div>
+    </div>
+  );
+}
+
+const SPEECH_ROLES: Record<SpeechRole, string> = {
+  assistant: 'Replies',
+  tool: 'Tool summaries',
+  system: 'Notices',
+  error: 'Errors',
+};
+
+// Engine, voice and speed overrides for one agent or role
+function VoiceProfileRow({
+  label,
+  profile,
+  onChange,
+  onRemove,
+}: {
+  label: string;
+  profile: VoiceProfile;
+  onChange: (profile: VoiceProfile) => void;
+  onRemove?: () => void;
+}) {
+  return (
+    <div className="flex gap-2 items-center">
+      <span className="w-28 text-sm text-gray-700 dark:text-gray-300 truncate" title={label}>
+        {label}
+      </span>
+      <select
+        value={profile.engine}
+        onChange={(e) => onChange({ ...profile, engine: e.target.value })}
+        className="px-2 py-2 bg-white dark:bg-gray-800 border border-gray-300 dark:border-gray-600 rounded-lg text-sm focus:ring-2 focus:ring-blue-500 focus:border-transparent"
+      >
+        <option value="">Same engine</option>
+        <option value="kokoro">Kokoro</option>
+        <option value="piper">Piper</option>
+        <option value="edge">Edge</option>
+        <option value="macos">macOS</option>
+        <option value="espeak">espeak-ng</option>
+        <option value="command">Custom command</option>
+        <option value="openai">OpenAI-compatible</option>
+      </select>
+      <input
+        type="text"
+        value={profile.voice}
+        onChange={(e) => onChange({ ...profile, voice: e.target.value })}
+        className="flex-1 min-w-0 px-3 py-2 bg-white dark:bg-gray-800 border border-gray-300 dark:border-gray-600 rounded-lg text-sm focus:ring-2 focus:ring-blue-500 focus:border-transparent"
+        placeholder="Same voice"
+      />
+      <input
+        type="number"
+        min="0.5"
+        max="2"
+        step="0.1"
+        value={profile.speed ?? ''}
+        onChange={(e) => onChange({ ...profile, speed: e.target.value ? parseFloat(e.target.value) : undefined })}
+        className="w-20 px-2 py-2 bg-white dark:bg-gray-800 border border-gray-300 dark:border-gray-600 rounded-lg text-sm focus:ring-2 focus:ring-blue-500 focus:border-transparent"
+        placeholder="Speed"
+      />
+      {onRemove && (
+        <button
+          onClick={onRemove}
+          className="p-2 text-gray-400 hover:text-red-500 transition-colors"
+          title="Remove"
+        >
+          <X className="w-4 h-4" />
+        </button>
+      )}
+    </div>
+  );
+}
+
+// Voices per OpenCode agent and per kind of message
+function VoiceProfileSettings({ settings }: { settings: SettingsStore }) {
+  const [newAgent, setNewAgent] = useState('');
+  const emptyProfile: VoiceProfile = { engine: '', voice: '' };
+  
+  const setAgentVoice = (agent: string, profile: VoiceProfile) => {
+    settings.setSettings({ agentVoices: { ...settings.agentVoices, [agent]: profile } });
+  };
+  
+  const removeAgentVoice = (agent: string) => {
+    const { [agent]: _, ...rest } = settings.agentVoices;
+    settings.setSettings({ agentVoices: rest });
+  };
+  
+  const addAgent = () => {
+    const agent = newAgent.trim();
+    if (!agent || agent in settings.agentVoices) return;
+    setAgentVoice(agent, emptyProfile);
+    setNewAgent('');
+  };
+  
+  return (
+    <div>
+      <h2 className="text-lg font-medium text-gray-900 dark:text-gray-100 mb-4">
+        Voice Profiles
+      </h2>
+      
+      <div className="space-y-3">
+        <p className="text-xs text-gray-500">
+          Empty fields keep the main voice. A message's role is applied on top of its agent's voice.
+        </p>
+        
+        {(Object.keys(SPEECH_ROLES) as SpeechRole[]).map((role) => (
+          <VoiceProfileRow
+            key={role}
+            label={SPEECH_ROLES[role]}
+            profile={settings.roleVoices[role] ?? emptyProfile}
+            onChange={(profile) => settings.setSettings({ roleVoices: { ...settings.roleVoices, [role]: profile } })}
+          />
+        ))}
+        
+        {Object.entries(settings.agentVoices).map(([agent, profile]) => (
+          <VoiceProfileRow
+            key={agent}
+            label={`Agent: ${agent}`}
+            profile={profile}
+            onChange={(p) => setAgentVoice(agent, p)}
+            onRemove={() => removeAgentVoice(agent)}
+          />
+        ))}
+        
+        <div className="flex gap-2">
+          <input
+            type="text"
+            value={newAgent}
+            onChange={(e) => setNewAgent(e.target.value)}
+            onKeyDown={(e) => e.key === 'Enter' && addAgent()}
+            className="flex-1 px-3 py-2 bg-white dark:bg-gray-800 border border-gray-300 dark:border-gray-600 rounded-lg text-sm focus:ring-2 focus:ring-blue-500 focus:border-transparent"
+            placeholder={settings.agent || 'Agent name'}
+          />
+          <button
+            onClick={addAgent}
+            disabled={!newAgent.trim()}
+            className="px-3 py-2 bg-gray-100 dark:bg-gray-700 text-gray-700 dark:text-gray-300 rounded-lg text-sm hover:bg-gray-200 dark:hover:bg-gray-600 disabled:opacity-50 transition-colors"
+          >
+            Add agent
+          </button>
+        </div>
+      </div>
+    </div>
+  );
+}
+
+interface PronunciationPreview {
+  text: string;
+  spoken: string;
+  files: string[];
+  errors: string[];
+}
+
+// Pronunciation lexicon preview
+function PronunciationSettings({ settings }: { settings: SettingsStore }) {
+  const [word, setWord] = useState('');
+  const [preview, setPreview] = useState<PronunciationPreview | null>(null);
+  const [lexiconPath, setLexiconPath] = useState<string | null>(null);
+  const [isPreviewing, setIsPreviewing] = useState(false);
+  
+  useEffect(() => {
+    invoke<string | null>('get_lexicon_path').then(setLexiconPath).catch(() => {});
+  }, []);
+  
+  const handlePreview = async () => {
+    if (!word.trim()) return;
+    setIsPreviewing(true);
+    try {
+      const result = await invoke<PronunciationPreview>('preview_pronunciation', {
+        text: word,
+        engine: settings.ttsEngine,
+        voice: settings.ttsVoice,
+        speed: settings.ttsSpeed,
+      });
+      setPreview(result);
+    } catch (error) {
+      console.error('Pronunciation preview failed:', error);
+    }
+    setIsPreviewing(false);
+  };
+  
+  return (
+    <div>
+      <h2 className="text-lg font-medium text-gray-900 dark:text-gray-100 mb-4">
+        Pronunciation
+      </h2>
+      
+      <div className="space-y-3">
+        <p className="text-xs text-gray-500">
+          Add words to {lexiconPath || 'lexicon.toml in the config directory'}, or to
+          .opencode-talk/lexicon.toml in a project. Use a [words] table for plain words
+          and [[patterns]] entries with match and say for regexes.
+        </p>
+        
+        <div className="flex gap-2">
+          <input
+            type="text"
+            value={word}
+            onChange={(e) => setWord(e.target.value)}
+            onKeyDown={(e) => e.key === 'Enter' && handlePreview()}
+            className="flex-1 px-3 py-2 bg-white dark:bg-gray-800 border border-gray-300 dark:border-gray-600 rounded-lg text-sm focus:ring-2 focus:ring-blue-500 focus:border-transparent"
+            placeholder="kubectl"
+          />
+          <button
+            onClick={handlePreview}
+            disabled={isPreviewing || !word.trim()}
+            className="flex items-center gap-2 px-4 py-2 bg-blue-500 text-white rounded-lg text-sm font-medium hover:bg-blue-600 disabled:opacity-50 transition-colors"
+          >
+            {isPreviewing ? (
+              <RefreshCw className="w-4 h-4 animate-spin" />
+            ) : (
+              <Volume2 className="w-4 h-4" />
+            )}
+            Preview
+          </button>
+        </div>
+        
+        {preview && (
+          <div className="text-xs space-y-1">
+            <p className="text-gray-700 dark:text-gray-300">
+              Spoken as: <span className="font-mono">{preview.spoken || '(nothing)'}</span>
+            </p>
+            {preview.files.length === 0 && (
+              <p className="text-gray-500">No lexicon files found</p>
+            )}
+            {preview.errors.map((error) => (
+              <p key={error} className="flex items-start gap-1 text-red-500">
+                <AlertCircle className="w-3 h-3 mt-0.5 shrink-0" />
+                {error}
+              </p>
+            ))}
+          </div>
+        )}
+      </div>
     </div>
   );
 }
@@ -430,6 +1247,189 @@ function OpenCodeSettings({
   );
 }
 
+interface PolicyEvaluation {
+  verdict: { action: 'allow' | 'ask' | 'deny'; rule: string | null; downgraded: boolean };
+  assessment: { toolName: string; description: string; severity: 'low' | 'medium' | 'high' };
+  path: string | null;
+  file: string | null;
+  errors: string[];
+}
+
+// Try the permission policy on a made-up request
+function PermissionPolicySettings() {
+  const [tool, setTool] = useState('bash');
+  const [target, setTarget] = useState('');
+  const [evaluation, setEvaluation] = useState<PolicyEvaluation | null>(null);
+  
+  const isFileTool = tool === 'edit' || tool === 'write';
+  
+  const handleEvaluate = async () => {
+    try {
+      const result = await invoke<PolicyEvaluation>('evaluate_policy', {
+        tool,
+        command: isFileTool ? null : target,
+        path: isFileTool ? target : null,
+      });
+      setEvaluation(result);
+    } catch (error) {
+      console.error('Policy evaluation failed:', error);
+    }
+  };
+  
+  // Load the file's path and errors up front
+  useEffect(() => {
+    handleEvaluate();
+  }, []);
+  
+  const actionLabels = {
+    allow: 'Allowed without asking',
+    ask: 'Asked about',
+    deny: 'Refused',
+  };
+  
+  return (
+    <div>
+      <h2 className="text-lg font-medium text-gray-900 dark:text-gray-100 mb-4">
+        Permission Policy
+      </h2>
+      
+      <div className="space-y-3">
+        <p className="text-xs text-gray-500">
+          Add [[rules]] to {evaluation?.path || 'policy.toml in the config directory'} with an
+          action (allow, ask or deny) and any of tool, command (a regex), path (globs, {'{project}'} is
+          the project directory) and severity. The first matching rule decides; requests no rule
+          matches follow the settings above.
+        </p>
+        
+        <div className="flex gap-2">
+          <select
+            value={tool}
+            onChange={(e) => setTool(e.target.value)}
+            className="px-3 py-2 bg-white dark:bg-gray-800 border border-gray-300 dark:border-gray-600 rounded-lg text-sm focus:ring-2 focus:ring-blue-500 focus:border-transparent"
+          >
+            <option value="bash">bash</option>
+            <option value="edit">edit</option>
+            <option value="write">write</option>
+            <option value="webfetch">webfetch</option>
+          </select>
+          <input
+            type="text"
+            value={target}
+            onChange={(e) => setTarget(e.target.value)}
+            onKeyDown={(e) => e.key === 'Enter' && handleEvaluate()}
+            className="flex-1 px-3 py-2 bg-white dark:bg-gray-800 border border-gray-300 dark:border-gray-600 rounded-lg text-sm font-mono focus:ring-2 focus:ring-blue-500 focus:border-transparent"
+            placeholder={isFileTool ? 'src/main.rs' : 'cargo test'}
+          />
+          <button
+            onClick={handleEvaluate}
+            className="flex items-center gap-2 px-4 py-2 bg-blue-500 text-white rounded-lg text-sm font-medium hover:bg-blue-600 transition-colors"
+          >
+            <Shield className="w-4 h-4" />
+            Check
+          </button>
+        </div>
+        
+        {evaluation && (
+          <div className="text-xs space-y-1">
+            {target.trim() && (
+              <p className="text-gray-700 dark:text-gray-300">
+                {actionLabels[evaluation.verdict.action]}
+                {evaluation.verdict.rule ? ` by rule "${evaluation.verdict.rule}"` : ' by the settings above'}
+                {evaluation.verdict.downgraded && ' (would be allowed, but the policy file has errors)'}
+                {' '}— {evaluation.assessment.description} ({evaluation.assessment.severity})
+              </p>
+            )}
+            {!evaluation.file && (
+              <p className="text-gray-500">No policy file found</p>
+            )}
+            {evaluation.errors.map((error) => (
+              <p key={error} className="flex items-start gap-1 text-red-500">
+                <AlertCircle className="w-3 h-3 mt-0.5 shrink-0" />
+                {error}
+              </p>
+            ))}
+          </div>
+        )}
+      </div>
+    </div>
+  );
+}
+
+type AuditRecord = { atMs: number; sessionId: string | null } & (
+  | { event: 'transcription'; text: string; source: string; handled: 'prompt' | 'command' | 'answer' | 'unclear' }
+  | { event: 'tool_call'; callId: string; tool: string; title: string | null; input: unknown }
+  | { event: 'permission'; permissionId: string; tool: string; description: string; severity: string; action: string; rule: string | null }
+  | { event: 'prompt'; permissionId: string; text: string }
+  | { event: 'decision'; permissionId: string; approved: boolean; via: string; rule: string | null }
+);
+
+function describeAuditRecord(record: AuditRecord): string {
+  switch (record.event) {
+    case 'transcription':
+      return `Heard "${record.text}" (${record.source}, ${record.handled})`;
+    case 'tool_call':
+      return `Tool call: ${record.tool}${record.title ? ` ${record.title}` : ''}`;
+    case 'permission':
+      return `Permission: ${record.description} (${record.severity}, ${record.action}${record.rule ? ` by rule "${record.rule}"` : ''})`;
+    case 'prompt':
+      return `Asked: ${record.text}`;
+    case 'decision':
+      return `${record.approved ? 'Approved' : 'Denied'} via ${record.via}${record.rule ? ` (rule "${record.rule}")` : ''}`;
+  }
+}
+
+// The latest entries of the audit log
+function AuditLogSettings() {
+  const [records, setRecords] = useState<AuditRecord[]>([]);
+  const [logPath, setLogPath] = useState<string | null>(null);
+  
+  const loadRecords = () => {
+    invoke<AuditRecord[]>('query_audit_log', { query: { limit: 20 } })
+      .then(setRecords)
+      .catch((error) => console.error('Failed to read the audit log:', error));
+  };
+  
+  useEffect(() => {
+    invoke<string | null>('get_audit_log_path').then(setLogPath).catch(() => {});
+    loadRecords();
+  }, []);
+  
+  return (
+    <div>
+      <div className="flex items-center justify-between mb-4">
+        <h2 className="text-lg font-medium text-gray-900 dark:text-gray-100">
+          Audit Log
+        </h2>
+        <button
+          onClick={loadRecords}
+          className="p-1.5 rounded-lg text-gray-500 hover:bg-gray-100 dark:hover:bg-gray-700 transition-colors"
+        >
+          <RefreshCw className="w-4 h-4" />
+        </button>
+      </div>
+      
+      <div className="space-y-3">
+        <p className="text-xs text-gray-500">
+          Every transcription, tool call, permission request and decision is appended
+          to {logPath || 'audit.jsonl in the data directory'}, one JSON record per line.
+        </p>
+        
+        <div className="text-xs space-y-1 max-h-48 overflow-y-auto">
+          {records.length === 0 && (
+            <p className="text-gray-500">Nothing recorded yet</p>
+          )}
+          {records.map((record, i) => (
+            <p key={i} className="text-gray-700 dark:text-gray-300">
+              <span className="text-gray-400">{new Date(record.atMs).toLocaleTimeString()}</span>{' '}
+              {describeAuditRecord(record)}
+            </p>
+          ))}
+        </div>
+      </div>
+    </div>
+  );
+}
+
 // Behavior Settings
 function BehaviorSettings({ settings }: { settings: SettingsStore }) {
   return (
@@ -475,9 +1475,53 @@ function BehaviorSettings({ settings }: { settings: SettingsStore }) {
               Require confirmation for git operations
             </span>
           </label>
+
+          <p className="text-xs text-gray-500 dark:text-gray-400">
+            Answer "yes", "no" or "details" when asked, or use the hotkeys below.
+          </p>
+
+          <div>
+            <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-2">
+              Deny After: {settings.confirmationTimeoutSecs}s without an answer
+            </label>
+            <input
+              type="range"
+              min="5"
+              max="120"
+              step="5"
+              value={settings.confirmationTimeoutSecs}
+              onChange={(e) => settings.setSettings({ confirmationTimeoutSecs: parseInt(e.target.value) })}
+              className="w-full"
+            />
+          </div>
+
+          <div className="grid grid-cols-2 gap-4">
+            <div>
+              <HotkeyField
+                label="Approve Hotkey"
+                action="approve"
+                value={settings.approveHotkey}
+                placeholder="Option+Shift+Y"
+                onChange={(hotkey) => settings.setSettings({ approveHotkey: hotkey })}
+              />
+            </div>
+            <div>
+              <HotkeyField
+                label="Deny Hotkey"
+                action="deny"
+                value={settings.denyHotkey}
+                placeholder="Option+Shift+N"
+                onChange={(hotkey) => settings.setSettings({ denyHotkey: hotkey })}
+              />
+            </div>
+          </div>
         </div>
       </div>
       
+      <PermissionPolicySettings />
+      
+      <AuditLogSettings />
+      
       <div>
         <h2 className="text-lg font-medium text-gray-900 dark:text-gray-100 mb-4">
           Other Settings
@@ -1,6 +1,14 @@
+import { useEffect, useState } from 'react';
+import { listen } from '@tauri-apps/api/event';
 import { cn } from '../lib/utils';
 import type { VoiceState } from '../stores/conversation';
 
+// Input level reported by the backend while recording (mic.rs)
+interface MicLevel {
+  rms: number;
+  peak: number;
+}
+
 interface StatusIndicatorProps {
   state: VoiceState;
   size?: 'sm' | 'md' | 'lg';
@@ -102,3 +110,27 @@ export function StatusIndicatorWithLabel({ state }: { state: VoiceState }) {
     </div>
   );
 }
+
+// Live microphone level while the backend is recording
+export function MicLevelMeter() {
+  const [level, setLevel] = useState<MicLevel>({ rms: 0, peak: 0 });
+  
+  useEffect(() => {
+    const unlisten = listen<MicLevel>('mic-level', (event) => setLevel(event.payload));
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+  
+  // Speech RMS rarely exceeds ~0.3, so scale it up to fill the bar
+  const width = Math.min(100, Math.sqrt(level.rms) * 180);
+  
+  return (
+    <div className="w-16 h-1.5 rounded-full bg-gray-200 dark:bg-gray-700 overflow-hidden" title="Microphone level">
+      <div
+        className={cn('h-full rounded-full transition-[width] duration-75', level.peak >= 0.99 ? 'bg-red-500' : 'bg-green-500')}
+        style={{ width: `${width}%` }}
+      />
+    </div>
+  );
+}
@@ -3,8 +3,10 @@
  */
 
 import { useEffect, useCallback } from 'react';
-import { useSettingsStore } from '../stores/settings';
-import { useConversationStore } from '../stores/conversation';
+import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import { useSettingsStore, type Settings } from '../stores/settings';
+import { useConversationStore, type PendingConfirmation } from '../stores/conversation';
 import { 
   connect, 
   disconnect, 
@@ -13,6 +15,7 @@ import {
   startNewConversation,
   askForProject,
   stopSpeaking,
+  selectSession,
 } from '../lib/voice-bridge';
 
 // Check if TTS engine is ready
@@ -22,11 +25,9 @@ async function checkTtsReadiness(
 ): Promise<void> {
   try {
     if (engine === 'kokoro') {
-      // Ping Kokoro server health endpoint
-      const response = await fetch('http://127.0.0.1:7892/health', {
-        signal: AbortSignal.timeout(2000),
-      });
-      if (response.ok) {
+      // Ping Kokoro server health endpoint (via the backend's shared HTTP client)
+      const healthy = await invoke<boolean>('check_kokoro_health');
+      if (healthy) {
         setTtsReady(true, 'Kokoro');
         return;
       }
@@ -85,6 +86,58 @@ export function useOpenCode() {
     };
   }, [settings.serverUrl]);
   
+  // Keep the backend's copy of the settings in sync
+  useEffect(() => {
+    const { setSettings, resetSettings, ...values } = settings;
+    invoke('update_settings', { settings: values }).catch((e) => {
+      console.error('Failed to sync settings to backend:', e);
+    });
+  }, [settings]);
+  
+  // Apply what was picked from the tray menu or changed by a voice command
+  useEffect(() => {
+    const unlisteners: Promise<UnlistenFn>[] = [
+      listen<Partial<Settings>>('settings-changed', (event) => {
+        useSettingsStore.getState().setSettings(event.payload);
+      }),
+      listen<{ id: string | null }>('session-selected', (event) => {
+        selectSession(event.payload.id);
+      }),
+    ];
+    
+    return () => {
+      unlisteners.forEach((unlisten) => unlisten.then((fn) => fn()));
+    };
+  }, []);
+  
+  // Show the permission requests the backend is asking about by voice
+  useEffect(() => {
+    const unlisteners: Promise<UnlistenFn>[] = [
+      listen<Omit<PendingConfirmation, 'timestamp'>>('confirmation-requested', (event) => {
+        useConversationStore.getState().setPendingConfirmation({ ...event.payload, timestamp: Date.now() });
+      }),
+      listen<{ id: string; approved?: boolean; via?: string }>('confirmation-resolved', (event) => {
+        const store = useConversationStore.getState();
+        if (store.pendingConfirmation?.id !== event.payload.id) return;
+        store.setPendingConfirmation(null);
+        if (event.payload.via === 'timeout') {
+          store.addToast({ type: 'warning', message: 'Permission request denied: no answer in time' });
+        }
+      }),
+    ];
+    
+    return () => {
+      unlisteners.forEach((unlisten) => unlisten.then((fn) => fn()));
+    };
+  }, []);
+  
+  // Let the backend pick up the project's pronunciation lexicon
+  useEffect(() => {
+    invoke('set_project_path', { path: conversation.projectPath }).catch((e) => {
+      console.error('Failed to sync project path to backend:', e);
+    });
+  }, [conversation.projectPath]);
+  
   // Re-check TTS readiness when TTS engine changes
   useEffect(() => {
     if (conversation.isConnected) {
@@ -1,18 +1,28 @@
 /**
  * Hook for voice input (STT) management
  * 
- * For now, this uses a simple text input simulation.
- * Voice recording via CapsLock will show the listening state
- * but actual transcription requires SuperWhisper or similar.
+ * The global hotkeys are registered and handled by the backend (hotkeys.rs),
+ * which keeps receiving key-up events while the window is hidden; this hook
+ * follows the state changes it emits. With the backend STT engines (local
+ * whisper, OpenAI-compatible, custom command) the backend records while the
+ * push-to-talk hotkey is held and transcribes on release. Other engines
+ * (SuperWhisper, dictation) capture audio themselves and deliver the text
+ * through the same `transcription` event.
+ * 
+ * The continuous mode hotkey toggles hands-free listening, where the backend's
+ * VAD cuts the microphone stream into utterances and transcribes each one.
+ * With the wake word enabled, the backend listens for it in the background and
+ * transcribes the command that follows without any hotkey.
  */
 
 import { useEffect, useCallback, useRef } from 'react';
 import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 import { invoke } from '@tauri-apps/api/core';
-import { register, unregister } from '@tauri-apps/plugin-global-shortcut';
 import { useSettingsStore } from '../stores/settings';
 import { useConversationStore } from '../stores/conversation';
 import { processVoiceInput, stopSpeaking } from '../lib/voice-bridge';
+import { isBackendStt } from '../lib/stt';
+import type { VoiceState } from '../stores/conversation';
 
 // Event types from Macrowhisper/SuperWhisper
 interface TranscriptionEvent {
@@ -20,6 +30,14 @@ interface TranscriptionEvent {
   confidence?: number;
 }
 
+// Registration result for one hotkey (hotkeys.rs)
+interface HotkeyStatus {
+  action: 'pushToTalk' | 'continuousMode' | 'interrupt' | 'approve' | 'deny';
+  hotkey: string;
+  registered: boolean;
+  error: string | null;
+}
+
 export function useVoiceInput() {
   const settings = useSettingsStore();
   const conversation = useConversationStore();
@@ -28,9 +46,10 @@ export function useVoiceInput() {
   const setPendingConfirmation = useConversationStore((state) => state.setPendingConfirmation);
   const setHotkeyReady = useConversationStore((state) => state.setHotkeyReady);
   const setHotkeyError = useConversationStore((state) => state.setHotkeyError);
+  const addToast = useConversationStore((state) => state.addToast);
   const unlistenRef = useRef<UnlistenFn | null>(null);
-  const shortcutUnlistenRef = useRef<(() => Promise<void>) | null>(null);
   const isListeningRef = useRef(false);
+  const isContinuousRef = useRef(false);
   
   // Handle incoming transcription
   const handleTranscription = useCallback(async (text: string) => {
@@ -41,9 +60,10 @@ export function useVoiceInput() {
     // Process the voice input
     await processVoiceInput(text);
     
-    // Return to idle
-    setVoiceState('idle');
-    await invoke('set_voice_state', { voiceState: 'idle' });
+    // Return to idle, or keep listening in continuous mode
+    const nextState = isContinuousRef.current ? 'listening' : 'idle';
+    setVoiceState(nextState);
+    await invoke('set_voice_state', { voiceState: nextState });
   }, [setVoiceState]);
   
   // Listen for transcription events from SuperWhisper/Macrowhisper
@@ -73,98 +93,146 @@ export function useVoiceInput() {
     isListeningRef.current = true;
     
     setVoiceState('listening');
+    
+    // The backend records for its own STT engines; external apps capture audio themselves
+    if (isBackendStt(settings.sttEngine)) {
+      try {
+        await invoke('start_recording');
+      } catch (error) {
+        console.error('Failed to start recording:', error);
+        isListeningRef.current = false;
+        setVoiceState('idle');
+      }
+      return;
+    }
+    
     await invoke('set_voice_state', { voiceState: 'listening' });
-  }, [setVoiceState]);
+  }, [setVoiceState, settings.sttEngine]);
   
   // Stop listening
   const stopListening = useCallback(async () => {
     if (!isListeningRef.current) return;
     isListeningRef.current = false;
     
+    // The transcript arrives as a `transcription` event like SuperWhisper's
+    if (isBackendStt(settings.sttEngine)) {
+      setVoiceState('processing');
+      try {
+        const text = await invoke<string>('stop_recording');
+        if (!text) {
+          setVoiceState('idle');
+        }
+      } catch (error) {
+        console.error('Transcription failed:', error);
+        setVoiceState('idle');
+      }
+      return;
+    }
+    
     setVoiceState('idle');
     await invoke('set_voice_state', { voiceState: 'idle' });
-  }, [setVoiceState]);
+  }, [setVoiceState, settings.sttEngine]);
   
-  // Register global shortcuts
+  // Toggle hands-free listening (the backend reports the change as an event)
+  const toggleContinuous = useCallback(async () => {
+    if (isContinuousRef.current) {
+      await invoke('stop_continuous_listening');
+      return;
+    }
+    
+    if (!isBackendStt(settings.sttEngine)) {
+      console.warn('Continuous mode needs a backend STT engine');
+      return;
+    }
+    
+    try {
+      await invoke('start_continuous_listening');
+    } catch (error) {
+      console.error('Failed to start continuous listening:', error);
+    }
+  }, [settings.sttEngine]);
+  
+  // Wake word: the backend moves to listening when it hears the phrase and
+  // sends the command as a normal transcription
   useEffect(() => {
+    if (!settings.wakeWordEnabled) return;
+    
     let mounted = true;
+    const unlisteners: Promise<UnlistenFn>[] = [
+      listen('wake-word', () => {
+        if (mounted) setVoiceState('listening');
+      }),
+      listen('wake-word-timeout', () => {
+        if (mounted) setVoiceState('idle');
+      }),
+    ];
     
-    // Reset hotkey ready state when re-registering
-    setHotkeyReady(false);
+    // Restart so a changed phrase, sensitivity or microphone takes effect
+    invoke('stop_wake_word')
+      .then(() => invoke('start_wake_word'))
+      .catch((error) => console.error('Failed to start wake word:', error));
     
-    const registerShortcuts = async () => {
-      // Convert hotkey format for Tauri
-      const pttHotkey = settings.pushToTalkHotkey
-        .replace('Option', 'Alt')
-        .replace('Command', 'Meta');
-      
-      const interruptHotkey = settings.interruptHotkey;
-      
-      try {
-        console.log('Registering push-to-talk hotkey:', pttHotkey);
-        
-        // Register push-to-talk (press to start, release to stop)
-        await register(pttHotkey, async (event) => {
-          if (!mounted) return;
-          
-          if (event.state === 'Pressed') {
-            await startListening();
-          } else if (event.state === 'Released') {
-            await stopListening();
-          }
-        });
-        
-        // Register interrupt hotkey
-        if (interruptHotkey && interruptHotkey !== pttHotkey) {
-          console.log('Registering interrupt hotkey:', interruptHotkey);
-          
-          await register(interruptHotkey, async () => {
-            if (!mounted) return;
-            
-            // Stop any speaking
-            await stopSpeaking();
-            isListeningRef.current = false;
-            setVoiceState('idle');
-            setPendingConfirmation(null);
-            await invoke('set_voice_state', { voiceState: 'idle' });
-          });
-        }
-        
-        // Mark hotkey as ready (use display format for UI)
-        if (mounted) {
-          setHotkeyReady(true, settings.pushToTalkHotkey);
-          console.log('Hotkey registered successfully:', settings.pushToTalkHotkey);
-        }
-        
-        shortcutUnlistenRef.current = async () => {
-          try {
-            await unregister(pttHotkey);
-            if (interruptHotkey && interruptHotkey !== pttHotkey) {
-              await unregister(interruptHotkey);
-            }
-          } catch (e) {
-            console.error('Failed to unregister shortcuts:', e);
-          }
-        };
-      } catch (error) {
-        const errorMessage = error instanceof Error ? error.message : String(error);
-        console.error('Failed to register shortcuts:', errorMessage);
-        
-        if (mounted) {
-          setHotkeyReady(false);
-          setHotkeyError(`Hotkey registration failed: ${errorMessage}`);
-        }
+    return () => {
+      mounted = false;
+      unlisteners.forEach((unlisten) => unlisten.then((fn) => fn()));
+      invoke('stop_wake_word');
+    };
+  }, [settings.wakeWordEnabled, settings.wakeWord, settings.wakeWordSensitivity, settings.inputDevice, settings.whisperModel, setVoiceState]);
+  
+  // Leave continuous mode when the STT engine changes or the hook unmounts
+  useEffect(() => {
+    return () => {
+      if (isContinuousRef.current) {
+        invoke('stop_continuous_listening');
       }
     };
+  }, [settings.sttEngine]);
+  
+  // Follow the backend's voice state machine, which the hotkeys drive
+  useEffect(() => {
+    let mounted = true;
+    
+    const applyHotkeyStatus = (statuses: HotkeyStatus[]) => {
+      const pushToTalk = statuses.find((status) => status.action === 'pushToTalk');
+      const errors = statuses.filter((status) => status.error).map((status) => status.error);
+      
+      setHotkeyReady(!!pushToTalk?.registered, pushToTalk?.hotkey ?? null);
+      setHotkeyError(errors.length > 0 ? `Hotkey registration failed: ${errors.join('; ')}` : null);
+    };
+    
+    const unlisteners: Promise<UnlistenFn>[] = [
+      listen<VoiceState>('voice-state-changed', (event) => {
+        if (!mounted) return;
+        isListeningRef.current = event.payload === 'listening' && !isContinuousRef.current;
+        setVoiceState(event.payload);
+      }),
+      listen<boolean>('continuous-listening-changed', (event) => {
+        isContinuousRef.current = event.payload;
+      }),
+      listen('interrupted', async () => {
+        if (!mounted) return;
+        isListeningRef.current = false;
+        setPendingConfirmation(null);
+        await stopSpeaking();
+      }),
+      listen<string>('voice-error', (event) => {
+        if (mounted) addToast({ type: 'error', message: event.payload });
+      }),
+      listen<HotkeyStatus[]>('hotkeys-changed', (event) => {
+        if (mounted) applyHotkeyStatus(event.payload);
+      }),
+    ];
     
-    registerShortcuts();
+    // The hotkeys may have been registered before this hook mounted
+    invoke<HotkeyStatus[]>('get_hotkey_status').then((statuses) => {
+      if (mounted && statuses.length > 0) applyHotkeyStatus(statuses);
+    });
     
     return () => {
       mounted = false;
-      setHotkeyReady(false);
-      shortcutUnlistenRef.current?.();
+      unlisteners.forEach((unlisten) => unlisten.then((fn) => fn()));
     };
-  }, [settings.pushToTalkHotkey, settings.interruptHotkey, setVoiceState, setPendingConfirmation, setHotkeyReady, setHotkeyError, startListening, stopListening]);
+  }, [setVoiceState, setPendingConfirmation, setHotkeyReady, setHotkeyError, addToast]);
   
   // Simulate text input (for testing without voice)
   const simulateInput = useCallback(async (text: string) => {
@@ -175,6 +243,7 @@ export function useVoiceInput() {
     voiceState: conversation.voiceState,
     startListening,
     stopListening,
+    toggleContinuous,
     simulateInput,
   };
 }
@@ -76,6 +76,9 @@ export function useVoiceOutput() {
           'en_US-ryan-medium',
         ];
       }
+      case 'espeak': {
+        return ['en-us', 'en-gb', 'de', 'es', 'fr'];
+      }
       case 'openai': {
         return ['alloy', 'echo', 'fable', 'onyx', 'nova', 'shimmer'];
       }
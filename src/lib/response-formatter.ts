@@ -5,6 +5,9 @@
  * - Summarizes code blocks
  * - Describes diagrams and tables
  * - Extracts key information for spoken delivery
+ * 
+ * The output may contain the backend's prosody tags ({{pause}}, {{emphasis}},
+ * {{rate 0.8}}, {{spell}}); each TTS engine renders them in its own way.
  */
 
 interface FormattedResponse {
@@ -103,7 +106,7 @@ export function formatForVoice(text: string): FormattedResponse {
     const fileMention = filesChanged.length > 0 
       ? ` in ${filesChanged[0]}` 
       : '';
-    return `I've written ${summary}${fileMention}. `;
+    return `I've written ${summary}${fileMention}.{{pause}} `;
   });
   
   // Replace inline code with just the content (remove backticks)
@@ -112,8 +115,8 @@ export function formatForVoice(text: string): FormattedResponse {
   // Replace markdown links with just the text
   spokenText = spokenText.replace(/\[([^\]]+)\]\([^)]+\)/g, '$1');
   
-  // Replace headers with emphasis
-  spokenText = spokenText.replace(/^#{1,6}\s+(.+)$/gm, '$1: ');
+  // Replace headers with a short pause after them
+  spokenText = spokenText.replace(/^#{1,6}\s+(.+)$/gm, '$1.{{pause 300ms}} ');
   
   // Replace bullet points
   spokenText = spokenText.replace(/^[\s]*[-*]\s+/gm, '');
@@ -132,7 +135,7 @@ export function formatForVoice(text: string): FormattedResponse {
   
   // Add notes about diagrams/tables if present
   if (hasDiagrams && !spokenText.includes('diagram') && !spokenText.includes('table')) {
-    spokenText += ' I\'ve also included a visual diagram or table, take a look at the output.';
+    spokenText += ' {{pause}}I\'ve also included a visual diagram or table, take a look at the output.';
   }
   
   // Trim and clean up
@@ -151,7 +154,7 @@ export function formatForVoice(text: string): FormattedResponse {
       truncated += sentence + ' ';
     }
     
-    spokenText = truncated.trim() + ' Would you like me to continue?';
+    spokenText = truncated.trim() + ' {{pause}}Would you like me to continue?';
   }
   
   return {
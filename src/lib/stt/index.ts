@@ -4,9 +4,19 @@
  * Provides a unified interface for voice input from:
  * - SuperWhisper (via Macrowhisper)
  * - macOS Dictation (fallback)
+ * - The backend's own recorder (mic.rs), transcribed by local whisper.cpp, an
+ *   OpenAI-compatible server or a custom command (stt.rs)
  */
 
-export type STTEngine = 'superwhisper' | 'macos';
+export type STTEngine = 'superwhisper' | 'macos' | 'whisper' | 'openai' | 'command';
+
+/**
+ * Whether the backend records the microphone for this engine (push-to-talk
+ * calls start_recording/stop_recording) rather than an external app
+ */
+export function isBackendStt(engine: STTEngine): boolean {
+  return engine === 'whisper' || engine === 'openai' || engine === 'command';
+}
 
 export interface STTConfig {
   engine: STTEngine;
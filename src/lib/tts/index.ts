@@ -6,12 +6,14 @@
  * - Kokoro (highest quality, local)
  * - Piper (fast, local)
  * - macOS say (instant, basic)
- * - OpenAI TTS (cloud, high quality)
+ * - espeak-ng (robotic, zero-setup on Linux)
+ * - Custom command (any local TTS binary)
+ * - OpenAI-compatible speech servers (OpenAI, Kokoro-FastAPI, LocalAI, ...)
  */
 
 import { invoke } from '@tauri-apps/api/core';
 
-export type TTSEngine = 'edge' | 'kokoro' | 'piper' | 'macos' | 'openai';
+export type TTSEngine = 'edge' | 'kokoro' | 'piper' | 'macos' | 'espeak' | 'command' | 'openai';
 
 export interface TTSConfig {
   engine: TTSEngine;
@@ -62,6 +64,8 @@ export function getVoicesForEngine(engine: TTSEngine): TTSVoice[] {
       return getKokoroVoices();
     case 'piper':
       return getPiperVoices();
+    case 'espeak':
+      return getEspeakVoices();
     case 'openai':
       return getOpenAIVoices();
     default:
@@ -143,6 +147,16 @@ function getPiperVoices(): TTSVoice[] {
   ];
 }
 
+function getEspeakVoices(): TTSVoice[] {
+  return [
+    { id: 'en-us', name: 'English (US)', language: 'en-US', gender: 'neutral' },
+    { id: 'en-gb', name: 'English (UK)', language: 'en-GB', gender: 'neutral' },
+    { id: 'de', name: 'German', language: 'de', gender: 'neutral' },
+    { id: 'es', name: 'Spanish', language: 'es', gender: 'neutral' },
+    { id: 'fr', name: 'French', language: 'fr', gender: 'neutral' },
+  ];
+}
+
 function getOpenAIVoices(): TTSVoice[] {
   return [
     { id: 'alloy', name: 'Alloy', language: 'en', gender: 'neutral' },
@@ -167,6 +181,8 @@ export function getDefaultVoice(engine: TTSEngine): string {
       return 'af_heart';
     case 'piper':
       return 'en_US-amy-medium';
+    case 'espeak':
+      return 'en-us';
     case 'openai':
       return 'nova';
     default:
@@ -196,6 +212,12 @@ export async function isEngineAvailable(engine: TTSEngine): Promise<boolean> {
       }
     case 'piper':
       return await invoke('is_command_available', { command: 'piper' });
+    case 'espeak':
+      // The backend falls back to plain espeak when espeak-ng is missing
+      return (
+        (await invoke<boolean>('is_command_available', { command: 'espeak-ng' })) ||
+        (await invoke<boolean>('is_command_available', { command: 'espeak' }))
+      );
     case 'openai':
       // OpenAI is always "available" but requires API key
       return true;
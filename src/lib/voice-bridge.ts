@@ -6,15 +6,13 @@
  * - Managing sessions
  * - Processing voice input and sending to OpenCode
  * - Receiving responses and formatting for TTS
- * - Handling confirmations for dangerous actions
+ * - Answering the permission requests the backend asks about
  */
 
 import { invoke } from '@tauri-apps/api/core';
 import { formatForVoice } from './response-formatter';
-import { requiresConfirmation, describeAction } from './confirmation';
 import { useConversationStore } from '../stores/conversation';
-import { useSettingsStore } from '../stores/settings';
-import { createSentenceBuffer, type SentenceBuffer } from './sentence-buffer';
+import { useSettingsStore, type SpeechRole } from '../stores/settings';
 
 // Voice mode system prompt addition
 const VOICE_MODE_PROMPT = `
@@ -90,7 +88,9 @@ let isStreamingResponse = false;
 // Current request ID for handling concurrent requests
 let currentRequestId: string | null = null;
 // Active sentence buffer for cleanup
-let activeSentenceBuffer: SentenceBuffer | null = null;
+let activeSentenceBuffer: { clear: () => void } | null = null;
+// When the current message was sent, for timing the answer in the history
+let responseStartedAt = 0;
 
 /**
  * Check if currently streaming a response
@@ -178,6 +178,8 @@ export async function getOrCreateSession(): Promise<Session | null> {
       });
       if (response.ok) {
         const session: Session = await response.json();
+        // OpenCode retitles sessions as they go, so refresh the tray tooltip
+        await invoke('set_session_id', { sessionId: session.id, title: session.title ?? null });
         return session;
       }
       // 404 means session doesn't exist anymore, create new one
@@ -203,6 +205,7 @@ export async function getOrCreateSession(): Promise<Session | null> {
       const session: Session = await response.json();
       store.setSessionId(session.id);
       currentSessionId = session.id;
+      await invoke('set_session_id', { sessionId: session.id, title: session.title ?? null });
       return session;
     }
     
@@ -222,8 +225,22 @@ export async function startNewConversation(): Promise<void> {
   store.clearMessages();
   store.setSessionId(null);
   currentSessionId = null;
+  await invoke('set_session_id', { sessionId: null, title: null });
   
-  await speak("Starting a new conversation.");
+  await speak("Starting a new conversation.", 'system');
+}
+
+/**
+ * Continue an existing session (picked from the tray's recent sessions), or
+ * start afresh when the backend cleared it ("new session" said aloud)
+ */
+export function selectSession(sessionId: string | null): void {
+  const store = useConversationStore.getState();
+  if (sessionId !== null && store.sessionId === sessionId) return;
+  
+  store.clearMessages();
+  store.setSessionId(sessionId);
+  currentSessionId = sessionId;
 }
 
 /**
@@ -247,7 +264,7 @@ export async function sendMessage(text: string): Promise<string | null> {
   // Get or create session
   const session = await getOrCreateSession();
   if (!session) {
-    await speak("I couldn't create a session. Please check the OpenCode server.");
+    await speak("I couldn't create a session. Please check the OpenCode server.", 'error');
     return null;
   }
   
@@ -257,10 +274,11 @@ export async function sendMessage(text: string): Promise<string | null> {
   
   // Add user message to store
   store.addMessage({ role: 'user', content: text });
+  responseStartedAt = Date.now();
   
   try {
-    // Use streaming for Piper (fast) or Kokoro TTS
-    const useStreaming = settings.ttsEngine === 'piper' || settings.ttsEngine === 'kokoro';
+    // Use streaming for engines the backend generation queue supports
+    const useStreaming = settings.ttsEngine === 'piper' || settings.ttsEngine === 'kokoro' || settings.ttsEngine === 'espeak' || settings.ttsEngine === 'command' || settings.ttsEngine === 'openai';
     
     if (useStreaming) {
       return await sendMessageStreaming(session.id, text, settings);
@@ -271,7 +289,7 @@ export async function sendMessage(text: string): Promise<string | null> {
   } catch (error) {
     const message = error instanceof Error ? error.message : 'Unknown error';
     console.error('Error sending message:', message);
-    await speak("Sorry, I encountered an error. Please try again.");
+    await speak("Sorry, I encountered an error. Please try again.", 'error');
     return null;
   } finally {
     // Reset state
@@ -303,32 +321,40 @@ async function sendMessageStreaming(
   let fullResponse = '';
   let finalized = false;
   
-  // Create sentence buffer that queues TTS for each sentence
-  // Note: We don't use the onTextUpdate callback here because the buffer's internal
-  // state can get out of sync when sentences are extracted. Instead, we update the UI
-  // directly with fullResponse after each delta.
-  const sentenceBuffer = createSentenceBuffer(
-    (sentence) => {
+  // Sentence segmentation happens in the backend (sentence_buffer.rs): we forward
+  // raw deltas and it queues each complete sentence for TTS.
+  // Note: We update the UI directly with fullResponse after each delta.
+  const ttsOptions = {
+    voice: settings.ttsVoice,
+    speed: settings.ttsSpeed,
+    engine: settings.ttsEngine,
+    role: 'assistant' as SpeechRole,
+  };
+  const sentenceBuffer = {
+    push(delta: string) {
       // Check if this request is still active
       if (currentRequestId !== requestId) {
         return;
       }
-      // Fire-and-forget: queue TTS without waiting
-      invoke('speak_sentence', {
-        text: sentence,
-        voice: settings.ttsVoice,
-        speed: settings.ttsSpeed,
-        engine: settings.ttsEngine,
-      }).catch((e) => {
+      // Fire-and-forget: the backend queues TTS without us waiting
+      invoke('push_delta', { delta, ...ttsOptions }).catch((e) => {
         console.error('[TTS] Error queuing sentence:', e);
       });
     },
-    undefined, // Don't use onTextUpdate - we'll update UI directly with fullResponse
-    (error, sentence) => {
-      // Error callback for sentence buffer
-      console.error('[SentenceBuffer] Error processing sentence:', error, sentence);
-    }
-  );
+    flush() {
+      if (currentRequestId !== requestId) {
+        return;
+      }
+      invoke('flush_sentences', ttsOptions).catch((e) => {
+        console.error('[TTS] Error queuing sentence:', e);
+      });
+    },
+    clear() {
+      invoke('clear_sentences').catch((e) => {
+        console.error('[TTS] Error clearing sentence buffer:', e);
+      });
+    },
+  };
   
   // Track the sentence buffer for cleanup
   activeSentenceBuffer = sentenceBuffer;
@@ -368,6 +394,9 @@ async function sendMessageStreaming(
           filesChanged: formatted.filesChanged,
           isIncomplete: options.isIncomplete,
         });
+        // Streaming spoke the whole answer sentence by sentence, not the
+        // shortened spoken text, so replay reads the full text
+        recordResponse(streamingMessageId, fullResponse, undefined, options.isIncomplete);
         // Keep the message
         store.stopStreaming({ keepMessage: true });
       } else {
@@ -491,32 +520,6 @@ async function sendMessageSync(
   for (const part of data.parts) {
     if (part.type === 'text' && part.text) {
       fullResponse += part.text;
-    } else if (part.type === 'tool-invocation' && part.toolInvocation) {
-      // Check if this requires confirmation
-      const action = requiresConfirmation(
-        part.toolInvocation.toolName,
-        part.toolInvocation.args,
-        {
-          confirmFileWrites: settings.confirmFileWrites,
-          confirmShellCommands: settings.confirmShellCommands,
-          confirmGitOperations: settings.confirmGitOperations,
-        }
-      );
-      
-      if (action && action.requiresConfirmation) {
-        // Store pending confirmation
-        store.setPendingConfirmation({
-          id: crypto.randomUUID(),
-          toolName: action.toolName,
-          toolArgs: part.toolInvocation.args,
-          description: action.description,
-          timestamp: Date.now(),
-        });
-        
-        // Ask for confirmation
-        await speak(describeAction(action));
-        return null;
-      }
     }
   }
   
@@ -530,6 +533,8 @@ async function sendMessageSync(
     spokenContent: formatted.spokenText,
     filesChanged: formatted.filesChanged,
   });
+  const messages = useConversationStore.getState().messages;
+  recordResponse(messages[messages.length - 1].id, fullResponse, formatted.spokenText);
   
   // Speak the response
   await speak(formatted.spokenText);
@@ -538,32 +543,80 @@ async function sendMessageSync(
 }
 
 /**
- * Handle confirmation response
+ * Record something in the backend's conversation history
+ */
+function recordHistory(entry: {
+  kind: 'response';
+  text: string;
+  spoken?: string;
+  durationMs?: number;
+  detail?: Record<string, unknown>;
+}): Promise<number | null> {
+  return invoke<number | null>('record_history_entry', { entry }).catch((e) => {
+    console.error('Failed to record history:', e);
+    return null;
+  });
+}
+
+/**
+ * Record an answer, linking its message to the history entry so it can be replayed
+ */
+function recordResponse(messageId: string, text: string, spoken?: string, isIncomplete?: boolean): void {
+  recordHistory({
+    kind: 'response',
+    text,
+    spoken,
+    durationMs: Date.now() - responseStartedAt,
+    detail: isIncomplete ? { incomplete: true } : undefined,
+  }).then((historyId) => {
+    if (historyId !== null) {
+      useConversationStore.getState().updateMessage(messageId, { historyId });
+    }
+  });
+}
+
+/**
+ * Speak a past answer again through the current voice (from the backend's
+ * replay cache when it was played with it before)
+ */
+export async function replayMessage(historyId: number): Promise<void> {
+  try {
+    await invoke('replay_message', { messageId: historyId });
+  } catch (e) {
+    console.error('Failed to replay message:', e);
+  }
+}
+
+/**
+ * Answer the pending permission request ("yes", "no" or "details"); the
+ * backend replies to OpenCode and says what happens next
+ */
+export async function answerConfirmation(answer: string): Promise<void> {
+  try {
+    const understood = await invoke<boolean>('answer_confirmation', { answer });
+    if (!understood) {
+      console.warn('Not an answer to the permission request:', answer);
+    }
+  } catch (e) {
+    console.error('Failed to answer confirmation:', e);
+  }
+}
+
+/**
+ * Handle confirmation response from the floating panel
  */
 export async function handleConfirmation(confirmed: boolean): Promise<void> {
-  const store = useConversationStore.getState();
-  const confirmation = store.pendingConfirmation;
-  
-  if (!confirmation) {
+  if (!useConversationStore.getState().pendingConfirmation) {
     return;
   }
-  
-  // Clear pending confirmation
-  store.setPendingConfirmation(null);
-  
-  if (confirmed) {
-    await speak("Okay, proceeding.");
-    // TODO: Actually execute the pending action
-    // This requires integration with OpenCode's permission system
-  } else {
-    await speak("Okay, I won't do that.");
-  }
+  await answerConfirmation(confirmed ? 'yes' : 'no');
 }
 
 /**
  * Speak text using TTS
+ * The role selects the voice profile (see Settings > Voice Profiles).
  */
-export async function speak(text: string): Promise<void> {
+export async function speak(text: string, role: SpeechRole = 'assistant'): Promise<void> {
   const settings = useSettingsStore.getState();
   const store = useConversationStore.getState();
   
@@ -575,6 +628,7 @@ export async function speak(text: string): Promise<void> {
       engine: settings.ttsEngine,
       voice: settings.ttsVoice,
       speed: settings.ttsSpeed,
+      role,
     });
   } catch (error) {
     console.error('TTS error:', error);
@@ -713,11 +767,11 @@ export async function selectProject(projectName: string): Promise<boolean> {
   
   if (match) {
     useConversationStore.getState().setProjectPath(match.path);
-    await speak(`Switching to ${match.name}.`);
+    await speak(`Switching to ${match.name}.`, 'system');
     return true;
   }
   
-  await speak(`I couldn't find a project called ${projectName}. Please try again.`);
+  await speak(`I couldn't find a project called ${projectName}. Please try again.`, 'error');
   return false;
 }
 
@@ -727,18 +781,10 @@ export async function selectProject(projectName: string): Promise<boolean> {
 export async function processVoiceInput(text: string): Promise<void> {
   const store = useConversationStore.getState();
   
-  // Check for pending confirmation
+  // Typed text answers a pending permission request (spoken answers are
+  // caught by the backend before they get here)
   if (store.pendingConfirmation) {
-    const { parseConfirmationResponse } = await import('./confirmation');
-    const response = parseConfirmationResponse(text);
-    
-    if (response === 'yes') {
-      await handleConfirmation(true);
-    } else if (response === 'no') {
-      await handleConfirmation(false);
-    } else {
-      await speak("Sorry, I didn't understand. Please say yes or no.");
-    }
+    await answerConfirmation(text);
     return;
   }
   
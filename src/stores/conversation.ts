@@ -13,13 +13,18 @@ export interface Message {
   filesChanged?: string[];
   // Mark if response may be incomplete due to error
   isIncomplete?: boolean;
+  // Backend history entry of an assistant answer, for replaying it
+  historyId?: number;
 }
 
+// A permission request OpenCode is waiting on (asked about by the backend, see permissions.rs)
 export interface PendingConfirmation {
   id: string;
   toolName: string;
-  toolArgs: Record<string, unknown>;
   description: string;
+  severity: 'low' | 'medium' | 'high';
+  details: string;
+  timeoutSecs: number;
   timestamp: number;
 }
 
@@ -53,7 +58,6 @@ interface ConversationStore {
   
   // Pending confirmation for dangerous actions
   pendingConfirmation: PendingConfirmation | null;
-  confirmationTimeoutId: ReturnType<typeof setTimeout> | null;
   
   // Connection status
   isConnected: boolean;
@@ -109,9 +113,6 @@ interface ConversationStore {
   reset: () => void;
 }
 
-// Confirmation timeout duration (30 seconds)
-const CONFIRMATION_TIMEOUT_MS = 30000;
-
 export const useConversationStore = create<ConversationStore>((set, get) => ({
   // Initial state
   sessionId: null,
@@ -124,7 +125,6 @@ export const useConversationStore = create<ConversationStore>((set, get) => ({
   streamingMessageId: null,
   streamingError: null,
   pendingConfirmation: null,
-  confirmationTimeoutId: null,
   isConnected: false,
   isConnecting: false,
   connectionError: null,
@@ -169,42 +169,8 @@ export const useConversationStore = create<ConversationStore>((set, get) => ({
     messages: state.messages.slice(-limit),
   })),
   
-  setPendingConfirmation: (confirmation) => {
-    const state = get();
-    
-    // Clear any existing timeout
-    if (state.confirmationTimeoutId) {
-      clearTimeout(state.confirmationTimeoutId);
-    }
-    
-    if (confirmation) {
-      // Set up auto-cancel timeout (30 seconds)
-      const timeoutId = setTimeout(() => {
-        const currentState = get();
-        if (currentState.pendingConfirmation?.id === confirmation.id) {
-          set({ 
-            pendingConfirmation: null, 
-            confirmationTimeoutId: null 
-          });
-          // Add toast notification
-          currentState.addToast({
-            type: 'warning',
-            message: 'Confirmation timed out after 30 seconds',
-          });
-        }
-      }, CONFIRMATION_TIMEOUT_MS);
-      
-      set({ 
-        pendingConfirmation: confirmation,
-        confirmationTimeoutId: timeoutId,
-      });
-    } else {
-      set({ 
-        pendingConfirmation: null,
-        confirmationTimeoutId: null,
-      });
-    }
-  },
+  // The backend times the confirmation out and tells us (confirmation-resolved)
+  setPendingConfirmation: (confirmation) => set({ pendingConfirmation: confirmation }),
   
   setConnectionStatus: (connected, error = null) => set({
     isConnected: connected,
@@ -348,10 +314,6 @@ export const useConversationStore = create<ConversationStore>((set, get) => ({
   },
   
   reset: () => {
-    const state = get();
-    if (state.confirmationTimeoutId) {
-      clearTimeout(state.confirmationTimeoutId);
-    }
     set({
       sessionId: null,
       projectPath: null,
@@ -362,7 +324,6 @@ export const useConversationStore = create<ConversationStore>((set, get) => ({
       streamingMessageId: null,
       streamingError: null,
       pendingConfirmation: null,
-      confirmationTimeoutId: null,
       isConnected: false,
       isConnecting: false,
       connectionError: null,
@@ -1,18 +1,54 @@
 import { create } from 'zustand';
 import { persist, createJSONStorage } from 'zustand/middleware';
 
+export interface LanguageVoice {
+  engine: string;  // Empty = use ttsEngine
+  voice: string;
+}
+
+// Kinds of spoken message, each of which can have its own voice
+export type SpeechRole = 'assistant' | 'tool' | 'system' | 'error';
+
+export interface VoiceProfile {
+  engine: string;  // Empty = inherit
+  voice: string;   // Empty = inherit (or the engine's default if engine is set)
+  speed?: number;
+}
+
 export interface Settings {
   // Voice Input
-  sttEngine: 'superwhisper' | 'macos';
+  sttEngine: 'superwhisper' | 'macos' | 'whisper' | 'openai' | 'command';
+  inputDevice: string;  // Microphone name, empty = system default
+  whisperModel: string;  // GGML model path, or a name like "base.en" in ~/.local/share/whisper-models
+  whisperLanguage: string;  // ISO 639-1, or "auto"
+  sttOpenaiBaseUrl: string;  // Any OpenAI-compatible /v1/audio/transcriptions server
+  sttOpenaiModel: string;
+  sttCommandTemplate: string;  // e.g. "mystt --lang {language} {input}", transcript on stdout
   pushToTalkHotkey: string;
   continuousModeHotkey: string;
+  vadSilenceMs: number;  // Silence that ends an utterance in continuous mode
+  vadMinSpeechMs: number;  // Shorter sounds (coughs, clicks) are ignored
+  echoSuppression: boolean;  // Ignore only the assistant's echo while it speaks, so you can talk over it
+  wakeWordEnabled: boolean;  // Listen for the wake word (needs a local whisper model)
+  wakeWord: string;
+  wakeWordSensitivity: number;  // 0 = exact match only, 1 = loose
   interruptHotkey: string;
   
   // Voice Output
-  ttsEngine: 'edge' | 'kokoro' | 'piper' | 'macos' | 'openai';
+  ttsEngine: 'edge' | 'kokoro' | 'piper' | 'macos' | 'espeak' | 'command' | 'openai';
   ttsVoice: string;
   ttsSpeed: number;
+  ttsVolume: number;  // Playback volume, 1.0 as synthesized (also changed by voice)
+  voiceMuted: boolean;  // Nothing is spoken (also toggled from the tray)
+  ttsLanguage: string;  // ISO 639-1 language of ttsVoice
+  languageVoices: Record<string, LanguageVoice>;  // Voices for other languages, by ISO 639-1 code
+  agentVoices: Record<string, VoiceProfile>;  // By OpenCode agent name
+  roleVoices: Partial<Record<SpeechRole, VoiceProfile>>;  // Applied over the agent's voice
   openaiApiKey?: string;
+  openaiBaseUrl: string;  // Any OpenAI-compatible /v1/audio/speech server
+  openaiModel: string;
+  ttsCommandTemplate: string;  // e.g. "mytts --voice {voice} --out {output} {text}"
+  ttsCommandFormat: 'wav' | 'mp3';
   
   // OpenCode
   serverUrl: string;
@@ -23,6 +59,9 @@ export interface Settings {
   confirmFileWrites: boolean;
   confirmShellCommands: boolean;
   confirmGitOperations: boolean;
+  confirmationTimeoutSecs: number;  // Unanswered permission requests are denied after this
+  approveHotkey: string;  // Answer a permission request without speaking
+  denyHotkey: string;
   showFloatingPanel: boolean;
   playSoundOnResponse: boolean;
   autoStartOnLogin: boolean;
@@ -40,14 +79,36 @@ export interface SettingsStore extends Settings {
 const defaultSettings: Settings = {
   // Voice Input
   sttEngine: 'superwhisper',
+  inputDevice: '',
+  whisperModel: 'base.en',
+  whisperLanguage: 'en',
+  sttOpenaiBaseUrl: 'https://api.openai.com',
+  sttOpenaiModel: 'whisper-1',
+  sttCommandTemplate: '',
   pushToTalkHotkey: 'Alt+Space',  // Option+Space on Mac
   continuousModeHotkey: 'Alt+Shift+Space',
+  vadSilenceMs: 800,
+  vadMinSpeechMs: 300,
+  echoSuppression: true,
+  wakeWordEnabled: false,
+  wakeWord: 'hey opencode',
+  wakeWordSensitivity: 0.5,
   interruptHotkey: 'Escape',
   
   // Voice Output - Kokoro with warm server for fast, high-quality streaming
   ttsEngine: 'kokoro',
   ttsVoice: 'af_heart',  // Natural female voice
   ttsSpeed: 1.2,
+  ttsVolume: 1.0,
+  voiceMuted: false,
+  ttsLanguage: 'en',
+  languageVoices: {},
+  agentVoices: {},
+  roleVoices: {},
+  openaiBaseUrl: 'https://api.openai.com',
+  openaiModel: 'tts-1',
+  ttsCommandTemplate: '',
+  ttsCommandFormat: 'wav',
   
   // OpenCode
   serverUrl: 'http://localhost:4096',
@@ -58,6 +119,9 @@ const defaultSettings: Settings = {
   confirmFileWrites: true,
   confirmShellCommands: true,
   confirmGitOperations: true,
+  confirmationTimeoutSecs: 30,
+  approveHotkey: 'Option+Shift+Y',
+  denyHotkey: 'Option+Shift+N',
   showFloatingPanel: true,
   playSoundOnResponse: true,
   autoStartOnLogin: false,